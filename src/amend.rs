@@ -0,0 +1,85 @@
+//! Detect that the hook runs for `git commit --amend`.
+//!
+//! git does not tell hooks whether the commit is amended (`ORIG_HEAD` is not updated by
+//! `--amend`), the command line of the `git commit` process running the hook is inspected instead.
+
+/// How many ancestor processes are inspected, the hook may run from a wrapper script
+#[cfg(target_os = "linux")]
+const MAX_ANCESTORS: usize = 3;
+
+/// Whether `args` is a `git commit --amend` command line
+fn is_amend_command<S: AsRef<str>>(args: &[S]) -> bool {
+    let mut args = args
+        .iter()
+        .map(AsRef::as_ref)
+        .take_while(|arg| *arg != "--");
+    args.any(|arg| arg == "commit") && args.any(|arg| arg == "--amend")
+}
+
+/// Parent process id of process `pid`
+#[cfg(target_os = "linux")]
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // `<pid> (<command>) <state> <ppid> ...`, the command may contain spaces
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Command line of process `pid`
+#[cfg(target_os = "linux")]
+fn command_line(pid: u32) -> Option<Vec<String>> {
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline")).ok()?;
+    Some(
+        cmdline
+            .split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+/// Whether the hook runs for `git commit --amend`, always `false` if the command line of the
+/// parent processes is not available
+#[cfg(target_os = "linux")]
+pub fn is_amending() -> bool {
+    let mut pid = std::os::unix::process::parent_id();
+    for _ in 0..MAX_ANCESTORS {
+        if command_line(pid).is_some_and(|args| is_amend_command(&args)) {
+            return true;
+        }
+        match parent_pid(pid) {
+            Some(parent) if parent > 1 => pid = parent,
+            _ => break,
+        }
+    }
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_amending() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_amend_command() {
+        assert!(is_amend_command(&["git", "commit", "--amend"]));
+        assert!(is_amend_command(&[
+            "git", "commit", "-q", "--amend", "-m", "msg"
+        ]));
+        assert!(!is_amend_command(&["git", "commit", "-m", "msg"]));
+        assert!(!is_amend_command(&["git", "commit", "--", "--amend"]));
+        assert!(!is_amend_command(&["git", "--amend"]));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_parent_pid() {
+        let pid = std::process::id();
+        assert_eq!(parent_pid(pid), Some(std::os::unix::process::parent_id()));
+        assert!(command_line(pid).is_some_and(|args| !args.is_empty()));
+    }
+}
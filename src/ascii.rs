@@ -0,0 +1,95 @@
+//! `--ascii` mode: the output only contains ASCII characters, for terminals and log collectors
+//! which cannot render Unicode.
+//!
+//! JSON output is left untouched, non-ASCII submodule names and paths being data.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ascii(ascii: bool) {
+    ASCII.store(ascii, Ordering::Relaxed);
+}
+
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// ASCII replacement of the symbols and box-drawing characters the output may contain
+fn replacement(c: char) -> Option<&'static str> {
+    match c {
+        '✓' | '✔' => Some("ok"),
+        '✗' | '✘' => Some("x"),
+        '→' => Some("->"),
+        '←' => Some("<-"),
+        '…' => Some("..."),
+        '•' => Some("-"),
+        '─' | '━' | '═' => Some("-"),
+        '│' | '┃' | '║' => Some("|"),
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' => Some("+"),
+        _ => None,
+    }
+}
+
+/// `text` with `*` bullet points replaced by `-`, known symbols replaced by their ASCII
+/// equivalent and any other non-ASCII character removed
+fn to_ascii(text: &str) -> String {
+    let mut ascii = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_start_matches(' ');
+        let indent = &line[..line.len() - content.len()];
+        ascii.push_str(indent);
+        let content = match content.strip_prefix("* ") {
+            Some(item) => {
+                ascii.push_str("- ");
+                item
+            }
+            None => content,
+        };
+        for c in content.chars() {
+            if c.is_ascii() {
+                ascii.push(c);
+            } else if let Some(replacement) = replacement(c) {
+                ascii.push_str(replacement);
+            }
+        }
+    }
+    ascii
+}
+
+/// `text` as printed: converted by [`to_ascii`] in `--ascii` mode, unchanged otherwise
+pub fn filter(text: &str) -> Cow<'_, str> {
+    if is_ascii() {
+        Cow::Owned(to_ascii(text))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Theme of the interactive prompts, whose colorful variant draws Unicode symbols
+pub fn theme() -> Box<dyn Theme> {
+    if is_ascii() {
+        Box::new(SimpleTheme)
+    } else {
+        Box::new(ColorfulTheme::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(
+            to_ascii("Issues:\n* sub → modified\n  * nested…\nnot * a bullet"),
+            "Issues:\n- sub -> modified\n  - nested...\nnot * a bullet"
+        );
+        assert_eq!(to_ascii("✓ git ✗ hook"), "ok git x hook");
+        assert_eq!(to_ascii("┌─┐\n│é│"), "+-+\n||");
+        assert_eq!(to_ascii("plain\n"), "plain\n");
+    }
+}
@@ -0,0 +1,112 @@
+//! Audit log: one JSON line appended per hook run (`submodulehook.auditlog`), e.g. to know
+//! how often submodule warnings are overridden.
+
+use crate::ProgramOutcome;
+use crate::confirmation::ConfirmationOutcome;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use submodule_hook_lib::SubmodulesDiagnostic;
+
+/// Hook run to record in the audit log at `path`
+#[derive(Debug)]
+pub struct Audit {
+    pub path: PathBuf,
+    pub repository: PathBuf,
+    /// Submodules reported by the diagnostic, empty if no check was run
+    pub submodules: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    /// Seconds since the Unix epoch
+    timestamp: u64,
+    repository: &'a Path,
+    submodules: &'a [String],
+    outcome: &'static str,
+}
+
+impl Audit {
+    /// Remember the submodules reported by `diagnostics`
+    pub fn set_submodules(&mut self, diagnostics: &SubmodulesDiagnostic) {
+        let mut submodules: Vec<String> = diagnostics.all_affected().map(String::from).collect();
+        submodules.sort();
+        submodules.dedup();
+        self.submodules = submodules;
+    }
+
+    /// Append the record of a run that ended with `outcome`
+    pub fn record(&self, outcome: &ProgramOutcome) -> anyhow::Result<()> {
+        let record = AuditRecord {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            repository: &self.repository,
+            submodules: &self.submodules,
+            outcome: outcome_name(outcome),
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // a single write per record, concurrent runs do not interleave lines
+        let line = serde_json::to_string(&record)? + "\n";
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn outcome_name(outcome: &ProgramOutcome) -> &'static str {
+    match outcome {
+        ProgramOutcome::Success(ConfirmationOutcome::Confirmed) => "confirmed",
+        ProgramOutcome::Success(ConfirmationOutcome::Declined) => "declined",
+        ProgramOutcome::Success(ConfirmationOutcome::Cancelled) => "cancelled",
+        ProgramOutcome::CheckError => "check_error",
+        ProgramOutcome::NoConfirmationNeeded => "no_confirmation_needed",
+        ProgramOutcome::Checked(_) => "checked",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::entry;
+
+    #[test]
+    fn test_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut audit = Audit {
+            path: dir.path().join("logs/audit.log"),
+            repository: PathBuf::from("/repo"),
+            submodules: vec![],
+        };
+        audit.set_submodules(&SubmodulesDiagnostic {
+            modified_staged_submodules: vec![entry("vendor/zlib")],
+            detached_head_submodules: vec![entry("vendor/zlib")],
+            unpushed_submodules: vec!["lib".to_string()],
+            ..SubmodulesDiagnostic::default()
+        });
+        audit
+            .record(&ProgramOutcome::Success(ConfirmationOutcome::Confirmed))
+            .unwrap();
+        audit.record(&ProgramOutcome::CheckError).unwrap();
+
+        let content = std::fs::read_to_string(&audit.path).unwrap();
+        let records: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert!(records[0]["timestamp"].is_u64());
+        assert_eq!(records[0]["repository"], "/repo");
+        assert_eq!(
+            records[0]["submodules"],
+            serde_json::json!(["lib", "vendor/zlib"])
+        );
+        assert_eq!(records[0]["outcome"], "confirmed");
+        assert_eq!(records[1]["outcome"], "check_error");
+    }
+}
@@ -0,0 +1,35 @@
+//! Colored output, disabled by `--no-color`, `NO_COLOR` (<https://no-color.org>) or when the
+//! output is not a terminal.
+
+use std::ffi::OsString;
+
+/// Whether colors may be used given `--no-color` and the value of `NO_COLOR`, which disables
+/// them when set to a non-empty value
+fn colors_allowed(no_color: bool, no_color_env: Option<OsString>) -> bool {
+    !no_color && no_color_env.is_none_or(|value| value.is_empty())
+}
+
+/// Enable or disable colors for the whole program, including the prompts and the log records.
+///
+/// Messages are styled once then printed to stdout or stderr, so colors are only enabled if both
+/// are terminals (or `CLICOLOR_FORCE` is set).
+pub fn init(no_color: bool) {
+    let enabled = colors_allowed(no_color, std::env::var_os("NO_COLOR"))
+        && console::colors_enabled()
+        && console::colors_enabled_stderr();
+    console::set_colors_enabled(enabled);
+    console::set_colors_enabled_stderr(enabled);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_allowed() {
+        assert!(colors_allowed(false, None));
+        assert!(colors_allowed(false, Some(OsString::new())));
+        assert!(!colors_allowed(false, Some(OsString::from("1"))));
+        assert!(!colors_allowed(true, None));
+    }
+}
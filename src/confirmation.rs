@@ -1,8 +1,20 @@
-use std::process::Termination;
-use console::style;
-use dialoguer::Confirm;
-use dialoguer::theme::ColorfulTheme;
-use crate::check_submodules::SubmodulesDiagnostic;
+use crate::ascii;
+use crate::check_submodules::{
+    self, GitOperation, SubmoduleChange, SubmoduleEntry, SubmodulesDiagnostic,
+};
+use crate::output::{CiFormat, ci_message};
+use crate::pager;
+use crate::quiet;
+use crate::terminal::TerminalState;
+use console::{Color, Term, style};
+use dialoguer::{Confirm, MultiSelect};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use submodule_hook_lib::display::{Highlight, Section, short_oid};
 
 /// Enum representing the outcome of user confirmation
 #[derive(Debug, PartialEq)]
@@ -15,54 +27,208 @@ pub enum ConfirmationOutcome {
     Cancelled,
 }
 
-impl Termination for ConfirmationOutcome {
-    fn report(self) -> std::process::ExitCode {
-        match self {
-            ConfirmationOutcome::Confirmed => std::process::ExitCode::SUCCESS,
-            ConfirmationOutcome::Declined => std::process::ExitCode::from(1),
-            ConfirmationOutcome::Cancelled => std::process::ExitCode::from(2),
-        }
-    }
+/// Exit codes of the outcomes preventing the commit (`submodulehook.exitcode.*`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExitCodes {
+    pub declined: u8,
+    pub cancelled: u8,
+    pub check_error: u8,
 }
 
-pub fn ask_confirmation(diagnostics: &SubmodulesDiagnostic) -> anyhow::Result<ConfirmationOutcome> {
-    let mut confirmation_message_lines = vec![];
-    if !diagnostics.modified_not_staged_submodules.is_empty() {
-        confirmation_message_lines.push(format!(
-            "{} {} {}",
-            style("The following submodules are").bold(),
-            style("modified but not staged").bold().red(),
-            style("for commit:").bold(),
-        ));
-        for name in &diagnostics.modified_not_staged_submodules {
-            confirmation_message_lines.push(format!(
-                "* {} (`git add {name}` to add submodule to staging)",
-                style(name).bold().red(),
-            ));
+impl Default for ExitCodes {
+    fn default() -> Self {
+        ExitCodes {
+            declined: 1,
+            cancelled: 2,
+            check_error: 3,
         }
     }
-    if !diagnostics.modified_staged_submodules.is_empty() {
-        confirmation_message_lines.push(format!(
-            "{} {} {}",
-            style("The following submodules are").bold(),
-            style("modified and staged").bold().green(),
-            style("for commit:").bold(),
-        ));
-        for name in &diagnostics.modified_staged_submodules {
-            confirmation_message_lines.push(format!(
-                "* {} (`git restore --staged {name}` to remove submodule from staging)",
-                style(name).bold().green(),
-            ));
+}
+
+impl ConfirmationOutcome {
+    /// Exit code of this outcome, using `exit_codes` for declined and cancelled confirmations
+    pub fn exit_code(&self, exit_codes: &ExitCodes) -> u8 {
+        match self {
+            ConfirmationOutcome::Confirmed => 0,
+            ConfirmationOutcome::Declined => exit_codes.declined,
+            ConfirmationOutcome::Cancelled => exit_codes.cancelled,
         }
     }
+}
+
+/// Answer substituted when the user does not answer the prompt in time
+#[derive(Debug, Clone, Copy)]
+pub struct PromptTimeout {
+    pub duration: Duration,
+    pub default: bool,
+}
 
-    println!("{}", confirmation_message_lines.join("\n"));
-    match Confirm::with_theme(&ColorfulTheme::default())
-        .with_prompt("Do you wish to continue anyway?".to_string())
-        .default(false)
+fn confirm_prompt(prompt: &str, default: bool) -> dialoguer::Result<bool> {
+    Confirm::with_theme(ascii::theme().as_ref())
+        .with_prompt(ascii::filter(prompt))
+        .default(default)
         .show_default(true)
         .report(true)
-        .interact() {
+        .interact()
+}
+
+/// How often a pending prompt checks whether the program was interrupted
+const INTERRUPT_POLL: Duration = Duration::from_millis(100);
+
+/// Run the prompt on a background thread, substituting `timeout.default` if no answer is
+/// received within `timeout.duration` and failing with `Interrupted` once the program is
+/// interrupted (`check_submodules::interrupt`).
+///
+/// The prompt thread cannot be interrupted, it is left blocked on its read and
+/// the terminal state is restored before returning.
+fn confirm_prompt_in_background(
+    prompt: &str,
+    default: bool,
+    timeout: Option<PromptTimeout>,
+) -> dialoguer::Result<bool> {
+    let terminal_state = TerminalState::save();
+    let abandon_prompt = || {
+        if let Some(terminal_state) = &terminal_state {
+            terminal_state.restore();
+        }
+        let _ = Term::stderr().show_cursor();
+    };
+    let (sender, receiver) = mpsc::channel();
+    let prompt = prompt.to_string();
+    thread::spawn(move || {
+        let _ = sender.send(confirm_prompt(&prompt, default));
+    });
+    let start = Instant::now();
+    loop {
+        match receiver.recv_timeout(INTERRUPT_POLL) {
+            Ok(result) => return result,
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(std::io::Error::other("prompt thread panicked").into());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if check_submodules::is_interrupted() {
+            abandon_prompt();
+            quiet_eprintln!();
+            return Err(std::io::Error::from(std::io::ErrorKind::Interrupted).into());
+        }
+        if let Some(timeout) = timeout
+            && start.elapsed() >= timeout.duration
+        {
+            abandon_prompt();
+            quiet_eprintln!(
+                "\nNo answer after {}s, defaulting to {}.",
+                timeout.duration.as_secs(),
+                if timeout.default { "yes" } else { "no" }
+            );
+            return Ok(timeout.default);
+        }
+    }
+}
+
+/// Answers the yes/no confirmation prompt
+pub trait ConfirmationProvider {
+    /// Ask `prompt`, `default` being the answer given by pressing Enter
+    fn confirm(&self, prompt: &str, default: bool) -> anyhow::Result<bool>;
+}
+
+/// Interactive prompt on the terminal
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DialoguerConfirmationProvider {
+    pub timeout: Option<PromptTimeout>,
+}
+
+impl ConfirmationProvider for DialoguerConfirmationProvider {
+    fn confirm(&self, prompt: &str, default: bool) -> anyhow::Result<bool> {
+        Ok(confirm_prompt_in_background(prompt, default, self.timeout)?)
+    }
+}
+
+/// How the confirmation is asked
+#[derive(Debug, Clone, Copy)]
+pub struct ConfirmationOptions<'a> {
+    /// Decide from `strict` without prompting
+    pub non_interactive: bool,
+    pub strict: bool,
+    pub prompt: &'a str,
+    /// Answer given by pressing Enter
+    pub default_confirm: bool,
+    pub warn_untracked_branch: bool,
+    /// Note that the commit is amended (`git commit --amend`)
+    pub is_amending: bool,
+    /// Acknowledge each submodule issue instead of answering a single prompt
+    pub multi_select: bool,
+    /// How the diagnostic is reported in non-interactive mode
+    pub ci_format: CiFormat,
+    /// Command showing the diagnostic when more than `pager::PAGER_THRESHOLD` submodules are
+    /// affected, `None` to always print it
+    pub pager: Option<&'a str>,
+}
+
+/// Ask the user to confirm, or decide without prompting when `non_interactive` is set.
+///
+/// In non-interactive mode the diagnostic is printed to stderr and the outcome is
+/// `Declined` in strict mode, `Confirmed` otherwise. The single prompt is answered by
+/// `provider`, a failure to answer it cancelling the confirmation.
+pub fn ask_confirmation(
+    diagnostics: &SubmodulesDiagnostic,
+    options: &ConfirmationOptions,
+    provider: &dyn ConfirmationProvider,
+) -> anyhow::Result<ConfirmationOutcome> {
+    let mut confirmation_message = confirmation_message(diagnostics);
+    if options.warn_untracked_branch
+        && let Some(warning) = untracked_branch_warning(diagnostics)
+    {
+        confirmation_message = format!("{confirmation_message}\n{warning}");
+    }
+    if options.is_amending {
+        confirmation_message = format!("{}\n{confirmation_message}", amend_note());
+    }
+    if options.non_interactive {
+        quiet_eprintln!(
+            "{}",
+            ci_message(
+                options.ci_format,
+                diagnostics,
+                &confirmation_message,
+                options.strict
+            )
+        );
+        return if options.strict {
+            quiet_eprintln!("Non-interactive mode: commit blocked (strict mode).");
+            Ok(ConfirmationOutcome::Declined)
+        } else {
+            quiet_eprintln!("Non-interactive mode: continuing anyway.");
+            Ok(ConfirmationOutcome::Confirmed)
+        };
+    }
+
+    let affected: HashSet<&str> = diagnostics.all_affected().collect();
+    match options.pager {
+        Some(pager)
+            if affected.len() > pager::PAGER_THRESHOLD
+                && !quiet::is_quiet()
+                && std::io::stdout().is_terminal() =>
+        {
+            if let Err(e) = pager::page(pager, &ascii::filter(&confirmation_message)) {
+                tracing::warn!("failed to run pager: {e}");
+                quiet_println!("{confirmation_message}");
+            }
+        }
+        _ => quiet_println!("{confirmation_message}"),
+    }
+    if check_submodules::is_interrupted() {
+        return Ok(ConfirmationOutcome::Cancelled);
+    }
+    if options.multi_select {
+        let outcome = select_issues(diagnostics, options.strict);
+        return if check_submodules::is_interrupted() {
+            Ok(ConfirmationOutcome::Cancelled)
+        } else {
+            Ok(outcome)
+        };
+    }
+    match provider.confirm(options.prompt, options.default_confirm) {
         Ok(confirmation) => {
             if confirmation {
                 Ok(ConfirmationOutcome::Confirmed)
@@ -72,4 +238,503 @@ pub fn ask_confirmation(diagnostics: &SubmodulesDiagnostic) -> anyhow::Result<Co
         }
         Err(_) => Ok(ConfirmationOutcome::Cancelled),
     }
-}
\ No newline at end of file
+}
+
+/// Submodule issue reported in a diagnostic
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue<'a> {
+    pub name: &'a str,
+    pub description: String,
+    /// Commits involved, e.g. `0123456 -> 89abcde`, empty if not relevant to the issue
+    pub commits: String,
+}
+
+/// Issues of the submodules reported in `diagnostics`, one per submodule and kind of issue
+pub fn issue_list<'a>(diagnostics: &'a SubmodulesDiagnostic) -> Vec<Issue<'a>> {
+    fn change(old: Option<git2::Oid>, new: Option<git2::Oid>) -> String {
+        format!("{} -> {}", short_oid(old), short_oid(new))
+    }
+    /// Commits involved in an issue of an entry
+    type Commits = fn(&SubmoduleEntry) -> String;
+    let entries: [(&Vec<_>, &str, Commits); 7] = [
+        (
+            &diagnostics.modified_not_staged_submodules,
+            "modified but not staged",
+            |entry| change(entry.index_oid, entry.workdir_head),
+        ),
+        (
+            &diagnostics.modified_staged_submodules,
+            "modified and staged",
+            |entry| change(entry.head_oid, entry.index_oid),
+        ),
+        (
+            &diagnostics.uninitialized_submodules,
+            "not initialized",
+            |entry| short_oid(entry.index_oid),
+        ),
+        (
+            &diagnostics.newly_added_submodules,
+            "newly added",
+            |entry| short_oid(entry.index_oid),
+        ),
+        (
+            &diagnostics.deleted_submodules,
+            "removed from the index",
+            |entry| short_oid(entry.head_oid),
+        ),
+        (
+            &diagnostics.detached_head_submodules,
+            "in detached HEAD state",
+            |entry| short_oid(entry.workdir_head),
+        ),
+        (
+            &diagnostics.unreachable_commit_submodules,
+            "commit not found on any remote",
+            |entry| short_oid(entry.index_oid),
+        ),
+    ];
+    let issue = |name: &'a str, description: &str| Issue {
+        name,
+        description: description.to_string(),
+        commits: String::new(),
+    };
+    let names = |names: &'a [String], description: &'static str| {
+        names.iter().map(move |name| issue(name, description))
+    };
+    entries
+        .into_iter()
+        .flat_map(|(entries, description, commits)| {
+            entries.iter().map(move |entry| Issue {
+                name: &entry.name,
+                description: description.to_string(),
+                commits: commits(entry),
+            })
+        })
+        .chain(
+            diagnostics
+                .pinned_version_mismatch
+                .iter()
+                .map(|mismatch| Issue {
+                    name: &mismatch.name,
+                    description: "not the pinned commit".to_string(),
+                    commits: change(Some(mismatch.pinned), mismatch.index_oid),
+                }),
+        )
+        .chain(names(
+            &diagnostics.config_mismatch_submodules,
+            "in .git/config but not in .gitmodules",
+        ))
+        .chain(names(
+            &diagnostics.path_conflict_submodules,
+            "path tracked as a regular file",
+        ))
+        .chain(names(
+            &diagnostics.duplicate_submodules,
+            "defined several times in .gitmodules",
+        ))
+        .chain(names(
+            &diagnostics.missing_commit_submodules,
+            "staged commit missing from the submodule",
+        ))
+        .chain(
+            diagnostics
+                .insecure_url_submodules
+                .iter()
+                .map(|(name, url)| issue(name, &format!("URL scheme not allowed ({url})"))),
+        )
+        .chain(names(
+            &diagnostics.uncommitted_changes_submodules,
+            "uncommitted changes",
+        ))
+        .chain(names(&diagnostics.unpushed_submodules, "unpushed commits"))
+        .chain(
+            diagnostics
+                .url_mismatch_submodules
+                .iter()
+                .map(|(name, _, _)| issue(name, ".gitmodules URL differs from .git/config")),
+        )
+        .collect()
+}
+
+/// One `<name>: <issue>` item per submodule reported in `diagnostics`
+pub fn issues(diagnostics: &SubmodulesDiagnostic) -> Vec<String> {
+    issue_list(diagnostics)
+        .into_iter()
+        .map(|issue| format!("{}: {}", issue.name, issue.description))
+        .collect()
+}
+
+/// Outcome once the user acknowledged the `issues` at the `acknowledged` indices, the others
+/// blocking the commit in `strict` mode, being only reported otherwise
+fn acknowledgement_outcome(
+    issues: &[String],
+    acknowledged: &[usize],
+    strict: bool,
+) -> ConfirmationOutcome {
+    let unacknowledged: Vec<&String> = issues
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !acknowledged.contains(index))
+        .map(|(_, issue)| issue)
+        .collect();
+    if unacknowledged.is_empty() {
+        return ConfirmationOutcome::Confirmed;
+    }
+    quiet_eprintln!(
+        "{} {} issue(s) not acknowledged:",
+        style("Warning:").bold().yellow(),
+        unacknowledged.len()
+    );
+    for issue in unacknowledged {
+        quiet_eprintln!("* {issue}");
+    }
+    if strict {
+        quiet_eprintln!("Commit blocked (strict mode).");
+        ConfirmationOutcome::Declined
+    } else {
+        ConfirmationOutcome::Confirmed
+    }
+}
+
+/// Let the user acknowledge each submodule issue of `diagnostics`
+fn select_issues(diagnostics: &SubmodulesDiagnostic, strict: bool) -> ConfirmationOutcome {
+    let issues = issues(diagnostics);
+    let selection = MultiSelect::with_theme(ascii::theme().as_ref())
+        .with_prompt("Acknowledge issues (space to select, enter to continue, esc to abort)")
+        .items(issues.iter().map(|issue| ascii::filter(issue)))
+        .report(true)
+        .interact_opt();
+    match selection {
+        Ok(Some(acknowledged)) => acknowledgement_outcome(&issues, &acknowledged, strict),
+        Ok(None) | Err(_) => ConfirmationOutcome::Cancelled,
+    }
+}
+
+/// Warning listing the submodules of `diagnostics` that do not track any branch, if any
+pub fn untracked_branch_warning(diagnostics: &SubmodulesDiagnostic) -> Option<String> {
+    let mut names: Vec<&str> = [
+        &diagnostics.modified_not_staged_submodules,
+        &diagnostics.modified_staged_submodules,
+        &diagnostics.uninitialized_submodules,
+        &diagnostics.newly_added_submodules,
+        &diagnostics.deleted_submodules,
+        &diagnostics.detached_head_submodules,
+        &diagnostics.unreachable_commit_submodules,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|entry| entry.branch.is_none())
+    .map(|entry| entry.name.as_str())
+    .collect();
+    if names.is_empty() {
+        return None;
+    }
+    names.sort_unstable();
+    names.dedup();
+    let mut lines = vec![format!(
+        "{} {}",
+        style("Warning:").bold().yellow(),
+        style("the following submodules do not track any branch:").bold(),
+    )];
+    for name in names {
+        lines.push(format!(
+            "* {} (`git submodule set-branch --branch <branch> {name}` to track a branch)",
+            style(name).bold().yellow(),
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Reminder that the commit is created by `operation`, not by a plain `git commit`
+pub fn operation_note(operation: GitOperation) -> String {
+    format!(
+        "{} you are currently in the middle of {operation}",
+        style("Note:").bold().cyan()
+    )
+}
+
+/// Reminder that the amended commit may already include submodule changes, not listed
+pub fn amend_note() -> String {
+    format!(
+        "{} you are amending a commit that may already include submodule changes \
+         (`git show --stat HEAD` to review them)",
+        style("Note:").bold().cyan()
+    )
+}
+
+/// Bold `text` colored according to its role in the description of the submodules
+fn colored_style(text: &str, highlight: Highlight) -> String {
+    let styled = style(text).bold();
+    let styled = match highlight {
+        Highlight::Text => styled,
+        Highlight::Keyword(section) | Highlight::Name(section) => styled.fg(section_color(section)),
+    };
+    match highlight {
+        Highlight::Keyword(Section::Unreachable) => styled.underlined().to_string(),
+        _ => styled.to_string(),
+    }
+}
+
+/// Color of the keyword and submodule names of a section
+fn section_color(section: Section) -> Color {
+    match section {
+        Section::NotStaged
+        | Section::Unreachable
+        | Section::Pinned
+        | Section::PathConflict
+        | Section::Duplicate
+        | Section::MissingCommit
+        | Section::Unpushed => Color::Red,
+        Section::Staged => Color::Green,
+        Section::Uninitialized => Color::Cyan,
+        Section::NewlyAdded
+        | Section::ConfigMismatch
+        | Section::UrlMismatch
+        | Section::Uncommitted => Color::Yellow,
+        Section::Deleted | Section::InsecureUrl => Color::Magenta,
+        Section::DetachedHead => Color::Blue,
+    }
+}
+
+/// Colored display of a `SubmodulesDiagnostic`, for terminals
+pub struct Colored<'a>(pub &'a SubmodulesDiagnostic);
+
+impl fmt::Display for Colored<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.render(&colored_style))
+    }
+}
+
+/// Colored description of the submodules listed in `diagnostics`
+pub fn confirmation_message(diagnostics: &SubmodulesDiagnostic) -> String {
+    Colored(diagnostics).to_string()
+}
+
+/// Colored description of the submodules changed by a checkout
+pub fn checkout_message(changes: &[SubmoduleChange]) -> String {
+    let mut lines = vec![format!(
+        "{} {} {}",
+        style("The following submodules were").bold(),
+        style("changed by the checkout").bold().yellow(),
+        style("(`git submodule update --init --recursive` to update them):").bold(),
+    )];
+    for change in changes {
+        let name = style(&change.name).bold().yellow();
+        lines.push(match (change.old_oid, change.new_oid) {
+            (None, new_oid) => format!("* {name} added at {}", short_oid(new_oid)),
+            (old_oid, None) => format!("* {name} removed, was {}", short_oid(old_oid)),
+            (old_oid, new_oid) => {
+                format!("* {name} {} -> {}", short_oid(old_oid), short_oid(new_oid))
+            }
+        });
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_PROMPT;
+    use crate::test_fixtures::entry;
+    use git2::Oid;
+    use std::cell::RefCell;
+
+    fn staged_diagnostics() -> SubmodulesDiagnostic {
+        SubmodulesDiagnostic {
+            modified_staged_submodules: vec![entry("sub")],
+            ..SubmodulesDiagnostic::default()
+        }
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let exit_codes = ExitCodes {
+            declined: 10,
+            cancelled: 20,
+            check_error: 30,
+        };
+        assert_eq!(ConfirmationOutcome::Confirmed.exit_code(&exit_codes), 0);
+        assert_eq!(ConfirmationOutcome::Declined.exit_code(&exit_codes), 10);
+        assert_eq!(ConfirmationOutcome::Cancelled.exit_code(&exit_codes), 20);
+        // documented defaults, see "Exit Code" in the README
+        let defaults = ExitCodes::default();
+        assert_eq!(ConfirmationOutcome::Confirmed.exit_code(&defaults), 0);
+        assert_eq!(ConfirmationOutcome::Declined.exit_code(&defaults), 1);
+        assert_eq!(ConfirmationOutcome::Cancelled.exit_code(&defaults), 2);
+    }
+
+    #[test]
+    fn test_untracked_branch_warning() {
+        let mut diagnostics = staged_diagnostics();
+        let warning = untracked_branch_warning(&diagnostics).unwrap();
+        assert!(warning.contains("sub"));
+
+        diagnostics.modified_staged_submodules[0].branch = Some("main".to_string());
+        assert!(untracked_branch_warning(&diagnostics).is_none());
+    }
+
+    #[test]
+    fn test_operation_note() {
+        let note = operation_note(GitOperation::InteractiveRebase);
+        assert_eq!(
+            console::strip_ansi_codes(&note),
+            "Note: you are currently in the middle of an interactive rebase"
+        );
+    }
+
+    #[test]
+    fn test_checkout_message() {
+        let oid = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let message = console::strip_ansi_codes(&checkout_message(&[
+            SubmoduleChange {
+                name: "added".to_string(),
+                old_oid: None,
+                new_oid: Some(oid),
+            },
+            SubmoduleChange {
+                name: "removed".to_string(),
+                old_oid: Some(oid),
+                new_oid: None,
+            },
+            SubmoduleChange {
+                name: "updated".to_string(),
+                old_oid: Some(Oid::zero()),
+                new_oid: Some(oid),
+            },
+        ]))
+        .to_string();
+        let lines: Vec<&str> = message.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "* added added at 0123456",
+                "* removed removed, was 0123456",
+                "* updated 0000000 -> 0123456",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colored_matches_display() {
+        let diagnostics = staged_diagnostics();
+        let colored = Colored(&diagnostics).to_string();
+        assert_eq!(console::strip_ansi_codes(&colored), diagnostics.to_string());
+    }
+
+    fn non_interactive_options(strict: bool) -> ConfirmationOptions<'static> {
+        ConfirmationOptions {
+            non_interactive: true,
+            strict,
+            prompt: DEFAULT_PROMPT,
+            default_confirm: false,
+            warn_untracked_branch: false,
+            is_amending: false,
+            multi_select: false,
+            ci_format: CiFormat::Plain,
+            pager: None,
+        }
+    }
+
+    /// Gives a predetermined answer, `None` failing as a prompt that cannot be shown, and
+    /// records the prompts asked
+    struct MockConfirmationProvider {
+        answer: Option<bool>,
+        asked: RefCell<Vec<(String, bool)>>,
+    }
+
+    impl MockConfirmationProvider {
+        fn new(answer: Option<bool>) -> MockConfirmationProvider {
+            MockConfirmationProvider {
+                answer,
+                asked: RefCell::new(vec![]),
+            }
+        }
+    }
+
+    impl ConfirmationProvider for MockConfirmationProvider {
+        fn confirm(&self, prompt: &str, default: bool) -> anyhow::Result<bool> {
+            self.asked.borrow_mut().push((prompt.to_string(), default));
+            self.answer.ok_or_else(|| anyhow::anyhow!("not a terminal"))
+        }
+    }
+
+    #[test]
+    fn test_non_interactive_strict_declines() {
+        let provider = MockConfirmationProvider::new(Some(true));
+        let outcome = ask_confirmation(
+            &staged_diagnostics(),
+            &non_interactive_options(true),
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(outcome, ConfirmationOutcome::Declined);
+        assert!(provider.asked.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_non_interactive_not_strict_confirms() {
+        let provider = MockConfirmationProvider::new(Some(false));
+        let outcome = ask_confirmation(
+            &staged_diagnostics(),
+            &non_interactive_options(false),
+            &provider,
+        )
+        .unwrap();
+        assert_eq!(outcome, ConfirmationOutcome::Confirmed);
+        assert!(provider.asked.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_interactive_outcomes() {
+        let options = ConfirmationOptions {
+            non_interactive: false,
+            default_confirm: true,
+            ..non_interactive_options(true)
+        };
+        for (answer, expected) in [
+            (Some(true), ConfirmationOutcome::Confirmed),
+            (Some(false), ConfirmationOutcome::Declined),
+            (None, ConfirmationOutcome::Cancelled),
+        ] {
+            let provider = MockConfirmationProvider::new(answer);
+            let outcome = ask_confirmation(&staged_diagnostics(), &options, &provider).unwrap();
+            assert_eq!(outcome, expected);
+            assert_eq!(
+                *provider.asked.borrow(),
+                [(DEFAULT_PROMPT.to_string(), true)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_issues() {
+        let mut diagnostics = staged_diagnostics();
+        let mut entry = diagnostics.modified_staged_submodules[0].clone();
+        entry.name = "other".to_string();
+        diagnostics.detached_head_submodules.push(entry);
+        assert_eq!(
+            issues(&diagnostics),
+            ["sub: modified and staged", "other: in detached HEAD state"]
+        );
+    }
+
+    #[test]
+    fn test_acknowledgement_outcome() {
+        let issues = vec![
+            "a: newly added".to_string(),
+            "b: not initialized".to_string(),
+        ];
+        assert_eq!(
+            acknowledgement_outcome(&issues, &[0, 1], true),
+            ConfirmationOutcome::Confirmed
+        );
+        assert_eq!(
+            acknowledgement_outcome(&issues, &[1], true),
+            ConfirmationOutcome::Declined
+        );
+        assert_eq!(
+            acknowledgement_outcome(&issues, &[], false),
+            ConfirmationOutcome::Confirmed
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Diagnose installation and configuration problems of the hook.
+
+use crate::install;
+use console::style;
+use git2::{Config as GitConfig, Repository};
+use std::path::Path;
+use submodule_hook_lib::{config, open_repository};
+
+/// Oldest libgit2 version supported by the `git2` crate the program is built with
+const MIN_LIBGIT2_VERSION: (u32, u32) = (1, 9);
+
+/// Result of a single doctor check
+#[derive(Debug)]
+pub struct DoctorCheck {
+    /// What is checked, e.g. "hook is installed"
+    pub name: &'static str,
+    /// Details on success, advice on how to fix the problem on failure
+    pub result: Result<String, String>,
+}
+
+impl DoctorCheck {
+    fn new(name: &'static str, result: Result<String, String>) -> DoctorCheck {
+        DoctorCheck { name, result }
+    }
+}
+
+/// Whether `path` can be executed by its owner
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o100 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+fn check_hook(repo: &Repository, repo_path: &Path, hook_name: &str) -> Vec<DoctorCheck> {
+    let hook = match install::hooks_dir(repo_path) {
+        Ok(hooks_dir) => hooks_dir.join(hook_name),
+        Err(e) => {
+            return vec![DoctorCheck::new("hook is installed", Err(e.to_string()))];
+        }
+    };
+    let hooks_path = repo
+        .config()
+        .and_then(|config| config.get_string("core.hooksPath"));
+    let installed = if let Ok(hooks_path) = hooks_path {
+        Err(format!(
+            "`core.hooksPath` is set to `{hooks_path}`, git does not run {}: \
+             `git config --unset core.hooksPath` or install the hook in `{hooks_path}`",
+            hook.display()
+        ))
+    } else if !hook.exists() {
+        Err(format!(
+            "{} does not exist: `submodule-hook install`",
+            hook.display()
+        ))
+    } else if !install::is_installed_hook(&hook) {
+        Err(format!(
+            "{} was not installed by submodule-hook: \
+             `submodule-hook install --on-existing chain` to run both",
+            hook.display()
+        ))
+    } else {
+        Ok(hook.display().to_string())
+    };
+    let mut checks = vec![DoctorCheck::new("hook is installed", installed)];
+    if hook.exists() {
+        let executable = if is_executable(&hook) {
+            Ok(hook.display().to_string())
+        } else {
+            Err(format!(
+                "git ignores non executable hooks: `chmod +x {}`",
+                hook.display()
+            ))
+        };
+        checks.push(DoctorCheck::new("hook is executable", executable));
+    }
+    checks
+}
+
+fn check_git_config(repo: &Repository) -> DoctorCheck {
+    let result = GitConfig::open_default()
+        .and_then(|_| repo.config())
+        .map(|_| "global and local git config".to_string())
+        .map_err(|e| {
+            format!(
+                "{}: fix the file reported by `git config --list`",
+                e.message()
+            )
+        });
+    DoctorCheck::new("git config parses", result)
+}
+
+fn check_config_files(repo: &Repository) -> DoctorCheck {
+    let files = config::check_config_files(repo.workdir());
+    let errors: Vec<String> = files
+        .iter()
+        .filter_map(|(path, result)| {
+            result
+                .as_ref()
+                .err()
+                .map(|e| format!("{}: {e}", path.display()))
+        })
+        .collect();
+    let result = if !errors.is_empty() {
+        Err(errors.join(", "))
+    } else if files.is_empty() {
+        Ok("no configuration file".to_string())
+    } else {
+        Ok(files
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", "))
+    };
+    DoctorCheck::new("configuration files parse", result)
+}
+
+fn check_submodules(repo: &Repository) -> DoctorCheck {
+    let result = match repo.submodules() {
+        Ok(submodules) if submodules.is_empty() => Err(
+            "the hook has nothing to check: `git submodule add <url>` to add a submodule"
+                .to_string(),
+        ),
+        Ok(submodules) => Ok(format!("{} submodule(s)", submodules.len())),
+        Err(e) => Err(format!("{}: check `.gitmodules`", e.message())),
+    };
+    DoctorCheck::new("repository has submodules", result)
+}
+
+fn check_libgit2() -> DoctorCheck {
+    let version = git2::Version::get();
+    let (major, minor, rev) = version.libgit2_version();
+    let description = format!(
+        "libgit2 {major}.{minor}.{rev}{}, git2 {}",
+        if version.vendored() {
+            " (vendored)"
+        } else {
+            ""
+        },
+        version.crate_version()
+    );
+    let result = if (major, minor) >= MIN_LIBGIT2_VERSION {
+        Ok(description)
+    } else {
+        let (min_major, min_minor) = MIN_LIBGIT2_VERSION;
+        Err(format!(
+            "{description} requires libgit2 {min_major}.{min_minor}: \
+             reinstall submodule-hook to build the vendored libgit2"
+        ))
+    };
+    DoctorCheck::new("git2 library is compatible", result)
+}
+
+/// Run all doctor checks for the `hook_name` hook of the repository at `repo_path`
+pub fn run(repo_path: &Path, hook_name: &str) -> Vec<DoctorCheck> {
+    let mut checks = vec![check_libgit2()];
+    let repo = match open_repository(repo_path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            checks.push(DoctorCheck::new(
+                "repository can be opened",
+                Err(format!(
+                    "{}: run from a git repository or pass `--repo <path>`",
+                    e.message()
+                )),
+            ));
+            return checks;
+        }
+    };
+    checks.push(DoctorCheck::new(
+        "repository can be opened",
+        Ok(repo.path().display().to_string()),
+    ));
+    checks.extend(check_hook(&repo, repo_path, hook_name));
+    checks.push(check_git_config(&repo));
+    checks.push(check_config_files(&repo));
+    checks.push(check_submodules(&repo));
+    checks
+}
+
+/// One `✓`/`✗` line per check, with advice on failures
+pub fn report(checks: &[DoctorCheck]) -> String {
+    checks
+        .iter()
+        .map(|check| match &check.result {
+            Ok(details) => format!("{} {} ({details})", style("✓").green(), check.name),
+            Err(advice) => format!(
+                "{} {}\n  {advice}",
+                style("✗").red().bold(),
+                style(check.name).bold()
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn failed(checks: &[DoctorCheck]) -> Vec<&'static str> {
+        checks
+            .iter()
+            .filter(|check| check.result.is_err())
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_not_a_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        let checks = run(temp_dir.path(), "pre-commit");
+        assert_eq!(failed(&checks), ["repository can be opened"]);
+    }
+
+    #[test]
+    fn test_hook_not_installed() {
+        let temp_dir = TempDir::new().unwrap();
+        Repository::init(temp_dir.path()).unwrap();
+
+        let checks = run(temp_dir.path(), "pre-commit");
+        assert_eq!(
+            failed(&checks),
+            ["hook is installed", "repository has submodules"]
+        );
+        assert!(report(&checks).contains("`submodule-hook install`"));
+
+        install::install(temp_dir.path(), "pre-commit", None).unwrap();
+        let checks = run(temp_dir.path(), "pre-commit");
+        assert_eq!(failed(&checks), ["repository has submodules"]);
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        Repository::init(temp_dir.path()).unwrap();
+        std::fs::write(temp_dir.path().join(".submodule-hook.toml"), "strict = 1").unwrap();
+
+        let check = check_config_files(&Repository::open(temp_dir.path()).unwrap());
+        assert!(check.result.unwrap_err().contains(".submodule-hook.toml"));
+    }
+}
@@ -0,0 +1,342 @@
+//! Install and uninstall the program as a git hook of a repository.
+
+use crate::ascii;
+use clap::ValueEnum;
+use dialoguer::Select;
+use std::fs;
+use std::path::{Path, PathBuf};
+use submodule_hook_lib::open_repository;
+use tracing::debug;
+
+/// Marker written in wrapper scripts, used to recognize hooks installed by this program
+const MARKER: &str = "installed by submodule-hook";
+
+/// What to do with a hook that is already installed
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExistingHook {
+    /// Move it to `<hook>.bak`
+    Backup,
+    /// Keep running it before this hook
+    Chain,
+}
+
+/// Hooks directory of the repository at `repo_path`, shared by all its worktrees
+pub fn hooks_dir(repo_path: &Path) -> anyhow::Result<PathBuf> {
+    let repo = open_repository(repo_path)?;
+    if repo.is_worktree() {
+        // git runs the hooks of the main repository, not `.git/worktrees/<name>/hooks`
+        return Ok(repo.commondir().join("hooks"));
+    }
+    Ok(repo.path().join("hooks"))
+}
+
+/// Sibling path of `hook` with `suffix` appended to its file name
+fn with_suffix(hook: &Path, suffix: &str) -> PathBuf {
+    let mut name = hook.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Shell script running `<hook>.chained` then `<hook>.submodule-hook`, stdin being
+/// forwarded to both for hooks reading it
+fn chain_script(hook_name: &str) -> String {
+    if hook_name == "pre-push" {
+        format!(
+            "#!/bin/sh\n\
+             # {MARKER}\n\
+             hooks_dir=$(dirname \"$0\")\n\
+             input=$(cat)\n\
+             printf '%s\\n' \"$input\" | \"$hooks_dir/{hook_name}.chained\" \"$@\" || exit $?\n\
+             printf '%s\\n' \"$input\" | \"$hooks_dir/{hook_name}.submodule-hook\" \"$@\"\n"
+        )
+    } else {
+        format!(
+            "#!/bin/sh\n\
+             # {MARKER}\n\
+             hooks_dir=$(dirname \"$0\")\n\
+             \"$hooks_dir/{hook_name}.chained\" \"$@\" || exit $?\n\
+             exec \"$hooks_dir/{hook_name}.submodule-hook\" \"$@\"\n"
+        )
+    }
+}
+
+/// Whether `hook` was installed by this program: a copy of the current executable, or a file
+/// containing `MARKER` (wrapper scripts, and copies of any build of the program, which embeds
+/// it).
+///
+/// User scripts merely calling `submodule-hook` are not, so that they are backed up or chained
+/// instead of being overwritten.
+pub fn is_installed_hook(hook: &Path) -> bool {
+    let marker = MARKER.as_bytes();
+    let Ok(content) = fs::read(hook) else {
+        return false;
+    };
+    content.windows(marker.len()).any(|window| window == marker)
+        || std::env::current_exe()
+            .and_then(fs::read)
+            .is_ok_and(|executable| executable == content)
+}
+
+/// Ask what to do with the existing `hook`, `None` if the user aborts
+fn ask_existing_hook(hook: &Path) -> anyhow::Result<Option<ExistingHook>> {
+    let choice = Select::with_theme(ascii::theme().as_ref())
+        .with_prompt(ascii::filter(&format!("{} already exists", hook.display())))
+        .items([
+            "back it up and replace it",
+            "chain to it (run it first)",
+            "abort",
+        ])
+        .default(0)
+        .interact_opt()?;
+    Ok(match choice {
+        Some(0) => Some(ExistingHook::Backup),
+        Some(1) => Some(ExistingHook::Chain),
+        _ => None,
+    })
+}
+
+/// Install the current executable as the `hook_name` hook of the repository at `repo_path`.
+///
+/// If another hook is already installed, `existing` decides whether it is backed up or chained,
+/// the user is asked when `existing` is `None`.
+pub fn install(
+    repo_path: &Path,
+    hook_name: &str,
+    existing: Option<ExistingHook>,
+) -> anyhow::Result<PathBuf> {
+    let hooks_dir = hooks_dir(repo_path)?;
+    fs::create_dir_all(&hooks_dir)?;
+    let hook = hooks_dir.join(hook_name);
+    let executable = std::env::current_exe()?;
+
+    let existing = if hook.exists() && !is_installed_hook(&hook) {
+        match existing {
+            Some(existing) => Some(existing),
+            None if console::user_attended() => match ask_existing_hook(&hook)? {
+                Some(existing) => Some(existing),
+                None => anyhow::bail!("Installation aborted."),
+            },
+            None => anyhow::bail!(
+                "{} already exists, use `--on-existing backup` or `--on-existing chain`",
+                hook.display()
+            ),
+        }
+    } else {
+        None
+    };
+
+    // never overwrite a hook kept by a previous installation
+    let keep = |kept: &Path| -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !kept.exists(),
+            "{} already exists, move it away before installing",
+            kept.display()
+        );
+        debug!("moving {} to {}", hook.display(), kept.display());
+        fs::rename(&hook, kept)?;
+        Ok(())
+    };
+    let installed = with_suffix(&hook, ".submodule-hook");
+    match existing {
+        Some(ExistingHook::Backup) => {
+            keep(&with_suffix(&hook, ".bak"))?;
+            fs::copy(&executable, &hook)?;
+        }
+        Some(ExistingHook::Chain) => {
+            keep(&with_suffix(&hook, ".chained"))?;
+            fs::copy(&executable, &installed)?;
+            set_executable(&installed)?;
+            fs::write(&hook, chain_script(hook_name))?;
+        }
+        None if hook.exists() && installed.exists() => {
+            // reinstalled over a chain: the wrapper script still runs the chained hook
+            debug!("updating {}", installed.display());
+            fs::copy(&executable, &installed)?;
+            set_executable(&installed)?;
+        }
+        None => {
+            fs::copy(&executable, &hook)?;
+        }
+    }
+    set_executable(&hook)?;
+    Ok(hook)
+}
+
+/// Remove the `hook_name` hook installed by `install` from the repository at `repo_path`,
+/// restoring the hook it replaced or chained to, if any
+pub fn uninstall(repo_path: &Path, hook_name: &str) -> anyhow::Result<PathBuf> {
+    let hook = hooks_dir(repo_path)?.join(hook_name);
+    if !hook.exists() {
+        anyhow::bail!("{} does not exist", hook.display());
+    }
+    if !is_installed_hook(&hook) {
+        anyhow::bail!("{} was not installed by submodule-hook", hook.display());
+    }
+    fs::remove_file(&hook)?;
+
+    let installed = with_suffix(&hook, ".submodule-hook");
+    if installed.exists() {
+        fs::remove_file(&installed)?;
+    }
+    for previous in [with_suffix(&hook, ".chained"), with_suffix(&hook, ".bak")] {
+        if previous.exists() {
+            debug!("restoring {}", previous.display());
+            fs::rename(&previous, &hook)?;
+            break;
+        }
+    }
+    Ok(hook)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+    use tempfile::TempDir;
+
+    fn create_repo_with_hook(content: Option<&str>) -> (TempDir, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = Repository::init(temp_dir.path()).unwrap();
+        let hook = repo.path().join("hooks").join("pre-commit");
+        if let Some(content) = content {
+            fs::create_dir_all(hook.parent().unwrap()).unwrap();
+            fs::write(&hook, content).unwrap();
+        }
+        (temp_dir, hook)
+    }
+
+    #[test]
+    fn test_install_and_uninstall() {
+        let (temp_dir, hook) = create_repo_with_hook(None);
+
+        assert_eq!(install(temp_dir.path(), "pre-commit", None).unwrap(), hook);
+        assert_eq!(
+            fs::read(&hook).unwrap(),
+            fs::read(std::env::current_exe().unwrap()).unwrap()
+        );
+
+        uninstall(temp_dir.path(), "pre-commit").unwrap();
+        assert!(!hook.exists());
+    }
+
+    #[test]
+    fn test_install_backup_existing_hook() {
+        let (temp_dir, hook) = create_repo_with_hook(Some("#!/bin/sh\nexit 0\n"));
+
+        install(temp_dir.path(), "pre-commit", Some(ExistingHook::Backup)).unwrap();
+        assert_eq!(
+            fs::read_to_string(with_suffix(&hook, ".bak")).unwrap(),
+            "#!/bin/sh\nexit 0\n"
+        );
+
+        uninstall(temp_dir.path(), "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook).unwrap(), "#!/bin/sh\nexit 0\n");
+    }
+
+    #[test]
+    fn test_install_chain_existing_hook() {
+        let (temp_dir, hook) = create_repo_with_hook(Some("#!/bin/sh\nexit 0\n"));
+
+        install(temp_dir.path(), "pre-commit", Some(ExistingHook::Chain)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&hook).unwrap(),
+            chain_script("pre-commit")
+        );
+        assert!(with_suffix(&hook, ".chained").exists());
+        assert!(with_suffix(&hook, ".submodule-hook").exists());
+
+        uninstall(temp_dir.path(), "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook).unwrap(), "#!/bin/sh\nexit 0\n");
+        assert!(!with_suffix(&hook, ".submodule-hook").exists());
+    }
+
+    #[test]
+    fn test_reinstall_chained_hook() {
+        let (temp_dir, hook) = create_repo_with_hook(Some("#!/bin/sh\nexit 0\n"));
+
+        install(temp_dir.path(), "pre-commit", Some(ExistingHook::Chain)).unwrap();
+        install(temp_dir.path(), "pre-commit", Some(ExistingHook::Chain)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&hook).unwrap(),
+            chain_script("pre-commit")
+        );
+        assert_eq!(
+            fs::read_to_string(with_suffix(&hook, ".chained")).unwrap(),
+            "#!/bin/sh\nexit 0\n"
+        );
+        assert_eq!(
+            fs::read(with_suffix(&hook, ".submodule-hook")).unwrap(),
+            fs::read(std::env::current_exe().unwrap()).unwrap()
+        );
+
+        uninstall(temp_dir.path(), "pre-commit").unwrap();
+        assert_eq!(fs::read_to_string(&hook).unwrap(), "#!/bin/sh\nexit 0\n");
+    }
+
+    #[test]
+    fn test_install_keeps_existing_backup() {
+        let (temp_dir, hook) = create_repo_with_hook(Some("#!/bin/sh\nexit 1\n"));
+        fs::write(with_suffix(&hook, ".bak"), "#!/bin/sh\nexit 0\n").unwrap();
+
+        assert!(install(temp_dir.path(), "pre-commit", Some(ExistingHook::Backup)).is_err());
+        assert_eq!(fs::read_to_string(&hook).unwrap(), "#!/bin/sh\nexit 1\n");
+        assert_eq!(
+            fs::read_to_string(with_suffix(&hook, ".bak")).unwrap(),
+            "#!/bin/sh\nexit 0\n"
+        );
+    }
+
+    #[test]
+    fn test_uninstall_foreign_hook() {
+        let (temp_dir, hook) = create_repo_with_hook(Some("#!/bin/sh\nexit 0\n"));
+
+        assert!(uninstall(temp_dir.path(), "pre-commit").is_err());
+        assert!(hook.exists());
+    }
+
+    #[test]
+    fn test_user_script_calling_the_hook() {
+        let script = "#!/bin/sh\nsubmodule-hook --strict true\nmake lint\n";
+        let (temp_dir, hook) = create_repo_with_hook(Some(script));
+        assert!(!is_installed_hook(&hook));
+
+        assert!(uninstall(temp_dir.path(), "pre-commit").is_err());
+        assert_eq!(fs::read_to_string(&hook).unwrap(), script);
+
+        install(temp_dir.path(), "pre-commit", Some(ExistingHook::Backup)).unwrap();
+        assert!(is_installed_hook(&hook));
+        assert_eq!(
+            fs::read_to_string(with_suffix(&hook, ".bak")).unwrap(),
+            script
+        );
+    }
+
+    #[test]
+    fn test_worktree_hooks_dir() {
+        let (temp_dir, hook) = create_repo_with_hook(None);
+        let repo = Repository::open(temp_dir.path()).unwrap();
+        let sig = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap();
+        let worktree_temp_dir = TempDir::new().unwrap();
+        let worktree_path = worktree_temp_dir.path().join("worktree");
+        repo.worktree("worktree", &worktree_path, None).unwrap();
+
+        assert_eq!(install(&worktree_path, "pre-commit", None).unwrap(), hook);
+    }
+}
@@ -0,0 +1,210 @@
+//! Prevent concurrent runs of the hook on the same repository.
+//!
+//! git may run the hook concurrently (e.g. `git gui` or an IDE committing alongside the command
+//! line), which would show duplicate prompts and race on the index. The lock is a file of the
+//! temporary directory holding the process id of its owner, hard linked from a temporary file
+//! so that it never exists without its content.
+
+use anyhow::bail;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use submodule_hook_lib::open_repository;
+use tracing::{debug, warn};
+
+/// Delay between two attempts to acquire a held lock
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Age above which a lock file without process id is stale, e.g. left by an older version
+/// which died while writing it
+const EMPTY_LOCK_AGE: Duration = Duration::from_secs(5);
+
+/// Lock held until dropped, the lock file is then removed
+#[derive(Debug)]
+pub struct HookLock {
+    path: PathBuf,
+}
+
+/// Lock file of the repository at `repo_path`, one per git directory so that worktrees do not
+/// wait for each other
+fn lock_path(repo_path: &Path) -> PathBuf {
+    let git_dir = open_repository(repo_path)
+        .map(|repo| repo.path().to_path_buf())
+        .unwrap_or_else(|_| repo_path.to_path_buf());
+    let git_dir = git_dir.canonicalize().unwrap_or(git_dir);
+    let mut hasher = DefaultHasher::new();
+    git_dir.hash(&mut hasher);
+    std::env::temp_dir().join(format!("submodule-hook-{:016x}.lock", hasher.finish()))
+}
+
+/// Process id written in the lock file at `path`, `None` if it has none
+fn lock_owner(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Content of the lock file at `path` if it is stale: its owner is no longer running, or it
+/// has had no process id for `EMPTY_LOCK_AGE`
+fn stale_content(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    match content.trim().parse() {
+        Ok(pid) => (!is_running(pid)).then_some(content),
+        Err(_) => {
+            let age = fs::metadata(path).ok()?.modified().ok()?.elapsed().ok()?;
+            (age >= EMPTY_LOCK_AGE).then_some(content)
+        }
+    }
+}
+
+/// Create the lock file at `path` holding the current process id, failing with
+/// `AlreadyExists` if it exists
+fn create_lock(path: &Path) -> std::io::Result<()> {
+    let pid = std::process::id();
+    let temp = path.with_extension(format!("{pid}.tmp"));
+    fs::write(&temp, pid.to_string())?;
+    let linked = fs::hard_link(&temp, path);
+    let _ = fs::remove_file(&temp);
+    linked
+}
+
+/// Whether process `pid` is still running
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // SAFETY: signal 0 only checks that the process exists and can be signaled
+    let signaled = unsafe { libc::kill(pid, 0) } == 0;
+    // the process exists but belongs to another user
+    signaled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+impl HookLock {
+    /// Acquire the lock of the repository at `repo_path`, waiting up to `timeout` for other
+    /// runs of the hook to release it
+    pub fn acquire(repo_path: &Path, timeout: Duration) -> anyhow::Result<HookLock> {
+        HookLock::acquire_at(lock_path(repo_path), timeout)
+    }
+
+    fn acquire_at(path: PathBuf, timeout: Duration) -> anyhow::Result<HookLock> {
+        let start = Instant::now();
+        loop {
+            match create_lock(&path) {
+                Ok(()) => {
+                    debug!("acquired lock {}", path.display());
+                    return Ok(HookLock { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if let Some(stale) = stale_content(&path) {
+                        // another waiter may have replaced the stale lock with its own since
+                        if fs::read_to_string(&path).is_ok_and(|content| content == stale) {
+                            warn!(
+                                "removing stale lock {} of process `{}`",
+                                path.display(),
+                                stale.trim()
+                            );
+                            let _ = fs::remove_file(&path);
+                        }
+                        continue;
+                    }
+                    let owner = lock_owner(&path);
+                    if start.elapsed() >= timeout {
+                        bail!(
+                            "another submodule-hook{} is running on this repository \
+                             (lock file {})",
+                            owner
+                                .map(|pid| format!(" (process {pid})"))
+                                .unwrap_or_default(),
+                            path.display()
+                        );
+                    }
+                    std::thread::sleep(RETRY_DELAY);
+                }
+                Err(e) => bail!("failed to create lock file {}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+impl Drop for HookLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("failed to remove lock {}: {e}", self.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_acquire_release() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hook.lock");
+
+        let lock = HookLock::acquire_at(path.clone(), Duration::ZERO).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+        let error = HookLock::acquire_at(path.clone(), Duration::from_millis(200)).unwrap_err();
+        assert!(error.to_string().contains("another submodule-hook"));
+
+        drop(lock);
+        assert!(!path.exists());
+        HookLock::acquire_at(path, Duration::ZERO).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hook.lock");
+        // above the maximum process id of Linux and macOS
+        fs::write(&path, "999999999").unwrap();
+
+        let _lock = HookLock::acquire_at(path.clone(), Duration::ZERO).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_empty_lock() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("hook.lock");
+        // owner died between creating the lock and writing its process id
+        fs::write(&path, "").unwrap();
+        assert!(HookLock::acquire_at(path.clone(), Duration::ZERO).is_err());
+
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - 2 * EMPTY_LOCK_AGE)
+            .unwrap();
+        let lock = HookLock::acquire_at(path.clone(), Duration::ZERO).unwrap();
+        assert_eq!(lock_owner(&path), Some(std::process::id()));
+        drop(lock);
+        // the temporary file holding the process id is not left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_lock_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let other_temp_dir = TempDir::new().unwrap();
+        git2::Repository::init(temp_dir.path()).unwrap();
+        git2::Repository::init(other_temp_dir.path()).unwrap();
+        let path = lock_path(temp_dir.path());
+        assert_eq!(path, lock_path(&temp_dir.path().join(".git")));
+        assert_ne!(path, lock_path(other_temp_dir.path()));
+    }
+}
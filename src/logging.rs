@@ -0,0 +1,118 @@
+//! Tracing subscriber setup, `RUST_LOG` selecting what is logged.
+
+use clap::ValueEnum;
+use tracing::Subscriber;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Format of the log records written to stderr
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// `tracing-subscriber` default format
+    Text,
+    /// One JSON object per line, e.g. for log aggregation pipelines
+    Json,
+}
+
+/// Subscriber writing the events matching `filter` to `writer`, closed spans are logged with
+/// their duration
+fn subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+    ansi: bool,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_span_events(FmtSpan::CLOSE);
+    match format {
+        LogFormat::Text => Box::new(builder.with_ansi(ansi).finish()),
+        LogFormat::Json => Box::new(builder.json().flatten_event(true).finish()),
+    }
+}
+
+/// Initialize the global subscriber, only errors are logged when `RUST_LOG` is not set, nothing
+/// in `quiet` mode
+pub fn init(format: LogFormat, quiet: bool) {
+    let default_level = if quiet {
+        LevelFilter::OFF
+    } else {
+        LevelFilter::ERROR
+    };
+    let filter = EnvFilter::builder()
+        .with_default_directive(default_level.into())
+        .from_env_lossy();
+    // see `color::init`
+    let ansi = console::colors_enabled_stderr();
+    let subscriber = subscriber(format, filter, std::io::stderr, ansi);
+    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("failed to initialize logging: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let buffer = Buffer::default();
+        let subscriber = subscriber(
+            LogFormat::Json,
+            EnvFilter::new("debug"),
+            buffer.clone(),
+            false,
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::debug_span!("check_submodule", submodule = "vendor/lib").entered();
+            tracing::debug!(commit = "abc", "commit is not on any remote");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // the event, then the span close with its duration
+        assert_eq!(lines.len(), 2);
+        let event = &lines[0];
+        assert_eq!(event["level"], "DEBUG");
+        assert_eq!(event["target"], "submodule_hook::logging::tests");
+        assert_eq!(event["message"], "commit is not on any remote");
+        assert_eq!(event["commit"], "abc");
+        assert_eq!(event["span"]["name"], "check_submodule");
+        assert_eq!(event["span"]["submodule"], "vendor/lib");
+        assert!(event["timestamp"].is_string());
+        assert_eq!(lines[1]["message"], "close");
+        assert!(lines[1]["time.busy"].is_string());
+    }
+}
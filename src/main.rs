@@ -1,12 +1,36 @@
-use clap::Parser;
-use std::path::PathBuf;
-use std::process::Termination;
-use config::HookConfig;
-use confirmation::ConfirmationOutcome;
-
-mod check_submodules;
-mod config;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use config::{ConfigSource, HookConfig};
+use confirmation::{
+    ConfirmationOptions, ConfirmationOutcome, DialoguerConfirmationProvider, ExitCodes,
+    PromptTimeout,
+};
+use output::{CiFormat, OutputFormat};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use submodule_hook_lib::display::short_oid;
+use submodule_hook_lib::{check_submodules, config};
+
+#[macro_use]
+mod quiet;
+
+mod amend;
+mod ascii;
+mod audit;
+mod color;
 mod confirmation;
+mod doctor;
+mod install;
+mod lock;
+mod logging;
+mod output;
+mod pager;
+mod progress;
+mod terminal;
+#[cfg(test)]
+mod test_fixtures;
 
 /// Enum representing the overall program outcome
 #[derive(Debug)]
@@ -17,21 +41,147 @@ enum ProgramOutcome {
     CheckError,
     /// No confirmation needed
     NoConfirmationNeeded,
+    /// Subcommand result with its own exit code (`check`, `--repos`, `config get`, `fix`,
+    /// `update`)
+    Checked(u8),
 }
 
-impl Termination for ProgramOutcome {
-    fn report(self) -> std::process::ExitCode {
+impl ProgramOutcome {
+    /// Exit code of this outcome, `exit_codes` being configurable by the user
+    fn exit_code(&self, exit_codes: &ExitCodes) -> u8 {
         match self {
-            ProgramOutcome::Success(outcome) => outcome.report(),
-            ProgramOutcome::CheckError => std::process::ExitCode::from(3),
-            ProgramOutcome::NoConfirmationNeeded => std::process::ExitCode::SUCCESS,
+            ProgramOutcome::Success(outcome) => outcome.exit_code(exit_codes),
+            ProgramOutcome::CheckError => exit_codes.check_error,
+            ProgramOutcome::NoConfirmationNeeded => 0,
+            ProgramOutcome::Checked(code) => *code,
         }
     }
 }
 
+/// Git hook the program is run as
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum HookType {
+    /// Ask confirmation before committing submodule changes
+    PreCommit,
+    /// Check that pushed submodule commits are available on a remote
+    PrePush,
+    /// Warn about submodules changed by a checkout
+    PostCheckout,
+    /// List the staged submodule changes in the commit message template
+    PrepareCommitMsg,
+    /// Warn about committed submodule commits missing from their submodule
+    PostCommit,
+}
+
+impl HookType {
+    /// Hook type matching the name the program is installed as (e.g. `.git/hooks/pre-push`)
+    fn from_program_name(program: &str) -> Option<HookType> {
+        let name = Path::new(program).file_stem()?.to_str()?;
+        HookType::from_str(name, true).ok()
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Inspect the hook configuration
+    #[command(arg_required_else_help = true)]
+    Config {
+        /// Print the resolved value of each option and the source it comes from
+        #[arg(long)]
+        show: bool,
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// Install the program as a hook of the repository (`--hook-type`, defaults to `pre-commit`)
+    Install {
+        /// Remove the installed hook instead, restoring the hook it replaced if any
+        #[arg(long)]
+        uninstall: bool,
+        /// What to do with an already installed hook, asked if not set
+        #[arg(long, value_enum)]
+        on_existing: Option<install::ExistingHook>,
+    },
+    /// Diagnose installation and configuration problems of the hook (`--hook-type`, defaults
+    /// to `pre-commit`)
+    Doctor,
+    /// Check submodules and report the result in the exit code, without prompting
+    #[command(after_help = "Exit codes:\n  \
+        0  no submodule is modified\n  \
+        1  some submodules are modified but not staged\n  \
+        2  some submodules are modified and staged\n  \
+        3  both, or an error occurred during submodule checking")]
+    Check,
+    /// Print the name of all submodules, one per line (a JSON array with `--format json`)
+    List,
+    /// Print a table of all submodules with their commits and status, always exits with 0
+    Status,
+    /// List submodules added, removed or updated between two commits, e.g. by a pull request
+    Diff {
+        /// Old revision, e.g. `main` or `HEAD~3`
+        old: String,
+        /// New revision
+        new: String,
+    },
+    /// Repair submodules in bulk, exits with 1 if a repair failed
+    #[command(group(
+        clap::ArgGroup::new("actions")
+            .required(true)
+            .multiple(true)
+            .args(["auto_stage", "auto_unstage", "auto_init"]),
+    ))]
+    Fix {
+        /// Stage the commit checked out in modified but not staged submodules
+        #[arg(long, conflicts_with = "auto_unstage")]
+        auto_stage: bool,
+        /// Restore the `HEAD` commit of staged submodules in the index
+        #[arg(long)]
+        auto_unstage: bool,
+        /// Clone and check out uninitialized submodules
+        #[arg(long)]
+        auto_init: bool,
+        /// Print what would be done without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Initialize uninitialized submodules and check out the recorded commit in drifted ones, as
+    /// `git submodule update --init`, exits with 1 if an update failed
+    Update {
+        /// Update the submodules of submodules too
+        #[arg(long)]
+        recursive: bool,
+        /// Check out the tip of the remote branch of initialized submodules instead
+        #[arg(long)]
+        remote: bool,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate the completion script for
+        shell: Shell,
+    },
+}
+
+/// Git config edition, keys may omit the `submodulehook.` prefix
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Write an option to the local git config, e.g. `config set strict true`
+    Set { key: String, value: String },
+    /// Print the git config value of an option, exits with 1 if it is not set
+    Get { key: String },
+    /// Print all `submodulehook.*` git config entries
+    List,
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+    /// Hook to run, defaults to the program name if it is a hook name, else `pre-commit`
+    #[arg(long, value_enum)]
+    hook_type: Option<HookType>,
+    /// Arguments passed by git to the hook (e.g. remote name and url for `pre-push`)
+    #[arg(hide = true)]
+    hook_args: Vec<String>,
     /// Make failed checks hard errors
     #[arg(long)]
     strict: Option<bool>,
@@ -41,78 +191,1257 @@ struct Args {
     /// Ask confirmation if a submodule is modified and not staged for commit
     #[arg(long)]
     confirm_not_staging: Option<bool>,
+    /// Ask confirmation if a submodule is not initialized
+    #[arg(long)]
+    confirm_uninitialized: Option<bool>,
+    /// Ask confirmation if a new submodule is added to the index
+    #[arg(long)]
+    confirm_new_submodule: Option<bool>,
+    /// Ask confirmation if a submodule is removed from the index
+    #[arg(long)]
+    confirm_deletion: Option<bool>,
+    /// Ask confirmation if a submodule is in detached HEAD state
+    #[arg(long)]
+    confirm_detached_head: Option<bool>,
     /// Repository path
     #[arg(long, default_value = ".")]
     repo: PathBuf,
+    /// Check each of these repositories as `check` does and exit with the worst exit code,
+    /// options are resolved from `--repo`
+    #[arg(long, num_args = 1.., value_name = "PATH")]
+    repos: Vec<PathBuf>,
+    /// Never prompt, decide from `--strict` instead (implied when `CI=true`)
+    #[arg(long)]
+    non_interactive: bool,
+    /// Print nothing but the output asked for (`list`, `status`, JSON...), only the exit code
+    /// reports the outcome. The prompt is still shown unless `--non-interactive` is set
+    #[arg(short, long)]
+    quiet: bool,
+    /// Print messages without colors, as when `NO_COLOR` is set or the output is not a terminal
+    #[arg(long)]
+    no_color: bool,
+    /// Print long confirmation messages instead of showing them in a pager
+    #[arg(long)]
+    no_pager: bool,
+    /// Also write the diagnostic as JSON to this file, whatever `--format`, e.g. for wrappers
+    /// of the interactive hook
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Print the status of every submodule before checking them, clean ones included
+    #[arg(short, long, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Skip checks for this submodule (can be repeated)
+    #[arg(long = "allow", value_name = "NAME")]
+    allow: Vec<String>,
+    /// Only allow submodule URLs with this scheme, e.g. `https` or `ssh` (can be repeated)
+    #[arg(long = "allowed-scheme", value_name = "SCHEME")]
+    allowed_schemes: Vec<String>,
+    /// Also check submodules of submodules
+    #[arg(long)]
+    recursive: Option<bool>,
+    /// Maximum submodule nesting depth when checking recursively
+    #[arg(long)]
+    max_depth: Option<usize>,
+    /// Seconds to wait for an answer before taking the default action (0 waits forever)
+    #[arg(long, value_name = "SECS", conflicts_with = "non_interactive")]
+    timeout: Option<u64>,
+    /// Answer used when the confirmation times out
+    #[arg(long)]
+    timeout_default: Option<bool>,
+    /// Seconds to wait for another run of the hook on the same repository to finish
+    #[arg(long, value_name = "SECS")]
+    lock_timeout: Option<u64>,
+    /// Print what the hook would do without prompting
+    #[arg(long)]
+    dry_run: bool,
+    /// Warn when a submodule does not track any branch
+    #[arg(long)]
+    warn_untracked_branch: Option<bool>,
+    /// Show how many commits staged submodules are ahead/behind their upstream branch
+    #[arg(long)]
+    ahead_behind: Option<bool>,
+    /// Ask confirmation if a staged submodule commit is not found on any remote
+    #[arg(long)]
+    verify_remote: Option<bool>,
+    /// Check submodules in parallel, faster for repositories with many submodules
+    #[arg(long)]
+    parallel: Option<bool>,
+    /// Do not inspect the working tree of submodules (uncommitted changes, untracked files),
+    /// only the commit they point to
+    #[arg(long)]
+    allow_dirty: Option<bool>,
+    /// Show the message and author of the commit checked out in modified but not staged submodules
+    #[arg(long)]
+    show_commit_message: Option<bool>,
+    /// Stage modified but not staged submodules instead of asking for confirmation
+    #[arg(long)]
+    auto_stage: Option<bool>,
+    /// Print diagnostics and prompt as usual but never block the commit (exit code 0)
+    #[arg(long)]
+    warn_only: Option<bool>,
+    /// Exit immediately with 0 while a rebase is in progress
+    #[arg(long)]
+    skip_rebase: Option<bool>,
+    /// Ask confirmation when amending a commit (false skips the hook on `git commit --amend`)
+    #[arg(long)]
+    warn_amending: Option<bool>,
+    /// Acknowledge each submodule issue in a multi-select prompt instead of a single confirmation
+    #[arg(long)]
+    multi_select: Option<bool>,
+    /// List files changed in the working tree of modified but not staged submodules
+    #[arg(long)]
+    show_files: Option<bool>,
+    /// Report submodules with uncommitted file changes in their working tree
+    #[arg(long)]
+    check_dirty: Option<bool>,
+    /// Report staged submodules whose branch has commits not pushed to its upstream
+    #[arg(long)]
+    check_unpushed: Option<bool>,
+    /// Show a progress bar while checking submodules, only when stderr is a terminal
+    #[arg(long)]
+    progress: Option<bool>,
+    /// Reuse submodule statuses cached in `.git/submodule-hook-cache` while the submodule `HEAD` is
+    /// unchanged, file changes that do not move it may be missed
+    #[arg(long)]
+    cache: Option<bool>,
+    /// Answer pressing Enter at the confirmation prompt gives (default: false, Enter declines)
+    #[arg(long)]
+    default_confirm: Option<bool>,
+    /// Only print ASCII characters: `-` bullet points, no symbol and no color (implies `--no-color`)
+    #[arg(long)]
+    ascii: Option<bool>,
+    /// Order of the submodules within each kind of issue: name, path or time (most recent
+    /// checked out commit first)
+    #[arg(long, value_name = "ORDER")]
+    sort: Option<check_submodules::SortOrder>,
+    /// Git config levels options are read from: global, local, system or all
+    #[arg(long, value_name = "LEVEL", default_value = "all")]
+    config_level: config::GitConfigLevel,
+    /// Output format, `json` and `markdown` print the diagnostic without prompting
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// How non-interactive runs report the diagnostic, detected from `GITHUB_ACTIONS` and
+    /// `GITLAB_CI` when `CI=true`
+    #[arg(long, value_enum)]
+    ci_format: Option<CiFormat>,
+    /// Format of the log records enabled by `RUST_LOG`
+    #[arg(long, value_enum, default_value_t = logging::LogFormat::Text)]
+    log_format: logging::LogFormat,
 }
 
-fn main() -> ProgramOutcome {
-    env_logger::init();
+/// Submodules changed by a checkout from `old_head` to `new_head` in the repository at `repo`
+fn checkout_changes(
+    repo: &Path,
+    old_head: &str,
+    new_head: &str,
+) -> anyhow::Result<Vec<check_submodules::SubmoduleChange>> {
+    let repo = check_submodules::open_repository(repo)?;
+    let old_oid = git2::Oid::from_str(old_head)?;
+    let new_oid = git2::Oid::from_str(new_head)?;
+    check_submodules::check_submodules_checkout_diff(old_oid, new_oid, &repo)
+}
+
+/// Append `comment` to the commit message file at `path`, after a blank line
+fn append_to_commit_message(path: &Path, comment: &str) -> std::io::Result<()> {
+    let mut message = std::fs::read_to_string(path)?;
+    if !message.is_empty() && !message.ends_with('\n') {
+        message.push('\n');
+    }
+    message.push('\n');
+    message.push_str(comment);
+    message.push('\n');
+    std::fs::write(path, message)
+}
+
+/// Run a `config` subcommand on the git config of the repository at `repo_path`
+fn run_config_action(repo_path: &Path, action: &ConfigAction) -> ProgramOutcome {
+    let warn_unknown = |key: &str| {
+        if !config::is_known_key(key) {
+            quiet_eprintln!("warning: `{key}` is not a known submodule-hook option");
+        }
+    };
+    let result = match action {
+        ConfigAction::Set { key, value } => {
+            warn_unknown(key);
+            config::set_git_config(repo_path, key, value)
+                .map(|_| ProgramOutcome::NoConfirmationNeeded)
+        }
+        ConfigAction::Get { key } => {
+            warn_unknown(key);
+            config::get_git_config(repo_path, key).map(|value| match value {
+                Some(value) => {
+                    println!("{}", ascii::filter(&value));
+                    ProgramOutcome::NoConfirmationNeeded
+                }
+                None => ProgramOutcome::Checked(1),
+            })
+        }
+        ConfigAction::List => config::list_git_config(repo_path).map(|entries| {
+            for (key, value) in entries {
+                println!("{}", ascii::filter(&format!("{key}={value}")));
+            }
+            ProgramOutcome::NoConfirmationNeeded
+        }),
+    };
+    result.unwrap_or_else(|e| {
+        quiet_eprintln!("Config error: {e}");
+        ProgramOutcome::CheckError
+    })
+}
+
+/// Check each repository of `repos` with `run_check`, printing the diagnostic of each under its
+/// path, and exit with the worst `check` exit code (`3` if a check failed)
+fn check_repositories(
+    repos: &[PathBuf],
+    run_check: impl Fn(
+        &Path,
+    ) -> Result<
+        Option<check_submodules::SubmodulesDiagnostic>,
+        check_submodules::CheckError,
+    >,
+    ci_format: CiFormat,
+    strict: bool,
+) -> ProgramOutcome {
+    let mut worst = 0;
+    for repo in repos {
+        quiet_eprintln!("{}", console::style(repo.display()).bold().underlined());
+        let code = match run_check(repo) {
+            Ok(Some(diagnostics)) => {
+                let code = check_submodules::check_exit_code(&diagnostics);
+                if code == 0 {
+                    quiet_eprintln!("No submodule issue");
+                } else {
+                    quiet_eprintln!(
+                        "{}",
+                        output::ci_message(
+                            ci_format,
+                            &diagnostics,
+                            &confirmation::confirmation_message(&diagnostics),
+                            strict
+                        )
+                    );
+                }
+                code
+            }
+            Ok(None) => {
+                quiet_eprintln!("Not checked");
+                0
+            }
+            Err(e) => {
+                quiet_eprintln!("Submodule check error: {e}");
+                3
+            }
+        };
+        worst = worst.max(code);
+    }
+    ProgramOutcome::Checked(worst)
+}
+
+/// Apply the repairs of the `fix` subcommand, printing each of them
+fn run_fix(
+    repo_path: &Path,
+    actions: &[check_submodules::FixAction],
+    dry_run: bool,
+) -> ProgramOutcome {
+    let reports = match check_submodules::fix_submodules(repo_path, actions, dry_run) {
+        Ok(reports) => reports,
+        Err(e) => {
+            quiet_eprintln!("Submodule fix error: {e}");
+            return ProgramOutcome::CheckError;
+        }
+    };
+    if reports.is_empty() {
+        quiet_println!("Nothing to fix");
+    }
+    let mut failed = false;
+    for report in reports {
+        match report.result {
+            Ok(()) if dry_run => quiet_println!("Would {} {}", report.action, report.name),
+            Ok(()) => quiet_println!("{}: {}d", report.name, report.action),
+            Err(e) => {
+                quiet_eprintln!("Failed to {} {}: {e}", report.action, report.name);
+                failed = true;
+            }
+        }
+    }
+    ProgramOutcome::Checked(u8::from(failed))
+}
+
+/// Update the submodules for the `update` subcommand, printing each update as it completes
+fn run_update(repo_path: &Path, recursive: bool, remote: bool) -> ProgramOutcome {
+    let mut updated = false;
+    let mut failed = false;
+    let result = check_submodules::update_submodules(repo_path, recursive, remote, |report| {
+        updated = true;
+        match report.result {
+            Ok(oid) => quiet_println!(
+                "{}: {} at {}",
+                report.name,
+                match report.kind {
+                    check_submodules::UpdateKind::Init => "initialized",
+                    check_submodules::UpdateKind::Checkout => "checked out",
+                    check_submodules::UpdateKind::Remote => "updated from remote",
+                },
+                short_oid(Some(oid))
+            ),
+            Err(e) => {
+                quiet_eprintln!("Failed to {} {}: {e}", report.kind, report.name);
+                failed = true;
+            }
+        }
+    });
+    if let Err(e) = result {
+        quiet_eprintln!("Submodule update error: {e}");
+        return ProgramOutcome::CheckError;
+    }
+    if !updated {
+        quiet_println!("Nothing to update");
+    }
+    ProgramOutcome::Checked(u8::from(failed))
+}
+
+impl Args {
+    /// Reject contradictory values, which clap cannot express for options taking a boolean
+    fn validate(&self) -> Result<(), clap::Error> {
+        if self.strict == Some(true) && self.warn_only == Some(true) {
+            return Err(Args::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "`--strict true` cannot be used with `--warn-only true`, \
+                 warn-only mode never blocks the commit",
+            ));
+        }
+        if !self.repos.is_empty() && !matches!(self.command, None | Some(Commands::Check)) {
+            return Err(Args::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "`--repos` can only be used with `check` or without subcommand",
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn main() -> std::process::ExitCode {
     let args = Args::parse();
+    if let Err(e) = args.validate() {
+        e.exit();
+    }
+    quiet::set_quiet(args.quiet);
+    // resolved before the logging is initialized, so that configuration warnings are only
+    // reported once the configuration is read again by the check
+    let ascii_mode = args.ascii.unwrap_or_else(|| {
+        config::get_config_at_level(args.repo.as_path(), args.config_level)
+            .ascii
+            .unwrap_or(false)
+    });
+    ascii::set_ascii(ascii_mode);
+    color::init(args.no_color || ascii_mode);
+    logging::init(args.log_format, args.quiet);
+    let mut exit_codes = ExitCodes::default();
+    let mut audit_log = None;
+    // the hook lock is released while unwinding, a panic is reported as a check error
+    let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        run(args, &mut exit_codes, &mut audit_log)
+    }))
+    .unwrap_or(ProgramOutcome::CheckError);
+    if let Some(audit_log) = audit_log
+        && let Err(e) = audit_log.record(&outcome)
+    {
+        quiet_eprintln!(
+            "Failed to write audit log `{}`: {e}",
+            audit_log.path.display()
+        );
+    }
+    std::process::ExitCode::from(outcome.exit_code(&exit_codes))
+}
+
+/// Run the subcommand or hook requested by `args`, `exit_codes` and `audit_log` being set from
+/// the configuration once it is resolved
+fn run(
+    args: Args,
+    exit_codes: &mut ExitCodes,
+    audit_log: &mut Option<audit::Audit>,
+) -> ProgramOutcome {
+    if let Some(Commands::Completions { shell }) = args.command {
+        let mut command = Args::command();
+        let name = command.get_name().to_string();
+        clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+    if let Some(Commands::Install {
+        uninstall,
+        on_existing,
+    }) = args.command
+    {
+        let hook_type = args.hook_type.unwrap_or(HookType::PreCommit);
+        let hook_name = hook_type.to_possible_value().expect("no skipped variant");
+        let result = if uninstall {
+            install::uninstall(args.repo.as_path(), hook_name.get_name())
+                .map(|hook| format!("Removed {}", hook.display()))
+        } else {
+            install::install(args.repo.as_path(), hook_name.get_name(), on_existing)
+                .map(|hook| format!("Installed {}", hook.display()))
+        };
+        return match result {
+            Ok(message) => {
+                quiet_println!("{message}");
+                ProgramOutcome::NoConfirmationNeeded
+            }
+            Err(e) => {
+                quiet_eprintln!("Installation error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+    if let Some(Commands::Doctor) = args.command {
+        let hook_type = args.hook_type.unwrap_or(HookType::PreCommit);
+        let hook_name = hook_type.to_possible_value().expect("no skipped variant");
+        let checks = doctor::run(args.repo.as_path(), hook_name.get_name());
+        println!("{}", ascii::filter(&doctor::report(&checks)));
+        return if checks.iter().all(|check| check.result.is_ok()) {
+            ProgramOutcome::NoConfirmationNeeded
+        } else {
+            ProgramOutcome::CheckError
+        };
+    }
+    if let Some(Commands::List) = args.command {
+        return match check_submodules::submodule_names(args.repo.as_path()) {
+            Ok(names) => {
+                let output = match args.format {
+                    OutputFormat::Text => Ok(ascii::filter(&names.join("\n")).into_owned()),
+                    OutputFormat::Json => output::names_to_json(&names),
+                    OutputFormat::Markdown => Ok(ascii::filter(
+                        &names
+                            .iter()
+                            .map(|name| format!("- {name}"))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    )
+                    .into_owned()),
+                };
+                match output {
+                    Ok(output) => {
+                        if !output.is_empty() {
+                            println!("{output}");
+                        }
+                        ProgramOutcome::NoConfirmationNeeded
+                    }
+                    Err(e) => {
+                        quiet_eprintln!("Serialization error: {e}");
+                        ProgramOutcome::CheckError
+                    }
+                }
+            }
+            Err(e) => {
+                quiet_eprintln!("Submodule list error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+    if let Some(Commands::Fix {
+        auto_stage,
+        auto_unstage,
+        auto_init,
+        dry_run,
+    }) = args.command
+    {
+        let actions: Vec<check_submodules::FixAction> = [
+            (auto_stage, check_submodules::FixAction::Stage),
+            (auto_unstage, check_submodules::FixAction::Unstage),
+            (auto_init, check_submodules::FixAction::Init),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, action)| enabled.then_some(action))
+        .collect();
+        return run_fix(args.repo.as_path(), &actions, dry_run);
+    }
+    if let Some(Commands::Update { recursive, remote }) = args.command {
+        return run_update(args.repo.as_path(), recursive, remote);
+    }
+    if let Some(Commands::Status) = args.command {
+        return match check_submodules::submodules_summary(args.repo.as_path()) {
+            Ok(summaries) if summaries.is_empty() => {
+                println!("No submodule");
+                ProgramOutcome::NoConfirmationNeeded
+            }
+            Ok(summaries) => {
+                println!("{}", ascii::filter(&output::status_table(&summaries)));
+                ProgramOutcome::NoConfirmationNeeded
+            }
+            Err(e) => {
+                quiet_eprintln!("Submodule status error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+    if let Some(Commands::Diff { old, new }) = &args.command {
+        return match check_submodules::check_submodules_diff(args.repo.as_path(), old, new) {
+            Ok(diff) => {
+                let output = match args.format {
+                    OutputFormat::Text => {
+                        Ok(ascii::filter(&output::diff_to_text(&diff)).into_owned())
+                    }
+                    OutputFormat::Json => output::diff_to_json(&diff),
+                    OutputFormat::Markdown => {
+                        Ok(ascii::filter(&output::diff_to_markdown(&diff)).into_owned())
+                    }
+                };
+                match output {
+                    Ok(output) => {
+                        if !output.is_empty() {
+                            println!("{output}");
+                        }
+                        ProgramOutcome::NoConfirmationNeeded
+                    }
+                    Err(e) => {
+                        quiet_eprintln!("Serialization error: {e}");
+                        ProgramOutcome::CheckError
+                    }
+                }
+            }
+            Err(e) => {
+                quiet_eprintln!("Submodule diff error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
     let cli_config = HookConfig {
         strict: args.strict,
         confirm_staging: args.confirm_staging,
         confirm_not_staging: args.confirm_not_staging,
+        confirm_uninitialized: args.confirm_uninitialized,
+        confirm_new_submodule: args.confirm_new_submodule,
+        confirm_deletion: args.confirm_deletion,
+        confirm_detached_head: args.confirm_detached_head,
+        non_interactive: args.non_interactive.then_some(true),
+        allow_list: args.allow,
+        allowed_schemes: args.allowed_schemes,
+        recursive: args.recursive,
+        max_depth: args.max_depth,
+        prompt_message: None,
+        timeout_secs: args.timeout,
+        timeout_default: args.timeout_default,
+        lock_timeout_secs: args.lock_timeout,
+        dry_run: args.dry_run.then_some(true),
+        warn_untracked_branch: args.warn_untracked_branch,
+        show_ahead_behind: args.ahead_behind,
+        verify_remote: args.verify_remote,
+        parallel: args.parallel,
+        allow_dirty: args.allow_dirty,
+        show_commit_message: args.show_commit_message,
+        auto_stage: args.auto_stage,
+        warn_only: args.warn_only,
+        skip_rebase: args.skip_rebase,
+        warn_amending: args.warn_amending,
+        multi_select: args.multi_select,
+        show_files: args.show_files,
+        check_dirty: args.check_dirty,
+        check_unpushed: args.check_unpushed,
+        progress: args.progress,
+        cache: args.cache,
+        default_confirm: args.default_confirm,
+        ascii: args.ascii,
+        sort: args.sort,
+        ..HookConfig::default()
+    };
+    if let Some(Commands::Config {
+        action: Some(action),
+        ..
+    }) = &args.command
+    {
+        return run_config_action(args.repo.as_path(), action);
+    }
+    if let Some(Commands::Config { show: true, .. }) = args.command {
+        let mut layers = config::get_config_layers(args.repo.as_path(), args.config_level);
+        let mut cli_config = cli_config;
+        if !cli_config.allow_list.is_empty() {
+            // CLI allowed submodules extend the configured ones
+            let mut allow_list =
+                config::get_config_at_level(args.repo.as_path(), args.config_level).allow_list;
+            allow_list.extend(cli_config.allow_list);
+            cli_config.allow_list = allow_list;
+        }
+        layers.push((ConfigSource::Cli, cli_config));
+        println!("{}", ascii::filter(&config::show_config(&layers)));
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+    let git_config = config::get_config_at_level(args.repo.as_path(), args.config_level);
+    // CLI allowed submodules extend the configured ones
+    let mut allow_list = git_config.allow_list.clone();
+    allow_list.extend(cli_config.allow_list.iter().cloned());
+    let config = git_config.merge(cli_config);
+    let strict = config.strict.unwrap_or(false);
+    let confirm_staging = config.confirm_staging.unwrap_or(true);
+    let confirm_not_staging = config.confirm_not_staging.unwrap_or(true);
+    let confirm_uninitialized = config.confirm_uninitialized.unwrap_or(true);
+    let confirm_new_submodule = config.confirm_new_submodule.unwrap_or(false);
+    let confirm_deletion = config.confirm_deletion.unwrap_or(true);
+    let confirm_detached_head = config.confirm_detached_head.unwrap_or(false);
+    let is_ci = std::env::var("CI").is_ok_and(|value| value == "true");
+    let non_interactive = config.non_interactive.unwrap_or(is_ci);
+    let ci_format = args.ci_format.unwrap_or_else(|| {
+        if is_ci {
+            CiFormat::detect()
+        } else {
+            CiFormat::Plain
+        }
+    });
+    let recursive = config.recursive.unwrap_or(false);
+    let max_depth = config
+        .max_depth
+        .unwrap_or(check_submodules::DEFAULT_MAX_DEPTH);
+    let prompt_message = config
+        .prompt_message
+        .unwrap_or_else(|| config::DEFAULT_PROMPT.to_string());
+    let timeout_default = config.timeout_default.unwrap_or(false);
+    let timeout = config
+        .timeout_secs
+        .filter(|secs| *secs > 0)
+        .map(|secs| PromptTimeout {
+            duration: Duration::from_secs(secs),
+            default: timeout_default,
+        });
+    let lock_timeout = Duration::from_secs(config.lock_timeout_secs.unwrap_or(10));
+    let dry_run = config.dry_run.unwrap_or(false);
+    let warn_untracked_branch = config.warn_untracked_branch.unwrap_or(false);
+    let show_ahead_behind = config.show_ahead_behind.unwrap_or(false);
+    let verify_remote = config.verify_remote.unwrap_or(false);
+    let parallel = config.parallel.unwrap_or(false);
+    let show_commit_message = config.show_commit_message.unwrap_or(false);
+    let auto_stage = config.auto_stage.unwrap_or(false);
+    let warn_only = config.warn_only.unwrap_or(false);
+    let skip_rebase = config.skip_rebase.unwrap_or(false);
+    let warn_amending = config.warn_amending.unwrap_or(true);
+    let multi_select = config.multi_select.unwrap_or(false);
+    let show_files = config.show_files.unwrap_or(false);
+    let check_dirty = config.check_dirty.unwrap_or(false);
+    let check_unpushed = config.check_unpushed.unwrap_or(false);
+    let progress = config.progress.unwrap_or(true);
+    let cache = config.cache.unwrap_or(false);
+    let default_confirm = config.default_confirm.unwrap_or(false);
+    let pager = if args.no_pager {
+        None
+    } else {
+        pager::pager_command(config.pager.clone(), std::env::var("PAGER").ok())
+    };
+    let sort = config.sort.unwrap_or_default();
+    let default_exit_codes = ExitCodes::default();
+    *exit_codes = ExitCodes {
+        declined: config
+            .exit_code_declined
+            .unwrap_or(default_exit_codes.declined),
+        cancelled: config
+            .exit_code_cancelled
+            .unwrap_or(default_exit_codes.cancelled),
+        check_error: config
+            .exit_code_check_error
+            .unwrap_or(default_exit_codes.check_error),
+    };
+    let warn_only_label = || {
+        quiet_eprintln!(
+            "{}",
+            console::style("WARN ONLY: commit will not be blocked")
+                .yellow()
+                .bold()
+        );
+    };
+    let default_ignore = if config.allow_dirty.unwrap_or(false) {
+        git2::SubmoduleIgnore::Dirty
+    } else {
+        git2::SubmoduleIgnore::None
+    };
+    #[cfg(not(feature = "ahead-behind"))]
+    if show_ahead_behind {
+        tracing::warn!("ahead/behind counts require the `ahead-behind` feature");
+    }
+    let run_check = |repo: &Path| {
+        if args.verbose {
+            match check_submodules::submodules_summary(repo) {
+                Ok(summaries) if !summaries.is_empty() => {
+                    eprintln!("{}", ascii::filter(&output::verbose_statuses(&summaries)));
+                }
+                Ok(_) => eprintln!("No submodule"),
+                Err(e) => tracing::warn!("failed to get submodule statuses: {e}"),
+            }
+        }
+        // cleared before the diagnostic is printed
+        let check_progress = if progress && !quiet::is_quiet() {
+            progress::CheckProgress::start()
+        } else {
+            None
+        };
+        let diagnostics = if parallel {
+            check_submodules::check_submodules_parallel(
+                strict,
+                repo,
+                &allow_list,
+                &config.ignore_policies,
+                default_ignore,
+                if recursive { max_depth } else { 1 },
+                cache,
+            )
+        } else if recursive {
+            check_submodules::check_submodules_recursive(
+                strict,
+                repo,
+                &allow_list,
+                &config.ignore_policies,
+                default_ignore,
+                max_depth,
+                cache,
+            )
+        } else {
+            check_submodules::check_submodules(
+                strict,
+                repo,
+                &allow_list,
+                &config.ignore_policies,
+                default_ignore,
+                cache,
+            )
+        };
+        drop(check_progress);
+        diagnostics.and_then(|diagnostics| {
+            diagnostics
+                .map(|mut diagnostics| {
+                    #[cfg(feature = "ahead-behind")]
+                    if show_ahead_behind {
+                        check_submodules::add_ahead_behind(&mut diagnostics, repo);
+                    }
+                    if verify_remote {
+                        check_submodules::check_unreachable_commits(&mut diagnostics, repo);
+                    }
+                    if show_commit_message {
+                        check_submodules::add_commit_summaries(&mut diagnostics, repo);
+                    }
+                    if show_files {
+                        check_submodules::add_changed_files(&mut diagnostics, repo);
+                    }
+                    if !diagnostics.duplicate_submodules.is_empty() {
+                        // ambiguous submodule definitions, fatal whatever `strict`
+                        return Err(check_submodules::CheckError::DuplicateSubmodules(
+                            diagnostics.duplicate_submodules,
+                        ));
+                    }
+                    if !diagnostics.missing_commit_submodules.is_empty() {
+                        // clones of the parent repository could not check the submodule out
+                        return Err(check_submodules::CheckError::MissingCommits(
+                            diagnostics.missing_commit_submodules,
+                        ));
+                    }
+                    check_submodules::check_pinned_commits(
+                        &mut diagnostics,
+                        repo,
+                        &config.pins,
+                        strict,
+                    )?;
+                    if check_unpushed {
+                        check_submodules::check_unpushed_commits(&mut diagnostics, repo);
+                    }
+                    if check_dirty {
+                        check_submodules::check_uncommitted_changes(
+                            &mut diagnostics,
+                            repo,
+                            &allow_list,
+                        );
+                    }
+                    check_submodules::check_submodule_urls(
+                        &mut diagnostics,
+                        repo,
+                        &config.allowed_schemes,
+                        strict,
+                    )?;
+                    check_submodules::sort_diagnostic(&mut diagnostics, repo, sort);
+                    if let Some(report_path) = &args.output
+                        && let Err(source) = output::write_json(report_path, &diagnostics)
+                    {
+                        let error = check_submodules::CheckError::ReportWriteFailed {
+                            path: report_path.clone(),
+                            source,
+                        };
+                        if strict {
+                            return Err(error);
+                        }
+                        quiet_eprintln!("Warning: {error}");
+                    }
+                    Ok(diagnostics)
+                })
+                .transpose()
+        })
     };
-    let git_config = config::get_config();
-    let strict = cli_config.strict.or(git_config.strict).unwrap_or(false);
-    let confirm_staging = cli_config
-        .confirm_staging
-        .or(git_config.confirm_staging)
-        .unwrap_or(true);
-    let confirm_not_staging = cli_config
-        .confirm_not_staging
-        .or(git_config.confirm_not_staging)
-        .unwrap_or(true);
-    
-    if confirm_staging || confirm_not_staging {
+
+    if !args.repos.is_empty() {
+        return check_repositories(&args.repos, run_check, ci_format, strict);
+    }
+
+    if let Some(Commands::Check) = args.command {
+        return match run_check(args.repo.as_path()) {
+            Ok(Some(diagnostics)) => {
+                let code = check_submodules::check_exit_code(&diagnostics);
+                if code != 0 {
+                    quiet_eprintln!(
+                        "{}",
+                        output::ci_message(
+                            ci_format,
+                            &diagnostics,
+                            &confirmation::confirmation_message(&diagnostics),
+                            strict
+                        )
+                    );
+                }
+                ProgramOutcome::Checked(code)
+            }
+            Ok(None) => ProgramOutcome::NoConfirmationNeeded,
+            Err(e) => {
+                quiet_eprintln!("Submodule check error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+
+    let hook_type = args
+        .hook_type
+        .or_else(|| HookType::from_program_name(&std::env::args().next()?))
+        .unwrap_or(HookType::PreCommit);
+    if hook_type == HookType::PrePush {
+        // refs being pushed are read from stdin, never prompt
+        let mut input = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+            quiet_eprintln!("Failed to read pushed refs: {e}");
+            return ProgramOutcome::CheckError;
+        }
+        let pushed = check_submodules::parse_pushed_refs(&input);
+        return match check_submodules::check_submodules_for_push(
+            strict,
+            args.repo.as_path(),
+            &allow_list,
+            &pushed,
+        ) {
+            Ok(Some(diagnostics)) if !diagnostics.is_empty() => {
+                quiet_eprintln!("{}", confirmation::confirmation_message(&diagnostics));
+                if warn_only {
+                    warn_only_label();
+                    quiet_eprintln!("Pushing anyway.");
+                    ProgramOutcome::Success(ConfirmationOutcome::Confirmed)
+                } else {
+                    quiet_eprintln!("Push blocked, push the submodule commits first.");
+                    ProgramOutcome::Success(ConfirmationOutcome::Declined)
+                }
+            }
+            Ok(_) => ProgramOutcome::NoConfirmationNeeded,
+            Err(e) => {
+                quiet_eprintln!("Submodule check error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+
+    if hook_type == HookType::PostCheckout {
+        // git passes <previous HEAD> <new HEAD> <branch checkout flag>, never fails the checkout
+        let [old_head, new_head, branch_checkout] = &args.hook_args[..] else {
+            quiet_eprintln!(
+                "post-checkout expects 3 arguments, got {}",
+                args.hook_args.len()
+            );
+            return ProgramOutcome::CheckError;
+        };
+        if branch_checkout == "0" {
+            // file checkout, HEAD did not change
+            return ProgramOutcome::NoConfirmationNeeded;
+        }
+        return match checkout_changes(args.repo.as_path(), old_head, new_head) {
+            Ok(changes) => {
+                let changes: Vec<_> = changes
+                    .into_iter()
+                    .filter(|change| !check_submodules::is_allowed(&change.name, &allow_list))
+                    .collect();
+                if !changes.is_empty() {
+                    quiet_eprintln!("{}", confirmation::checkout_message(&changes));
+                }
+                ProgramOutcome::NoConfirmationNeeded
+            }
+            Err(e) => {
+                quiet_eprintln!("Submodule check error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+
+    if hook_type == HookType::PrepareCommitMsg {
+        // git passes <message file> [<source> [<commit>]], never fails the commit
+        let Some(message_file) = args.hook_args.first() else {
+            quiet_eprintln!("prepare-commit-msg expects the commit message file as argument");
+            return ProgramOutcome::CheckError;
+        };
+        // with `-m`, `-F` or `-C` the message is not edited, the comment would not be stripped
+        if matches!(
+            args.hook_args.get(1).map(String::as_str),
+            Some("message" | "commit")
+        ) {
+            return ProgramOutcome::NoConfirmationNeeded;
+        }
+        match check_submodules::staged_submodule_changes(args.repo.as_path()) {
+            Ok(changes) => {
+                let changes: Vec<_> = changes
+                    .into_iter()
+                    .filter(|change| !check_submodules::is_allowed(&change.name, &allow_list))
+                    .collect();
+                if !changes.is_empty()
+                    && let Err(e) = append_to_commit_message(
+                        Path::new(message_file),
+                        &output::commit_message_comment(&changes),
+                    )
+                {
+                    quiet_eprintln!("Failed to write commit message file: {e}");
+                }
+            }
+            Err(e) => quiet_eprintln!("Submodule check error: {e}"),
+        }
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+
+    if hook_type == HookType::PostCommit {
+        // the commit already happened, only warn
+        match check_submodules::check_committed_submodules(args.repo.as_path(), &allow_list) {
+            Ok(diagnostics) if !diagnostics.is_clean() => {
+                quiet_eprintln!(
+                    "{}",
+                    console::style(
+                        "Warning: the commit records submodule commits that do not exist"
+                    )
+                    .yellow()
+                    .bold()
+                );
+                quiet_eprintln!("{}", confirmation::confirmation_message(&diagnostics));
+            }
+            Ok(_) => {}
+            Err(e) => quiet_eprintln!("Submodule check error: {e}"),
+        }
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+
+    if args.format != OutputFormat::Text {
+        // machine-readable or pasted output, never prompt
+        return match run_check(args.repo.as_path()) {
+            Ok(Some(diagnostics)) => {
+                let output = match args.format {
+                    OutputFormat::Markdown => {
+                        Ok(ascii::filter(&output::to_markdown(&diagnostics)).into_owned())
+                    }
+                    _ => output::to_json(&diagnostics),
+                };
+                match output {
+                    Ok(output) => {
+                        println!("{output}");
+                        ProgramOutcome::NoConfirmationNeeded
+                    }
+                    Err(e) => {
+                        quiet_eprintln!("Serialization error: {e}");
+                        ProgramOutcome::CheckError
+                    }
+                }
+            }
+            Ok(None) => ProgramOutcome::NoConfirmationNeeded,
+            Err(e) => {
+                quiet_eprintln!("Submodule check error: {e}");
+                ProgramOutcome::CheckError
+            }
+        };
+    }
+
+    *audit_log = config.audit_log.clone().map(|path| audit::Audit {
+        path,
+        repository: args
+            .repo
+            .canonicalize()
+            .unwrap_or_else(|_| args.repo.clone()),
+        submodules: vec![],
+    });
+    // one prompt at a time, git may run the hook concurrently (e.g. IDE integrations)
+    let _lock = match lock::HookLock::acquire(args.repo.as_path(), lock_timeout) {
+        Ok(lock) => lock,
+        Err(e) => {
+            quiet_eprintln!("Lock error: {e}");
+            return ProgramOutcome::CheckError;
+        }
+    };
+    // SIGINT and SIGTERM stop the check or the prompt, the hook then exits as cancelled
+    // instead of being killed, e.g. while the index is written
+    if let Err(e) = ctrlc::set_handler(check_submodules::interrupt) {
+        tracing::warn!("failed to set signal handler: {e}");
+    }
+    let operation = check_submodules::operation_in_progress(args.repo.as_path());
+    if skip_rebase && operation.is_some_and(|operation| operation.is_rebase()) {
+        if dry_run {
+            quiet_println!("[dry run] rebase in progress, the hook would be skipped");
+        }
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+    let is_amending = amend::is_amending();
+    if is_amending && !warn_amending {
+        if dry_run {
+            quiet_println!("[dry run] amending a commit, the hook would be skipped");
+        }
+        return ProgramOutcome::NoConfirmationNeeded;
+    }
+
+    if confirm_staging
+        || confirm_not_staging
+        || confirm_uninitialized
+        || confirm_new_submodule
+        || confirm_deletion
+        || confirm_detached_head
+        || verify_remote
+        || !config.pins.is_empty()
+        || !config.allowed_schemes.is_empty()
+        || check_dirty
+        || check_unpushed
+    {
         // only check submodules if configuration enables confirmation
-        match check_submodules::check_submodules(strict, args.repo.as_path()) {
+        let diagnostics = match run_check(args.repo.as_path()) {
+            Ok(Some(mut diagnostics)) if auto_stage => {
+                if dry_run {
+                    for entry in &diagnostics.modified_not_staged_submodules {
+                        quiet_println!("[dry run] submodule `{}` would be staged", entry.name);
+                    }
+                    diagnostics.modified_not_staged_submodules.clear();
+                    Ok(Some(diagnostics))
+                } else {
+                    check_submodules::stage_modified_submodules(
+                        &mut diagnostics,
+                        args.repo.as_path(),
+                        strict,
+                    )
+                    .map(|staged| {
+                        for name in staged {
+                            quiet_eprintln!("Staged submodule `{name}`");
+                        }
+                        Some(diagnostics)
+                    })
+                }
+            }
+            diagnostics => diagnostics,
+        };
+        if let (Some(audit_log), Ok(Some(diagnostics))) = (audit_log.as_mut(), &diagnostics) {
+            audit_log.set_submodules(diagnostics);
+        }
+        match diagnostics {
+            Ok(Some(diagnostics)) if diagnostics.is_clean() => {
+                if dry_run {
+                    quiet_println!("[dry run] no submodule to report, no confirmation needed");
+                }
+                return ProgramOutcome::NoConfirmationNeeded;
+            }
             Ok(Some(diagnostics)) => {
-                let prompt_for_confirmation = (!diagnostics.modified_not_staged_submodules.is_empty()
+                let prompt_for_confirmation = (!diagnostics
+                    .modified_not_staged_submodules
+                    .is_empty()
                     && confirm_not_staging)
-                    || (!diagnostics.modified_staged_submodules.is_empty() && confirm_staging);
-                
+                    || (!diagnostics.modified_staged_submodules.is_empty() && confirm_staging)
+                    || (!diagnostics.uninitialized_submodules.is_empty() && confirm_uninitialized)
+                    || (!diagnostics.newly_added_submodules.is_empty() && confirm_new_submodule)
+                    || (!diagnostics.deleted_submodules.is_empty() && confirm_deletion)
+                    || (!diagnostics.detached_head_submodules.is_empty() && confirm_detached_head)
+                    || !diagnostics.unreachable_commit_submodules.is_empty()
+                    || !diagnostics.pinned_version_mismatch.is_empty()
+                    || !diagnostics.path_conflict_submodules.is_empty()
+                    || !diagnostics.insecure_url_submodules.is_empty()
+                    || !diagnostics.uncommitted_changes_submodules.is_empty()
+                    || !diagnostics.unpushed_submodules.is_empty();
+
+                if dry_run {
+                    quiet_println!(
+                        "[dry run] submodule-hook would run with the following diagnostic:"
+                    );
+                    quiet_println!("{}", confirmation::confirmation_message(&diagnostics));
+                    if let Some(operation) = operation {
+                        quiet_println!("{}", confirmation::operation_note(operation));
+                    }
+                    if is_amending {
+                        quiet_println!("{}", confirmation::amend_note());
+                    }
+                    if warn_untracked_branch
+                        && let Some(warning) = confirmation::untracked_branch_warning(&diagnostics)
+                    {
+                        quiet_println!("{warning}");
+                    }
+                    if prompt_for_confirmation {
+                        quiet_println!("[dry run] confirmation would be asked");
+                    } else {
+                        quiet_println!("[dry run] no confirmation needed");
+                    }
+                    return ProgramOutcome::NoConfirmationNeeded;
+                }
+
                 if prompt_for_confirmation {
-                    return match confirmation::ask_confirmation(&diagnostics) {
-                        Ok(outcome) => {
-                            match outcome {
-                                ConfirmationOutcome::Confirmed => {
-                                    // User confirmed
-                                    ProgramOutcome::Success(ConfirmationOutcome::Confirmed)
-                                }
-                                ConfirmationOutcome::Declined => {
-                                    // User declined
-                                    eprintln!("Commit aborted by user.");
-                                    ProgramOutcome::Success(ConfirmationOutcome::Declined)
-                                }
-                                ConfirmationOutcome::Cancelled => {
-                                    // User cancelled (e.g., Ctrl+C)
-                                    eprintln!("Confirmation cancelled by user.");
-                                    ProgramOutcome::Success(ConfirmationOutcome::Cancelled)
+                    if warn_only {
+                        warn_only_label();
+                    }
+                    if let Some(operation) = operation {
+                        quiet_eprintln!("{}", confirmation::operation_note(operation));
+                    }
+                    let options = ConfirmationOptions {
+                        non_interactive,
+                        // in warn-only mode non-interactive runs never report a blocked commit
+                        strict: strict && !warn_only,
+                        prompt: &prompt_message,
+                        default_confirm,
+                        warn_untracked_branch,
+                        is_amending,
+                        multi_select,
+                        ci_format,
+                        pager: pager.as_deref(),
+                    };
+                    let provider = DialoguerConfirmationProvider { timeout };
+                    let outcome =
+                        match confirmation::ask_confirmation(&diagnostics, &options, &provider) {
+                            Ok(outcome) => {
+                                match outcome {
+                                    ConfirmationOutcome::Confirmed => {
+                                        // User confirmed
+                                        ProgramOutcome::Success(ConfirmationOutcome::Confirmed)
+                                    }
+                                    ConfirmationOutcome::Declined => {
+                                        // User declined
+                                        if !non_interactive {
+                                            quiet_eprintln!("Commit aborted by user.");
+                                        }
+                                        ProgramOutcome::Success(ConfirmationOutcome::Declined)
+                                    }
+                                    ConfirmationOutcome::Cancelled => {
+                                        // User cancelled (e.g., Ctrl+C)
+                                        quiet_eprintln!("Confirmation cancelled by user.");
+                                        ProgramOutcome::Success(ConfirmationOutcome::Cancelled)
+                                    }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            // Error occurred during confirmation
-                            eprintln!("Confirmation error: {e}");
-                            ProgramOutcome::Success(ConfirmationOutcome::Cancelled)
-                        }
-                    }
+                            Err(e) => {
+                                // Error occurred during confirmation
+                                quiet_eprintln!("Confirmation error: {e}");
+                                ProgramOutcome::Success(ConfirmationOutcome::Cancelled)
+                            }
+                        };
+                    return if warn_only && !check_submodules::is_interrupted() {
+                        ProgramOutcome::NoConfirmationNeeded
+                    } else {
+                        outcome
+                    };
+                }
+                if !diagnostics.config_mismatch_submodules.is_empty()
+                    || !diagnostics.url_mismatch_submodules.is_empty()
+                {
+                    // reported as a warning only, it does not affect the commit
+                    let mismatches = check_submodules::SubmodulesDiagnostic {
+                        config_mismatch_submodules: diagnostics.config_mismatch_submodules,
+                        url_mismatch_submodules: diagnostics.url_mismatch_submodules,
+                        ..Default::default()
+                    };
+                    quiet_eprintln!("{}", confirmation::confirmation_message(&mismatches));
                 }
             }
             Ok(None) => {
                 // No diagnostics to show
                 return ProgramOutcome::NoConfirmationNeeded;
             }
+            Err(check_submodules::CheckError::Interrupted) => {
+                quiet_eprintln!("Interrupted.");
+                return ProgramOutcome::Success(ConfirmationOutcome::Cancelled);
+            }
             Err(e) => {
                 // Error occurred during submodule checking
-                eprintln!("Submodule check error: {e}");
+                quiet_eprintln!("Submodule check error: {e}");
+                let fatal = matches!(
+                    e,
+                    check_submodules::CheckError::DuplicateSubmodules(_)
+                        | check_submodules::CheckError::MissingCommits(_)
+                );
+                if warn_only && !fatal {
+                    warn_only_label();
+                    return ProgramOutcome::NoConfirmationNeeded;
+                }
                 return ProgramOutcome::CheckError;
             }
         }
     }
-    
+
     // No confirmation needed
     ProgramOutcome::NoConfirmationNeeded
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_outcome_exit_code() {
+        // documented defaults, see "Exit Code" in the README
+        let defaults = ExitCodes::default();
+        let exit_code = |outcome: ProgramOutcome| outcome.exit_code(&defaults);
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Confirmed)),
+            0
+        );
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Declined)),
+            1
+        );
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Cancelled)),
+            2
+        );
+        assert_eq!(exit_code(ProgramOutcome::CheckError), 3);
+        assert_eq!(exit_code(ProgramOutcome::NoConfirmationNeeded), 0);
+        assert_eq!(exit_code(ProgramOutcome::Checked(0)), 0);
+        assert_eq!(exit_code(ProgramOutcome::Checked(4)), 4);
+
+        // `submodulehook.exitcode.*` only change declined, cancelled and check error outcomes
+        let exit_codes = ExitCodes {
+            declined: 10,
+            cancelled: 20,
+            check_error: 30,
+        };
+        let exit_code = |outcome: ProgramOutcome| outcome.exit_code(&exit_codes);
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Confirmed)),
+            0
+        );
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Declined)),
+            10
+        );
+        assert_eq!(
+            exit_code(ProgramOutcome::Success(ConfirmationOutcome::Cancelled)),
+            20
+        );
+        assert_eq!(exit_code(ProgramOutcome::CheckError), 30);
+        assert_eq!(exit_code(ProgramOutcome::NoConfirmationNeeded), 0);
+        assert_eq!(exit_code(ProgramOutcome::Checked(1)), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let validate = |args: &[&str]| {
+            Args::try_parse_from(std::iter::once("submodule-hook").chain(args.iter().copied()))
+                .and_then(|args| args.validate())
+                .map_err(|e| e.kind())
+        };
+        assert_eq!(validate(&["--strict", "true"]), Ok(()));
+        assert_eq!(
+            validate(&["--strict", "false", "--warn-only", "true"]),
+            Ok(())
+        );
+        assert_eq!(
+            validate(&["--strict", "true", "--warn-only", "true"]),
+            Err(clap::error::ErrorKind::ArgumentConflict)
+        );
+        assert_eq!(
+            validate(&["--non-interactive", "--timeout", "30"]),
+            Err(clap::error::ErrorKind::ArgumentConflict)
+        );
+        assert_eq!(validate(&["--repos", "a", "b"]), Ok(()));
+        assert_eq!(validate(&["--repos=a", "check"]), Ok(()));
+        assert_eq!(
+            validate(&["--repos=a", "list"]),
+            Err(clap::error::ErrorKind::ArgumentConflict)
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use crate::ascii;
+use crate::check_submodules::{
+    SubmoduleChange, SubmoduleState, SubmoduleSummary, SubmodulesDiagnostic, SubmodulesDiff,
+};
+use crate::confirmation::{issue_list, issues};
+use clap::ValueEnum;
+use comfy_table::{Table, presets};
+use serde::Serialize;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use submodule_hook_lib::display::short_oid;
+
+/// Output format of the submodules diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Interactive colored output with confirmation prompt
+    Text,
+    /// JSON output on stdout, no confirmation prompt
+    Json,
+    /// GitHub Flavored Markdown table on stdout, e.g. for PR descriptions, no confirmation
+    /// prompt
+    Markdown,
+}
+
+/// How the diagnostic is reported in CI logs
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CiFormat {
+    /// GitHub Actions annotations (`::error file=.gitmodules::...`) on `.gitmodules`
+    Github,
+    /// Collapsible GitLab CI log section
+    Gitlab,
+    /// Diagnostic only
+    Plain,
+}
+
+impl CiFormat {
+    /// Format of the CI system the program runs on, from `GITHUB_ACTIONS` and `GITLAB_CI`
+    pub fn detect() -> CiFormat {
+        let is_set = |name: &str| std::env::var(name).is_ok_and(|value| value == "true");
+        if is_set("GITHUB_ACTIONS") {
+            CiFormat::Github
+        } else if is_set("GITLAB_CI") {
+            CiFormat::Gitlab
+        } else {
+            CiFormat::Plain
+        }
+    }
+}
+
+/// Escape `text` for the message of a GitHub Actions workflow command
+fn escape_github(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Diagnostic `message` as reported in the logs of `ci_format`: preceded by one annotation
+/// per issue on GitHub Actions (errors in `strict` mode, warnings otherwise), in a
+/// collapsible section on GitLab CI
+pub fn ci_message(
+    ci_format: CiFormat,
+    diagnostics: &SubmodulesDiagnostic,
+    message: &str,
+    strict: bool,
+) -> String {
+    match ci_format {
+        CiFormat::Github => {
+            let level = if strict { "error" } else { "warning" };
+            let mut lines: Vec<String> = issues(diagnostics)
+                .iter()
+                .map(|issue| {
+                    format!(
+                        "::{level} file=.gitmodules,title=Submodule issue::{}",
+                        escape_github(issue)
+                    )
+                })
+                .collect();
+            lines.push(message.to_string());
+            lines.join("\n")
+        }
+        CiFormat::Gitlab => {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            format!(
+                "\x1b[0Ksection_start:{timestamp}:submodule_hook[collapsed=false]\r\x1b[0KSubmodule issues\n\
+                 {message}\n\
+                 \x1b[0Ksection_end:{timestamp}:submodule_hook\r\x1b[0K"
+            )
+        }
+        CiFormat::Plain => message.to_string(),
+    }
+}
+
+/// JSON report: the diagnostic plus convenience fields
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    diagnostics: &'a SubmodulesDiagnostic,
+    has_issues: bool,
+}
+
+pub fn to_json(diagnostics: &SubmodulesDiagnostic) -> anyhow::Result<String> {
+    let report = JsonReport {
+        diagnostics,
+        has_issues: !diagnostics.is_clean(),
+    };
+    Ok(serde_json::to_string_pretty(&report)?)
+}
+
+/// Write the JSON report of `diagnostics` to the file at `path`, replacing it
+pub fn write_json(path: &Path, diagnostics: &SubmodulesDiagnostic) -> std::io::Result<()> {
+    let json = to_json(diagnostics).map_err(std::io::Error::other)?;
+    std::fs::write(path, json + "\n")
+}
+
+pub fn diff_to_json(diff: &SubmodulesDiff) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(diff)?)
+}
+
+pub fn names_to_json(names: &[String]) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(names)?)
+}
+
+/// Markdown table cell containing `text`
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// GitHub Flavored Markdown table of `rows`, below a `header` row
+fn markdown_table(header: [&str; 3], rows: impl IntoIterator<Item = [String; 3]>) -> String {
+    let mut lines = vec![
+        format!("| {} |", header.join(" | ")),
+        format!("|{}", " --- |".repeat(header.len())),
+    ];
+    lines.extend(
+        rows.into_iter()
+            .map(|row| format!("| {} |", row.map(|cell| markdown_cell(&cell)).join(" | "))),
+    );
+    lines.join("\n")
+}
+
+/// Commits as a Markdown code span, empty if there is none
+fn markdown_commits(commits: &str) -> String {
+    if commits.is_empty() {
+        String::new()
+    } else {
+        format!("`{commits}`")
+    }
+}
+
+/// `Submodule | Status | Commit` table of the issues in `diagnostics`, one row per submodule
+/// and kind of issue
+pub fn to_markdown(diagnostics: &SubmodulesDiagnostic) -> String {
+    if diagnostics.is_clean() {
+        return "No submodule issue".to_string();
+    }
+    markdown_table(
+        ["Submodule", "Status", "Commit"],
+        issue_list(diagnostics).into_iter().map(|issue| {
+            [
+                issue.name.to_string(),
+                issue.description,
+                markdown_commits(&issue.commits),
+            ]
+        }),
+    )
+}
+
+/// `Submodule | Change | Commit` table of the submodules changed in `diff`, then
+/// `.gitmodules changed` if it differs
+pub fn diff_to_markdown(diff: &SubmodulesDiff) -> String {
+    let mut markdown = markdown_table(
+        ["Submodule", "Change", "Commit"],
+        diff.changes.iter().map(|change| {
+            [
+                change.name.clone(),
+                change.kind().to_string(),
+                markdown_commits(&format!(
+                    "{} -> {}",
+                    short_oid(change.old_oid),
+                    short_oid(change.new_oid)
+                )),
+            ]
+        }),
+    );
+    if diff.gitmodules_changed {
+        markdown.push_str("\n\n`.gitmodules` changed");
+    }
+    markdown
+}
+
+/// One `<kind> <name> <old oid> <new oid>` line per changed submodule, `none` standing for a
+/// missing commit, then `.gitmodules changed` if it differs
+pub fn diff_to_text(diff: &SubmodulesDiff) -> String {
+    let oid = |oid: Option<git2::Oid>| oid.map_or("none".to_string(), |oid| oid.to_string());
+    let mut lines: Vec<String> = diff
+        .changes
+        .iter()
+        .map(|change| {
+            format!(
+                "{} {} {} {}",
+                change.kind(),
+                change.name,
+                oid(change.old_oid),
+                oid(change.new_oid)
+            )
+        })
+        .collect();
+    if diff.gitmodules_changed {
+        lines.push(".gitmodules changed".to_string());
+    }
+    lines.join("\n")
+}
+
+/// Comment block listing the submodule changes of a commit, stripped by git from the final
+/// commit message
+pub fn commit_message_comment(changes: &[SubmoduleChange]) -> String {
+    let mut lines = vec!["# Submodule changes:".to_string()];
+    lines.extend(changes.iter().map(|change| {
+        format!(
+            "#   {} {} -> {} ({})",
+            change.name,
+            short_oid(change.old_oid),
+            short_oid(change.new_oid),
+            change.kind()
+        )
+    }));
+    lines.join("\n")
+}
+
+/// Table of `summaries`, one row per submodule
+pub fn status_table(summaries: &[SubmoduleSummary]) -> String {
+    let preset = if ascii::is_ascii() {
+        presets::ASCII_FULL_CONDENSED
+    } else {
+        presets::UTF8_FULL_CONDENSED
+    };
+    let mut table = Table::new();
+    table
+        .load_preset(preset)
+        .set_header(["Name", "Path", "HEAD commit", "Index commit", "Status"]);
+    for summary in summaries {
+        table.add_row([
+            summary.name.clone(),
+            summary.path.clone(),
+            short_oid(summary.workdir_head),
+            short_oid(summary.index_oid),
+            summary
+                .states
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]);
+    }
+    table.to_string()
+}
+
+/// One line per submodule with its states, clean submodules being dimmed
+pub fn verbose_statuses(summaries: &[SubmoduleSummary]) -> String {
+    summaries
+        .iter()
+        .map(|summary| {
+            let states = summary
+                .states
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if summary.states == [SubmoduleState::Clean] {
+                format!("* {}: {states}", console::style(&summary.name).dim())
+            } else {
+                format!("* {}: {states}", summary.name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_submodules::SubmoduleEntry;
+    use crate::test_fixtures::entry;
+
+    fn empty_diagnostics() -> SubmodulesDiagnostic {
+        SubmodulesDiagnostic::default()
+    }
+
+    #[test]
+    fn test_to_json_clean() {
+        let json = to_json(&empty_diagnostics()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["has_issues"], false);
+        assert_eq!(value["modified_staged_submodules"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_write_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        write_json(&path, &empty_diagnostics()).unwrap();
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(value["has_issues"], false);
+
+        assert!(
+            write_json(
+                &dir.path().join("missing/report.json"),
+                &empty_diagnostics()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_to_json_with_issues() {
+        let mut diagnostics = empty_diagnostics();
+        diagnostics.modified_staged_submodules.push(SubmoduleEntry {
+            index_oid: Some(git2::Oid::zero()),
+            branch: Some("main".to_string()),
+            ..entry("sub")
+        });
+
+        let json = to_json(&diagnostics).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["has_issues"], true);
+        assert_eq!(
+            value["modified_staged_submodules"],
+            serde_json::json!([{
+                "name": "sub",
+                "path": "sub",
+                "head_oid": null,
+                "index_oid": "0000000000000000000000000000000000000000",
+                "workdir_head": null,
+                "branch": "main",
+                "ahead_behind": null,
+                "head_commit": null,
+                "changed_files": null,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut diagnostics = empty_diagnostics();
+        assert_eq!(to_markdown(&diagnostics), "No submodule issue");
+        diagnostics.modified_staged_submodules.push(SubmoduleEntry {
+            index_oid: Some(git2::Oid::zero()),
+            ..entry("sub")
+        });
+        diagnostics
+            .uncommitted_changes_submodules
+            .push("a|b".to_string());
+        assert_eq!(
+            to_markdown(&diagnostics),
+            "| Submodule | Status | Commit |\n\
+             | --- | --- | --- |\n\
+             | sub | modified and staged | `none -> 0000000` |\n\
+             | a\\|b | uncommitted changes |  |"
+        );
+    }
+
+    #[test]
+    fn test_ci_message() {
+        let mut diagnostics = empty_diagnostics();
+        diagnostics.modified_staged_submodules.push(entry("sub"));
+        assert_eq!(
+            ci_message(CiFormat::Github, &diagnostics, "message", true),
+            "::error file=.gitmodules,title=Submodule issue::sub: modified and staged\nmessage"
+        );
+        assert!(
+            ci_message(CiFormat::Github, &diagnostics, "message", false).starts_with("::warning ")
+        );
+        let gitlab = ci_message(CiFormat::Gitlab, &diagnostics, "message", false);
+        assert!(gitlab.contains("section_start:"));
+        assert!(gitlab.contains("\nmessage\n"));
+        assert_eq!(
+            ci_message(CiFormat::Plain, &diagnostics, "message", false),
+            "message"
+        );
+    }
+
+    #[test]
+    fn test_escape_github() {
+        assert_eq!(escape_github("100% done\nnext"), "100%25 done%0Anext");
+    }
+
+    #[test]
+    fn test_verbose_statuses() {
+        let summary = |name: &str, states| SubmoduleSummary {
+            name: name.to_string(),
+            path: name.to_string(),
+            workdir_head: None,
+            index_oid: None,
+            states,
+        };
+        let statuses = verbose_statuses(&[
+            summary("clean", vec![SubmoduleState::Clean]),
+            summary(
+                "changed",
+                vec![SubmoduleState::Modified, SubmoduleState::Staged],
+            ),
+        ]);
+        let lines: Vec<String> = console::strip_ansi_codes(&statuses)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(lines, ["* clean: clean", "* changed: modified, staged"]);
+    }
+
+    #[test]
+    fn test_status_table() {
+        let table = status_table(&[SubmoduleSummary {
+            name: "sub".to_string(),
+            path: "libs/sub".to_string(),
+            workdir_head: Some(git2::Oid::zero()),
+            index_oid: None,
+            states: vec![SubmoduleState::Modified, SubmoduleState::Staged],
+        }]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].contains("Name") && lines[1].contains("Status"));
+        assert!(lines[3].contains("libs/sub"));
+        assert!(lines[3].contains("0000000"));
+        assert!(lines[3].contains("none"));
+        assert!(lines[3].contains("modified, staged"));
+    }
+
+    #[test]
+    fn test_commit_message_comment() {
+        let comment = commit_message_comment(&submodules_diff().changes);
+        assert_eq!(
+            comment,
+            "# Submodule changes:\n\
+             #   added none -> 0000000 (added)\n\
+             #   updated 0000000 -> 0000000 (updated)"
+        );
+    }
+
+    fn submodules_diff() -> SubmodulesDiff {
+        SubmodulesDiff {
+            changes: vec![
+                SubmoduleChange {
+                    name: "added".to_string(),
+                    old_oid: None,
+                    new_oid: Some(git2::Oid::zero()),
+                },
+                SubmoduleChange {
+                    name: "updated".to_string(),
+                    old_oid: Some(git2::Oid::zero()),
+                    new_oid: Some(git2::Oid::zero()),
+                },
+            ],
+            gitmodules_changed: true,
+        }
+    }
+
+    #[test]
+    fn test_diff_to_text() {
+        let zero = git2::Oid::zero();
+        assert_eq!(
+            diff_to_text(&submodules_diff()),
+            format!("added added none {zero}\nupdated updated {zero} {zero}\n.gitmodules changed")
+        );
+    }
+
+    #[test]
+    fn test_diff_to_markdown() {
+        assert_eq!(
+            diff_to_markdown(&submodules_diff()),
+            "| Submodule | Change | Commit |\n\
+             | --- | --- | --- |\n\
+             | added | added | `none -> 0000000` |\n\
+             | updated | updated | `0000000 -> 0000000` |\n\n\
+             `.gitmodules` changed"
+        );
+    }
+
+    #[test]
+    fn test_diff_to_json() {
+        let json = diff_to_json(&submodules_diff()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["gitmodules_changed"], true);
+        assert_eq!(
+            value["changes"][0],
+            serde_json::json!({
+                "name": "added",
+                "old_oid": null,
+                "new_oid": "0000000000000000000000000000000000000000",
+            })
+        );
+    }
+
+    #[test]
+    fn test_names_to_json() {
+        let names = vec!["a".to_string(), "vendor/b".to_string()];
+        let value: serde_json::Value =
+            serde_json::from_str(&names_to_json(&names).unwrap()).unwrap();
+        assert_eq!(value, serde_json::json!(["a", "vendor/b"]));
+        assert_eq!(names_to_json(&[]).unwrap(), "[]");
+    }
+}
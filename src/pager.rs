@@ -0,0 +1,73 @@
+//! Long confirmation messages are shown in a pager, so that every issue can be read before
+//! answering the prompt.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Number of affected submodules above which the confirmation message is paged
+pub const PAGER_THRESHOLD: usize = 10;
+
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Pager command: `submodulehook.pager`, then `$PAGER`, then `less -R`, `None` if the chosen
+/// value is empty
+pub fn pager_command(configured: Option<String>, pager_env: Option<String>) -> Option<String> {
+    let command = configured
+        .or(pager_env)
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    (!command.trim().is_empty()).then_some(command)
+}
+
+/// Pipe `text` to `command`, run by the shell so that it may have arguments, and wait for the
+/// user to quit it
+pub fn page(command: &str, text: &str) -> anyhow::Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell.arg(command).stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the pager may be quit before reading the whole text
+        let _ = writeln!(stdin, "{text}");
+    }
+    let status = child.wait()?;
+    anyhow::ensure!(status.success(), "`{command}` failed ({status})");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None, None).as_deref(), Some("less -R"));
+        assert_eq!(
+            pager_command(None, Some("more".to_string())).as_deref(),
+            Some("more")
+        );
+        assert_eq!(
+            pager_command(Some("bat".to_string()), Some("more".to_string())).as_deref(),
+            Some("bat")
+        );
+        assert_eq!(
+            pager_command(Some(String::new()), Some("more".to_string())),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let paged = dir.path().join("paged");
+        page(&format!("cat > {}", paged.display()), "issues").unwrap();
+        assert_eq!(std::fs::read_to_string(paged).unwrap(), "issues\n");
+        assert!(page("false", "issues").is_err());
+    }
+}
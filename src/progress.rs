@@ -0,0 +1,37 @@
+//! Progress bar advanced as submodules are checked.
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use submodule_hook_lib::check_submodules;
+
+/// Progress bar shown on stderr while it is alive, cleared once dropped
+pub struct CheckProgress {
+    bar: ProgressBar,
+}
+
+impl CheckProgress {
+    /// Start reporting the progress of the checks, `None` if stderr is not a terminal
+    pub fn start() -> Option<Self> {
+        if !console::Term::stderr().is_term() {
+            return None;
+        }
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+        bar.set_style(
+            ProgressStyle::with_template("Checking submodules [{pos}/{len}] {msg}")
+                .expect("valid progress bar template"),
+        );
+        let reported = bar.clone();
+        check_submodules::set_progress_callback(Some(Box::new(move |checked, total, name| {
+            reported.set_length(total as u64);
+            reported.set_position(checked as u64);
+            reported.set_message(name.to_string());
+        })));
+        Some(CheckProgress { bar })
+    }
+}
+
+impl Drop for CheckProgress {
+    fn drop(&mut self) {
+        check_submodules::set_progress_callback(None);
+        self.bar.finish_and_clear();
+    }
+}
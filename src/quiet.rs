@@ -0,0 +1,44 @@
+//! `--quiet` mode: messages are not printed, only the exit code reports the outcome.
+//!
+//! Output explicitly asked for (`list`, `status`, `diff`, `config`, JSON diagnostics...) is
+//! still printed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `println!` unless `--quiet` is set, ASCII only in `--ascii` mode
+macro_rules! quiet_println {
+    () => {
+        if !$crate::quiet::is_quiet() {
+            println!();
+        }
+    };
+    ($($arg:tt)*) => {
+        if !$crate::quiet::is_quiet() {
+            println!("{}", $crate::ascii::filter(&format!($($arg)*)));
+        }
+    };
+}
+
+/// `eprintln!` unless `--quiet` is set, ASCII only in `--ascii` mode
+macro_rules! quiet_eprintln {
+    () => {
+        if !$crate::quiet::is_quiet() {
+            eprintln!();
+        }
+    };
+    ($($arg:tt)*) => {
+        if !$crate::quiet::is_quiet() {
+            eprintln!("{}", $crate::ascii::filter(&format!($($arg)*)));
+        }
+    };
+}
@@ -0,0 +1,48 @@
+//! Save and restore the terminal state around a prompt that may be abandoned mid-read.
+
+#[cfg(unix)]
+mod imp {
+    use std::fs::File;
+    use std::os::fd::AsRawFd;
+
+    /// Terminal attributes of the controlling terminal
+    pub struct TerminalState {
+        tty: File,
+        termios: libc::termios,
+    }
+
+    impl TerminalState {
+        pub fn save() -> Option<Self> {
+            let tty = File::open("/dev/tty").ok()?;
+            let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+            // SAFETY: `tty` is an open file descriptor and `termios` is a valid out pointer
+            if unsafe { libc::tcgetattr(tty.as_raw_fd(), termios.as_mut_ptr()) } != 0 {
+                return None;
+            }
+            // SAFETY: `tcgetattr` succeeded so `termios` is initialized
+            let termios = unsafe { termios.assume_init() };
+            Some(TerminalState { tty, termios })
+        }
+
+        pub fn restore(&self) {
+            // SAFETY: `tty` is an open file descriptor and `termios` was read from it
+            unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSANOW, &self.termios) };
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    /// Terminal state restoration is not supported on this platform
+    pub struct TerminalState;
+
+    impl TerminalState {
+        pub fn save() -> Option<Self> {
+            None
+        }
+
+        pub fn restore(&self) {}
+    }
+}
+
+pub use imp::TerminalState;
@@ -0,0 +1,18 @@
+//! Values shared by the tests of the binary modules.
+
+use submodule_hook_lib::check_submodules::SubmoduleEntry;
+
+/// Entry of the submodule `name` checked out at the path `name`, without any commit
+pub fn entry(name: &str) -> SubmoduleEntry {
+    SubmoduleEntry {
+        name: name.to_string(),
+        path: name.to_string(),
+        head_oid: None,
+        index_oid: None,
+        workdir_head: None,
+        branch: None,
+        ahead_behind: None,
+        head_commit: None,
+        changed_files: None,
+    }
+}
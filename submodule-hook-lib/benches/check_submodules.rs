@@ -0,0 +1,62 @@
+//! Serial, parallel and cached submodule checks on synthetic repositories with 1 to 100
+//! submodules in various states.
+//!
+//! Run with `cargo bench`.
+
+mod helpers;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use git2::SubmoduleIgnore;
+use helpers::{SUBMODULE_COUNTS, SyntheticRepo};
+use std::collections::HashMap;
+use std::hint::black_box;
+use submodule_hook_lib::check_submodules;
+
+fn bench_check_submodules(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check_submodules");
+    for count in SUBMODULE_COUNTS {
+        let repo = SyntheticRepo::new(count);
+        let parent_path = &repo.parent_path;
+        group.bench_with_input(BenchmarkId::new("serial", count), &count, |b, _| {
+            b.iter(|| {
+                check_submodules::check_submodules(
+                    false,
+                    black_box(parent_path),
+                    &[],
+                    &HashMap::new(),
+                    SubmoduleIgnore::None,
+                    false,
+                )
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", count), &count, |b, _| {
+            b.iter(|| {
+                check_submodules::check_submodules_parallel(
+                    false,
+                    black_box(parent_path),
+                    &[],
+                    &HashMap::new(),
+                    SubmoduleIgnore::None,
+                    1,
+                    false,
+                )
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cached", count), &count, |b, _| {
+            b.iter(|| {
+                check_submodules::check_submodules(
+                    false,
+                    black_box(parent_path),
+                    &[],
+                    &HashMap::new(),
+                    SubmoduleIgnore::None,
+                    true,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_check_submodules);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! Synthetic repositories shared by the benchmarks.
+
+#[path = "../src/test_repos.rs"]
+mod test_repos;
+
+use std::path::PathBuf;
+use tempfile::TempDir;
+use test_repos::{
+    add_submodule, create_temp_repo, deinit_submodule_wd, modify_submodule_wd, stage_submodule,
+};
+
+/// Number of submodules of the benchmarked repositories
+pub const SUBMODULE_COUNTS: [usize; 4] = [1, 5, 20, 100];
+
+/// Parent repository with `count` committed submodules `sub_<i>`, in turn clean, modified but
+/// not staged, modified and staged, and not initialized
+pub struct SyntheticRepo {
+    /// Keeps the repositories alive
+    _temp_dirs: Vec<TempDir>,
+    pub parent_path: PathBuf,
+}
+
+impl SyntheticRepo {
+    pub fn new(count: usize) -> SyntheticRepo {
+        let (parent_temp_dir, parent) = create_temp_repo().unwrap();
+        let mut temp_dirs = vec![parent_temp_dir];
+        let names: Vec<String> = (0..count).map(|i| format!("sub_{i:03}")).collect();
+        for name in &names {
+            let (temp_dir, _) = add_submodule(&parent, name).unwrap();
+            temp_dirs.push(temp_dir);
+        }
+
+        // changed once every submodule is committed, a staged submodule would otherwise be
+        // committed along with the next one
+        for (i, name) in names.iter().enumerate() {
+            match i % 4 {
+                1 => modify_submodule_wd(&parent, name).unwrap(),
+                2 => {
+                    modify_submodule_wd(&parent, name).unwrap();
+                    stage_submodule(&parent, name).unwrap();
+                }
+                3 => deinit_submodule_wd(&parent, name).unwrap(),
+                _ => {}
+            }
+        }
+        SyntheticRepo {
+            _temp_dirs: temp_dirs,
+            parent_path: parent.workdir().unwrap().to_path_buf(),
+        }
+    }
+}
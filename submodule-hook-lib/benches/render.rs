@@ -0,0 +1,51 @@
+//! Formatting of the confirmation message of synthetic repositories with 1 to 100 submodules
+//! in various states.
+//!
+//! Run with `cargo bench`.
+
+mod helpers;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use git2::SubmoduleIgnore;
+use helpers::{SUBMODULE_COUNTS, SyntheticRepo};
+use std::collections::HashMap;
+use std::hint::black_box;
+use submodule_hook_lib::check_submodules;
+use submodule_hook_lib::display::Highlight;
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    for count in SUBMODULE_COUNTS {
+        let repo = SyntheticRepo::new(count);
+        let diagnostic = check_submodules::check_submodules(
+            false,
+            &repo.parent_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("plain", count),
+            &diagnostic,
+            |b, diagnostic| b.iter(|| black_box(diagnostic).to_string()),
+        );
+        // as the hook prompt, each piece of text being styled
+        group.bench_with_input(
+            BenchmarkId::new("highlighted", count),
+            &diagnostic,
+            |b, diagnostic| {
+                b.iter(|| {
+                    black_box(diagnostic)
+                        .render(&|text: &str, _: Highlight| format!("\x1b[1m{text}\x1b[0m"))
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
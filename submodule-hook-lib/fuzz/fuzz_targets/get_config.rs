@@ -0,0 +1,16 @@
+//! Arbitrary bytes as the global git config: `get_config_with_global` must never panic.
+//!
+//! Run with `cargo +nightly fuzz run get_config` from `submodule-hook-lib`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use submodule_hook_lib::config;
+
+fuzz_target!(|data: &[u8]| {
+    let dir = tempfile::tempdir().unwrap();
+    let global = dir.path().join("gitconfig");
+    std::fs::write(&global, data).unwrap();
+    // not a repository, the repository sources are skipped
+    let _ = config::get_config_with_global(dir.path(), Some(&global));
+});
@@ -0,0 +1,160 @@
+//! Cache of submodule statuses, stored in `.git/submodule-hook-cache`.
+//!
+//! The whole cache is invalidated when the index or `HEAD` of the repository change,
+//! a cached status being reused while the `HEAD` of the submodule is unchanged.
+
+use crate::config::ignore_policy_name;
+use git2::{Oid, Repository, SubmoduleIgnore, SubmoduleStatus};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+const CACHE_FILE_NAME: &str = "submodule-hook-cache";
+
+/// Cached status of a submodule, valid for its `HEAD` and ignore policy
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CachedStatus {
+    head: Oid,
+    ignore: &'static str,
+    status: SubmoduleStatus,
+}
+
+#[derive(Debug)]
+pub(crate) struct StatusCache {
+    path: PathBuf,
+    /// Index checksum and `HEAD` of the repository the statuses were computed for
+    key: String,
+    statuses: HashMap<String, CachedStatus>,
+    updated: bool,
+}
+
+/// Trailing SHA-1 checksum of the index file, as hex
+fn index_checksum(index_path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(index_path)?;
+    file.seek(SeekFrom::End(-20))?;
+    let mut checksum = [0u8; 20];
+    file.read_exact(&mut checksum)?;
+    Ok(checksum.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Parse a `<head> <status bits> <ignore policy> <name>` line
+fn parse_line(line: &str) -> Option<(String, CachedStatus)> {
+    let mut fields = line.splitn(4, ' ');
+    let head = Oid::from_str(fields.next()?).ok()?;
+    let status = SubmoduleStatus::from_bits_truncate(fields.next()?.parse().ok()?);
+    let ignore = match fields.next()? {
+        "untracked" => "untracked",
+        "dirty" => "dirty",
+        "all" => "all",
+        "none" => "none",
+        _ => return None,
+    };
+    let name = fields.next()?;
+    Some((
+        name.to_string(),
+        CachedStatus {
+            head,
+            ignore,
+            status,
+        },
+    ))
+}
+
+impl StatusCache {
+    /// Cache of `repo`, empty if missing or outdated, `None` if the index checksum is not available
+    pub(crate) fn load(repo: &Repository) -> Option<StatusCache> {
+        let index_path = repo.index().ok()?.path()?.to_path_buf();
+        let checksum = index_checksum(&index_path).ok()?;
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .map(|oid| oid.to_string())
+            .unwrap_or_default();
+        let mut cache = StatusCache {
+            path: repo.path().join(CACHE_FILE_NAME),
+            key: format!("{checksum} {head}"),
+            statuses: HashMap::new(),
+            updated: false,
+        };
+        if let Ok(content) = fs::read_to_string(&cache.path) {
+            let mut lines = content.lines();
+            if lines.next() == Some(cache.key.as_str()) {
+                cache.statuses = lines.filter_map(parse_line).collect();
+            } else {
+                debug!("index or HEAD changed, discarding submodule status cache");
+                cache.updated = true;
+            }
+        }
+        Some(cache)
+    }
+
+    /// Cached status of submodule `name`, if its `head` and `ignore` policy are unchanged
+    pub(crate) fn get(
+        &self,
+        name: &str,
+        head: Oid,
+        ignore: SubmoduleIgnore,
+    ) -> Option<SubmoduleStatus> {
+        self.statuses
+            .get(name)
+            .filter(|cached| cached.head == head && cached.ignore == ignore_policy_name(ignore))
+            .map(|cached| cached.status)
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        name: &str,
+        head: Oid,
+        ignore: SubmoduleIgnore,
+        status: SubmoduleStatus,
+    ) {
+        let cached = CachedStatus {
+            head,
+            ignore: ignore_policy_name(ignore),
+            status,
+        };
+        if self.statuses.insert(name.to_string(), cached) != Some(cached) {
+            self.updated = true;
+        }
+    }
+
+    /// Write the cache back, if updated
+    pub(crate) fn save(&self) -> io::Result<()> {
+        if !self.updated {
+            return Ok(());
+        }
+        let mut lines = vec![self.key.clone()];
+        for (name, cached) in &self.statuses {
+            lines.push(format!(
+                "{} {} {} {name}",
+                cached.head,
+                cached.status.bits(),
+                cached.ignore
+            ));
+        }
+        fs::write(&self.path, lines.join("\n") + "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cache_line() {
+        let head = "0123456789abcdef0123456789abcdef01234567";
+        let (name, cached) = parse_line(&format!("{head} 5 dirty vendor/my lib")).unwrap();
+        assert_eq!(name, "vendor/my lib");
+        assert_eq!(cached.head, Oid::from_str(head).unwrap());
+        assert_eq!(cached.ignore, "dirty");
+        assert_eq!(
+            cached.status,
+            SubmoduleStatus::IN_HEAD | SubmoduleStatus::IN_CONFIG
+        );
+        assert!(parse_line(&format!("{head} 5 sometimes vendor")).is_none());
+        assert!(parse_line("not-an-oid 5 none vendor").is_none());
+    }
+}
@@ -0,0 +1,5486 @@
+use crate::cache::StatusCache;
+use crate::display::Section;
+use git2::build::CheckoutBuilder;
+use git2::{
+    Cred, CredentialType, Direction, ErrorCode, FetchOptions, FileMode, ObjectType, Oid, Pathspec,
+    PathspecFlags, RemoteCallbacks, Repository, RepositoryState, Status, StatusOptions,
+    SubmoduleIgnore, SubmoduleStatus, SubmoduleUpdateOptions, Tree, TreeWalkMode, TreeWalkResult,
+};
+use glob::Pattern;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use thiserror::Error;
+use tracing::{debug, debug_span, error, info_span, warn};
+
+/// (De)serialize an optional `Oid` as its hex string
+mod optional_oid {
+    use git2::Oid;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(oid: &Option<Oid>, serializer: S) -> Result<S::Ok, S::Error> {
+        match oid {
+            Some(oid) => serializer.serialize_some(&oid.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Oid>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|hex| Oid::from_str(&hex).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// (De)serialize an `Oid` as its hex string
+mod oid_hex {
+    use git2::Oid;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(oid: &Oid, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&oid.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Oid, D::Error> {
+        Oid::from_str(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A submodule and the commits it points to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmoduleEntry {
+    pub name: String,
+    /// Path of the submodule relative to the parent repository working directory
+    #[serde(default)]
+    pub path: String,
+    /// Commit recorded in the parent repository `HEAD`
+    #[serde(with = "optional_oid")]
+    pub head_oid: Option<Oid>,
+    /// Commit recorded in the parent repository index
+    #[serde(with = "optional_oid")]
+    pub index_oid: Option<Oid>,
+    /// Commit checked out in the submodule working directory
+    #[serde(with = "optional_oid")]
+    pub workdir_head: Option<Oid>,
+    /// Branch tracked by the submodule (`submodule.<name>.branch`), `None` if pinned to a commit
+    pub branch: Option<String>,
+    /// Position of the staged commit relative to the submodule upstream branch, if computed
+    pub ahead_behind: Option<AheadBehind>,
+    /// Commit checked out in the submodule working directory, if looked up
+    pub head_commit: Option<CommitSummary>,
+    /// Files changed in the submodule working directory, if looked up
+    pub changed_files: Option<Vec<String>>,
+}
+
+/// First line of the message and author of a commit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitSummary {
+    pub summary: String,
+    pub author: String,
+}
+
+/// A submodule whose commit recorded in the index differs from the commit it is pinned to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedMismatch {
+    pub name: String,
+    /// Commit set by `submodulehook.<name>.pin`
+    #[serde(with = "oid_hex")]
+    pub pinned: Oid,
+    /// Commit recorded in the parent repository index, `None` if the submodule was removed
+    #[serde(with = "optional_oid")]
+    pub index_oid: Option<Oid>,
+}
+
+/// Commit counts of a submodule commit relative to a remote tracking branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AheadBehind {
+    pub ahead: usize,
+    pub behind: usize,
+    /// Remote tracking branch, e.g. `origin/main`
+    pub upstream: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmodulesDiagnostic {
+    pub modified_not_staged_submodules: Vec<SubmoduleEntry>,
+    pub modified_staged_submodules: Vec<SubmoduleEntry>,
+    pub uninitialized_submodules: Vec<SubmoduleEntry>,
+    pub newly_added_submodules: Vec<SubmoduleEntry>,
+    pub deleted_submodules: Vec<SubmoduleEntry>,
+    pub detached_head_submodules: Vec<SubmoduleEntry>,
+    /// Staged submodule commits not reachable from any remote tracking branch
+    pub unreachable_commit_submodules: Vec<SubmoduleEntry>,
+    /// Submodules whose staged commit is not the commit they are pinned to
+    pub pinned_version_mismatch: Vec<PinnedMismatch>,
+    /// Submodules configured in `.git/config` but missing from `.gitmodules`, e.g. left over
+    /// after being removed from `.gitmodules`
+    pub config_mismatch_submodules: Vec<String>,
+    /// Submodules whose path is tracked as a regular file by the parent repository index
+    pub path_conflict_submodules: Vec<String>,
+    /// Submodules defined by several sections of `.gitmodules`
+    pub duplicate_submodules: Vec<String>,
+    /// Staged submodule commits missing from the submodule repository, e.g. garbage-collected
+    pub missing_commit_submodules: Vec<String>,
+    /// Submodule names and URLs whose scheme is not allowed, see `check_submodule_urls`
+    pub insecure_url_submodules: Vec<(String, String)>,
+    /// Submodules with uncommitted changes to tracked files, see `check_uncommitted_changes`
+    pub uncommitted_changes_submodules: Vec<String>,
+    /// Staged submodules whose branch is ahead of its upstream, see `check_unpushed_commits`
+    pub unpushed_submodules: Vec<String>,
+    /// Submodule names, `.gitmodules` URLs and `.git/config` URLs that differ, e.g. after
+    /// `git submodule set-url` without `git submodule sync`
+    pub url_mismatch_submodules: Vec<(String, String, String)>,
+}
+
+impl SubmodulesDiagnostic {
+    /// Whether no submodule is reported
+    pub fn is_clean(&self) -> bool {
+        let SubmodulesDiagnostic {
+            modified_not_staged_submodules,
+            modified_staged_submodules,
+            uninitialized_submodules,
+            newly_added_submodules,
+            deleted_submodules,
+            detached_head_submodules,
+            unreachable_commit_submodules,
+            pinned_version_mismatch,
+            config_mismatch_submodules,
+            path_conflict_submodules,
+            duplicate_submodules,
+            missing_commit_submodules,
+            insecure_url_submodules,
+            uncommitted_changes_submodules,
+            unpushed_submodules,
+            url_mismatch_submodules,
+        } = self;
+        modified_not_staged_submodules.is_empty()
+            && modified_staged_submodules.is_empty()
+            && uninitialized_submodules.is_empty()
+            && newly_added_submodules.is_empty()
+            && deleted_submodules.is_empty()
+            && detached_head_submodules.is_empty()
+            && unreachable_commit_submodules.is_empty()
+            && pinned_version_mismatch.is_empty()
+            && config_mismatch_submodules.is_empty()
+            && path_conflict_submodules.is_empty()
+            && duplicate_submodules.is_empty()
+            && missing_commit_submodules.is_empty()
+            && insecure_url_submodules.is_empty()
+            && uncommitted_changes_submodules.is_empty()
+            && unpushed_submodules.is_empty()
+            && url_mismatch_submodules.is_empty()
+    }
+
+    /// Alias of `is_clean`
+    pub fn is_empty(&self) -> bool {
+        self.is_clean()
+    }
+
+    /// Names of the submodules reported, whatever the issue, a submodule with several issues
+    /// being listed once per issue
+    pub fn all_affected(&self) -> impl Iterator<Item = &str> {
+        // destructured so that new fields cannot be forgotten
+        let SubmodulesDiagnostic {
+            modified_not_staged_submodules,
+            modified_staged_submodules,
+            uninitialized_submodules,
+            newly_added_submodules,
+            deleted_submodules,
+            detached_head_submodules,
+            unreachable_commit_submodules,
+            pinned_version_mismatch,
+            config_mismatch_submodules,
+            path_conflict_submodules,
+            duplicate_submodules,
+            missing_commit_submodules,
+            insecure_url_submodules,
+            uncommitted_changes_submodules,
+            unpushed_submodules,
+            url_mismatch_submodules,
+        } = self;
+        let entries = [
+            modified_not_staged_submodules,
+            modified_staged_submodules,
+            uninitialized_submodules,
+            newly_added_submodules,
+            deleted_submodules,
+            detached_head_submodules,
+            unreachable_commit_submodules,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.name.as_str());
+        let names = [
+            config_mismatch_submodules,
+            path_conflict_submodules,
+            duplicate_submodules,
+            missing_commit_submodules,
+            uncommitted_changes_submodules,
+            unpushed_submodules,
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str);
+        entries
+            .chain(names)
+            .chain(pinned_version_mismatch.iter().map(|pin| pin.name.as_str()))
+            .chain(
+                insecure_url_submodules
+                    .iter()
+                    .map(|(name, _)| name.as_str()),
+            )
+            .chain(
+                url_mismatch_submodules
+                    .iter()
+                    .map(|(name, _, _)| name.as_str()),
+            )
+    }
+
+    /// Copy of this diagnostic keeping only the submodules whose name satisfies `predicate`,
+    /// e.g. to apply an allow list to an existing diagnostic
+    pub fn filter_by_name(&self, predicate: impl Fn(&str) -> bool) -> SubmodulesDiagnostic {
+        let SubmodulesDiagnostic {
+            modified_not_staged_submodules,
+            modified_staged_submodules,
+            uninitialized_submodules,
+            newly_added_submodules,
+            deleted_submodules,
+            detached_head_submodules,
+            unreachable_commit_submodules,
+            pinned_version_mismatch,
+            config_mismatch_submodules,
+            path_conflict_submodules,
+            duplicate_submodules,
+            missing_commit_submodules,
+            insecure_url_submodules,
+            uncommitted_changes_submodules,
+            unpushed_submodules,
+            url_mismatch_submodules,
+        } = self;
+        let entries = |entries: &[SubmoduleEntry]| {
+            entries
+                .iter()
+                .filter(|entry| predicate(&entry.name))
+                .cloned()
+                .collect()
+        };
+        let names = |names: &[String]| {
+            names
+                .iter()
+                .filter(|name| predicate(name))
+                .cloned()
+                .collect()
+        };
+        SubmodulesDiagnostic {
+            modified_not_staged_submodules: entries(modified_not_staged_submodules),
+            modified_staged_submodules: entries(modified_staged_submodules),
+            uninitialized_submodules: entries(uninitialized_submodules),
+            newly_added_submodules: entries(newly_added_submodules),
+            deleted_submodules: entries(deleted_submodules),
+            detached_head_submodules: entries(detached_head_submodules),
+            unreachable_commit_submodules: entries(unreachable_commit_submodules),
+            pinned_version_mismatch: pinned_version_mismatch
+                .iter()
+                .filter(|pin| predicate(&pin.name))
+                .cloned()
+                .collect(),
+            config_mismatch_submodules: names(config_mismatch_submodules),
+            path_conflict_submodules: names(path_conflict_submodules),
+            duplicate_submodules: names(duplicate_submodules),
+            missing_commit_submodules: names(missing_commit_submodules),
+            insecure_url_submodules: insecure_url_submodules
+                .iter()
+                .filter(|(name, _)| predicate(name))
+                .cloned()
+                .collect(),
+            uncommitted_changes_submodules: names(uncommitted_changes_submodules),
+            unpushed_submodules: names(unpushed_submodules),
+            url_mismatch_submodules: url_mismatch_submodules
+                .iter()
+                .filter(|(name, _, _)| predicate(name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Append the submodules reported by `other`, e.g. the diagnostic of a nested repository,
+    /// as `prefix/<name>` (names are kept as is if `prefix` is empty)
+    pub fn merge(&mut self, other: SubmodulesDiagnostic, prefix: &str) {
+        let prefixed = |entries: Vec<SubmoduleEntry>| {
+            entries.into_iter().map(move |entry| {
+                if prefix.is_empty() {
+                    entry
+                } else {
+                    SubmoduleEntry {
+                        name: format!("{prefix}/{}", entry.name),
+                        path: format!("{prefix}/{}", entry.path),
+                        ..entry
+                    }
+                }
+            })
+        };
+        self.modified_not_staged_submodules
+            .extend(prefixed(other.modified_not_staged_submodules));
+        self.modified_staged_submodules
+            .extend(prefixed(other.modified_staged_submodules));
+        self.uninitialized_submodules
+            .extend(prefixed(other.uninitialized_submodules));
+        self.newly_added_submodules
+            .extend(prefixed(other.newly_added_submodules));
+        self.deleted_submodules
+            .extend(prefixed(other.deleted_submodules));
+        self.detached_head_submodules
+            .extend(prefixed(other.detached_head_submodules));
+        self.unreachable_commit_submodules
+            .extend(prefixed(other.unreachable_commit_submodules));
+        let prefixed_name = |name: String| {
+            if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            }
+        };
+        self.pinned_version_mismatch
+            .extend(
+                other
+                    .pinned_version_mismatch
+                    .into_iter()
+                    .map(|mismatch| PinnedMismatch {
+                        name: prefixed_name(mismatch.name),
+                        ..mismatch
+                    }),
+            );
+        let prefixed_names = |names: Vec<String>| names.into_iter().map(prefixed_name);
+        self.config_mismatch_submodules
+            .extend(prefixed_names(other.config_mismatch_submodules));
+        self.path_conflict_submodules
+            .extend(prefixed_names(other.path_conflict_submodules));
+        self.duplicate_submodules
+            .extend(prefixed_names(other.duplicate_submodules));
+        self.missing_commit_submodules
+            .extend(prefixed_names(other.missing_commit_submodules));
+        self.insecure_url_submodules.extend(
+            other
+                .insecure_url_submodules
+                .into_iter()
+                .map(|(name, url)| (prefixed_name(name), url)),
+        );
+        self.uncommitted_changes_submodules
+            .extend(prefixed_names(other.uncommitted_changes_submodules));
+        self.unpushed_submodules
+            .extend(prefixed_names(other.unpushed_submodules));
+        self.url_mismatch_submodules
+            .extend(other.url_mismatch_submodules.into_iter().map(
+                |(name, gitmodules_url, config_url)| {
+                    let name = if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{prefix}/{name}")
+                    };
+                    (name, gitmodules_url, config_url)
+                },
+            ));
+    }
+}
+
+/// A submodule reported by a `SubmodulesDiagnostic`, with the issue it is reported for
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagnosticEntry {
+    pub name: String,
+    pub status: Section,
+}
+
+impl SubmoduleEntry {
+    /// Entry of the submodule `name` without any commit, branch or path
+    fn named(name: String) -> SubmoduleEntry {
+        SubmoduleEntry {
+            name,
+            path: String::new(),
+            head_oid: None,
+            index_oid: None,
+            workdir_head: None,
+            branch: None,
+            ahead_behind: None,
+            head_commit: None,
+            changed_files: None,
+        }
+    }
+}
+
+/// Every reported submodule in the order of the fields of `SubmodulesDiagnostic` (that of
+/// `Section`), a submodule with several issues being yielded once per issue
+impl IntoIterator for SubmodulesDiagnostic {
+    type Item = DiagnosticEntry;
+    type IntoIter = std::vec::IntoIter<DiagnosticEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // destructured so that new fields cannot be forgotten
+        let SubmodulesDiagnostic {
+            modified_not_staged_submodules,
+            modified_staged_submodules,
+            uninitialized_submodules,
+            newly_added_submodules,
+            deleted_submodules,
+            detached_head_submodules,
+            unreachable_commit_submodules,
+            pinned_version_mismatch,
+            config_mismatch_submodules,
+            path_conflict_submodules,
+            duplicate_submodules,
+            missing_commit_submodules,
+            insecure_url_submodules,
+            uncommitted_changes_submodules,
+            unpushed_submodules,
+            url_mismatch_submodules,
+        } = self;
+        let entry_names = |entries: Vec<SubmoduleEntry>| entries.into_iter().map(|e| e.name);
+        let sections: [(Vec<String>, Section); 16] = [
+            (
+                entry_names(modified_not_staged_submodules).collect(),
+                Section::NotStaged,
+            ),
+            (
+                entry_names(modified_staged_submodules).collect(),
+                Section::Staged,
+            ),
+            (
+                entry_names(uninitialized_submodules).collect(),
+                Section::Uninitialized,
+            ),
+            (
+                entry_names(newly_added_submodules).collect(),
+                Section::NewlyAdded,
+            ),
+            (entry_names(deleted_submodules).collect(), Section::Deleted),
+            (
+                entry_names(detached_head_submodules).collect(),
+                Section::DetachedHead,
+            ),
+            (
+                entry_names(unreachable_commit_submodules).collect(),
+                Section::Unreachable,
+            ),
+            (
+                pinned_version_mismatch
+                    .into_iter()
+                    .map(|pin| pin.name)
+                    .collect(),
+                Section::Pinned,
+            ),
+            (config_mismatch_submodules, Section::ConfigMismatch),
+            (path_conflict_submodules, Section::PathConflict),
+            (duplicate_submodules, Section::Duplicate),
+            (missing_commit_submodules, Section::MissingCommit),
+            (
+                insecure_url_submodules
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect(),
+                Section::InsecureUrl,
+            ),
+            (uncommitted_changes_submodules, Section::Uncommitted),
+            (unpushed_submodules, Section::Unpushed),
+            (
+                url_mismatch_submodules
+                    .into_iter()
+                    .map(|(name, _, _)| name)
+                    .collect(),
+                Section::UrlMismatch,
+            ),
+        ];
+        sections
+            .into_iter()
+            .flat_map(|(names, status)| {
+                names
+                    .into_iter()
+                    .map(move |name| DiagnosticEntry { name, status })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Adds each submodule to the field of its `status`, e.g. to build diagnostics in tests.
+///
+/// Only names are known: commits, branches, paths and URLs are left empty, pinned commits are
+/// set to zero.
+impl Extend<DiagnosticEntry> for SubmodulesDiagnostic {
+    fn extend<I: IntoIterator<Item = DiagnosticEntry>>(&mut self, iter: I) {
+        for DiagnosticEntry { name, status } in iter {
+            match status {
+                Section::NotStaged => self
+                    .modified_not_staged_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::Staged => self
+                    .modified_staged_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::Uninitialized => self
+                    .uninitialized_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::NewlyAdded => self
+                    .newly_added_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::Deleted => self.deleted_submodules.push(SubmoduleEntry::named(name)),
+                Section::DetachedHead => self
+                    .detached_head_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::Unreachable => self
+                    .unreachable_commit_submodules
+                    .push(SubmoduleEntry::named(name)),
+                Section::Pinned => self.pinned_version_mismatch.push(PinnedMismatch {
+                    name,
+                    pinned: Oid::zero(),
+                    index_oid: None,
+                }),
+                Section::ConfigMismatch => self.config_mismatch_submodules.push(name),
+                Section::PathConflict => self.path_conflict_submodules.push(name),
+                Section::Duplicate => self.duplicate_submodules.push(name),
+                Section::MissingCommit => self.missing_commit_submodules.push(name),
+                Section::InsecureUrl => self.insecure_url_submodules.push((name, String::new())),
+                Section::Uncommitted => self.uncommitted_changes_submodules.push(name),
+                Section::Unpushed => self.unpushed_submodules.push(name),
+                Section::UrlMismatch => {
+                    self.url_mismatch_submodules
+                        .push((name, String::new(), String::new()))
+                }
+            }
+        }
+    }
+}
+
+/// Exit code of the `check` subcommand: `0` if no submodule is modified, `1` if some are
+/// modified but not staged, `2` if some are staged, `3` if both
+pub fn check_exit_code(diagnostic: &SubmodulesDiagnostic) -> u8 {
+    let not_staged = !diagnostic.modified_not_staged_submodules.is_empty();
+    let staged = !diagnostic.modified_staged_submodules.is_empty();
+    u8::from(not_staged) | (u8::from(staged) << 1)
+}
+
+/// Default bound on submodule nesting when checking recursively
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// Whether `name` matches any entry of `allow_list`, entries being glob patterns
+/// (e.g. `vendor/*`, `deps/**`) or exact names
+pub fn is_allowed(name: &str, allow_list: &[String]) -> bool {
+    allow_list
+        .iter()
+        .any(|allowed| match Pattern::new(allowed) {
+            Ok(pattern) => pattern.matches(name),
+            Err(e) => {
+                warn!("invalid allow pattern `{allowed}`: {e}");
+                allowed == name
+            }
+        })
+}
+
+/// Whether the submodule `name` checked out at `path` is active, as git 2.20+ decides:
+/// `submodule.<name>.active` first, then the `submodule.active` pathspecs.
+///
+/// Unlike git, submodules are considered active when neither is set, even without
+/// `submodule.<name>.url`, so that uninitialized submodules are still reported.
+fn is_active(repo: &Repository, name: &str, path: &Path) -> bool {
+    let Ok(config) = repo.config() else {
+        return true;
+    };
+    if let Ok(active) = config.get_bool(&format!("submodule.{name}.active")) {
+        return active;
+    }
+    let mut specs = vec![];
+    if let Ok(mut entries) = config.entries(Some(r"^submodule\.active$")) {
+        while let Some(Ok(entry)) = entries.next() {
+            if let Some(value) = entry.value() {
+                specs.push(value.to_string());
+            }
+        }
+    }
+    if specs.is_empty() {
+        return true;
+    }
+    match Pathspec::new(specs.iter()) {
+        Ok(pathspec) => pathspec.matches_path(path, PathspecFlags::DEFAULT),
+        Err(e) => {
+            warn!("invalid submodule.active pathspec: {e}");
+            true
+        }
+    }
+}
+
+/// Names of the submodules of `config` having a `submodule.<name>.<key>` entry
+fn configured_submodules(config: &git2::Config, key: &str) -> Vec<String> {
+    let mut names = vec![];
+    if let Ok(mut entries) = config.entries(Some(&format!(r"^submodule\..+\.{key}$"))) {
+        while let Some(Ok(entry)) = entries.next() {
+            if let Some(name) = entry
+                .name()
+                .and_then(|entry_name| entry_name.strip_prefix("submodule."))
+                .and_then(|entry_name| entry_name.strip_suffix(&format!(".{key}")))
+            {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// `.gitmodules` of the working tree of `repo`, `None` if there is none
+fn gitmodules_config(repo: &Repository) -> Option<git2::Config> {
+    git2::Config::open(&repo.workdir()?.join(".gitmodules")).ok()
+}
+
+/// Submodules defined by several sections of the `.gitmodules` of `repo`, sorted: one of
+/// their `path` or `url` keys is repeated.
+fn duplicate_submodules(repo: &Repository) -> Vec<String> {
+    let Some(gitmodules) = gitmodules_config(repo) else {
+        return vec![];
+    };
+    let mut duplicates = vec![];
+    for key in ["path", "url"] {
+        let mut names = configured_submodules(&gitmodules, key);
+        names.sort();
+        for pair in names.windows(2) {
+            if pair[0] == pair[1] && !duplicates.contains(&pair[0]) {
+                debug!(submodule = %pair[0], "defined several times in .gitmodules");
+                duplicates.push(pair[0].clone());
+            }
+        }
+    }
+    duplicates.sort();
+    duplicates
+}
+
+/// Submodules initialized in the local config of `repo` (`submodule.<name>.url`) but missing
+/// from its `.gitmodules`, sorted.
+///
+/// Submodules of `.gitmodules` missing from the local config are not initialized, they are
+/// already reported as such.
+fn config_mismatch_submodules(repo: &Repository) -> Vec<String> {
+    if repo.workdir().is_none() {
+        return vec![];
+    }
+    let gitmodules = gitmodules_config(repo)
+        .map(|gitmodules| configured_submodules(&gitmodules, "path"))
+        .unwrap_or_default();
+    let Ok(local_config) = repo
+        .config()
+        .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+    else {
+        return vec![];
+    };
+    let mut mismatches: Vec<String> = configured_submodules(&local_config, "url")
+        .into_iter()
+        .filter(|name| !gitmodules.contains(name))
+        .collect();
+    mismatches.sort();
+    mismatches.dedup();
+    for name in &mismatches {
+        debug!(submodule = %name, "in .git/config but not in .gitmodules");
+    }
+    mismatches
+}
+
+/// Submodules whose URL in the `.gitmodules` of `repo` differs from the one of its local config
+/// (`submodule.<name>.url`), as `(name, .gitmodules URL, .git/config URL)` sorted by name.
+///
+/// Relative `.gitmodules` URLs are resolved as `git submodule sync` does, submodules missing
+/// from the local config are not initialized and already reported as such.
+fn url_mismatch_submodules(repo: &Repository) -> Vec<(String, String, String)> {
+    let Some(gitmodules) = gitmodules_config(repo) else {
+        return vec![];
+    };
+    let Ok(local_config) = repo
+        .config()
+        .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+    else {
+        return vec![];
+    };
+    let mut mismatches = vec![];
+    for name in configured_submodules(&gitmodules, "url") {
+        let key = format!("submodule.{name}.url");
+        let (Ok(gitmodules_url), Ok(config_url)) =
+            (gitmodules.get_string(&key), local_config.get_string(&key))
+        else {
+            continue;
+        };
+        let resolved_url = if url_scheme(&gitmodules_url) == "relative" {
+            resolve_relative_url(&superproject_url(repo), &gitmodules_url)
+        } else {
+            gitmodules_url.clone()
+        };
+        if resolved_url != config_url {
+            debug!(submodule = %name, %gitmodules_url, %config_url, "URL differs from .git/config");
+            mismatches.push((name, gitmodules_url, config_url));
+        }
+    }
+    mismatches.sort();
+    mismatches.dedup();
+    mismatches
+}
+
+/// URL relative submodule URLs of `repo` are resolved against: the URL of the remote of the
+/// current branch (`origin` by default), the working directory if it has none
+fn superproject_url(repo: &Repository) -> String {
+    let remote_name = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(String::from))
+        .and_then(|branch| {
+            repo.config()
+                .ok()?
+                .get_string(&format!("branch.{branch}.remote"))
+                .ok()
+        })
+        .unwrap_or_else(|| "origin".to_string());
+    repo.find_remote(&remote_name)
+        .ok()
+        .and_then(|remote| remote.url().map(String::from))
+        .or_else(|| Some(repo.workdir()?.to_str()?.to_string()))
+        .unwrap_or_default()
+}
+
+/// Resolve the relative submodule URL `url` (`./` or `../` prefixed) against `base`, each `../`
+/// removing the last path component of `base`, e.g. `../lib.git` against
+/// `git@example.com:org/app.git` is `git@example.com:org/lib.git`
+fn resolve_relative_url(base: &str, url: &str) -> String {
+    let mut base = base.trim_end_matches('/').to_string();
+    let mut separator = '/';
+    let mut url = url;
+    loop {
+        if let Some(rest) = url.strip_prefix("./") {
+            url = rest;
+        } else if let Some(rest) = url.strip_prefix("../") {
+            // `host:path` URLs separate the host with `:`
+            if let Some(index) = base.rfind(['/', ':']) {
+                separator = if base[index..].starts_with(':') {
+                    ':'
+                } else {
+                    '/'
+                };
+                base.truncate(index);
+            }
+            url = rest;
+        } else {
+            break;
+        }
+    }
+    format!("{base}{separator}{url}")
+}
+
+/// Error raised while checking submodules in strict mode
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum CheckError {
+    #[error("Unable to open repository: {0}")]
+    RepoOpenFailed(#[source] git2::Error),
+    #[error("`{}` is a bare repository, submodules can only be checked in a working tree", .0.display())]
+    BareRepository(PathBuf),
+    #[error("Submodule `{name}` path `{}` is tracked as a regular file, the repository may be corrupted", .path.display())]
+    PathConflict { name: String, path: PathBuf },
+    #[error("Submodules defined several times in `.gitmodules`: {}", .0.join(", "))]
+    DuplicateSubmodules(Vec<String>),
+    #[error("Submodule `{name}` URL `{url}` uses a disallowed scheme ({scheme})")]
+    DisallowedUrlScheme {
+        name: String,
+        url: String,
+        scheme: &'static str,
+    },
+    #[error("Staged commits not found in submodules: {}", .0.join(", "))]
+    MissingCommits(Vec<String>),
+    #[error("Failed to list submodules: {0}")]
+    SubmoduleListFailed(#[source] git2::Error),
+    #[error("Failed to get status of submodule `{name}`: {source}")]
+    SubmoduleStatusFailed { name: String, source: git2::Error },
+    #[error("Failed to stage submodule `{name}`: {source}")]
+    StageFailed { name: String, source: git2::Error },
+    #[error("Submodule `{}` is pinned to {pinned} but the index records {}", .name, .index_oid.map_or("no commit".to_string(), |oid| oid.to_string()))]
+    PinnedVersionMismatch {
+        name: String,
+        pinned: Oid,
+        index_oid: Option<Oid>,
+    },
+    #[error("Failed to write the diagnostic to `{}`: {source}", .path.display())]
+    ReportWriteFailed {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Set once the program is asked to stop, e.g. by a signal handler
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask running checks to stop, they return `CheckError::Interrupted` before inspecting the
+/// next submodule. Safe to call from a signal handler thread.
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether `interrupt` was called
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Notified once each top-level submodule is checked, with the number of submodules checked
+/// so far, the number of submodules to check and the name of the submodule just checked
+pub type ProgressCallback = Box<dyn Fn(usize, usize, &str) + Send + Sync>;
+
+static PROGRESS: RwLock<Option<ProgressCallback>> = RwLock::new(None);
+
+/// Set the callback reporting the progress of the checks, `None` to remove it
+pub fn set_progress_callback(callback: Option<ProgressCallback>) {
+    if let Ok(mut progress) = PROGRESS.write() {
+        *progress = callback;
+    }
+}
+
+/// Call the progress callback, if any
+fn report_progress(checked: usize, total: usize, name: &str) {
+    if let Ok(progress) = PROGRESS.read()
+        && let Some(progress) = progress.as_ref()
+    {
+        progress(checked, total, name);
+    }
+}
+
+/// Open the repository at `path`.
+///
+/// For the current directory (`.`), `GIT_DIR` and `GIT_WORK_TREE` are honored as git does,
+/// e.g. when the hook runs in a worktree or from `git --git-dir=<dir> --work-tree=<dir>`.
+pub fn open_repository(path: &Path) -> Result<Repository, git2::Error> {
+    open_repository_with(
+        path,
+        std::env::var_os("GIT_DIR"),
+        std::env::var_os("GIT_WORK_TREE"),
+    )
+}
+
+fn open_repository_with(
+    path: &Path,
+    git_dir: Option<OsString>,
+    work_tree: Option<OsString>,
+) -> Result<Repository, git2::Error> {
+    if path != Path::new(".") {
+        return Repository::open(path);
+    }
+    let repo = match git_dir {
+        Some(git_dir) => {
+            debug!("opening repository from GIT_DIR: {}", git_dir.display());
+            Repository::open(git_dir)?
+        }
+        None => Repository::open(path)?,
+    };
+    if let Some(work_tree) = work_tree {
+        debug!("using GIT_WORK_TREE: {}", work_tree.display());
+        repo.set_workdir(Path::new(&work_tree), false)?;
+    }
+    Ok(repo)
+}
+
+/// Git operation in progress in a repository, committing on behalf of the user
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitOperation {
+    Rebase,
+    InteractiveRebase,
+    Merge,
+    CherryPick,
+}
+
+impl GitOperation {
+    pub fn is_rebase(&self) -> bool {
+        matches!(self, GitOperation::Rebase | GitOperation::InteractiveRebase)
+    }
+}
+
+impl fmt::Display for GitOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GitOperation::Rebase => "a rebase",
+            GitOperation::InteractiveRebase => "an interactive rebase",
+            GitOperation::Merge => "a merge",
+            GitOperation::CherryPick => "a cherry-pick",
+        })
+    }
+}
+
+/// Rebase, merge or cherry-pick in progress in the repository at `path`, if any
+pub fn operation_in_progress(path: &Path) -> Option<GitOperation> {
+    let repo = open_repository(path).ok()?;
+    match repo.state() {
+        RepositoryState::Rebase
+        | RepositoryState::RebaseMerge
+        | RepositoryState::ApplyMailboxOrRebase => Some(GitOperation::Rebase),
+        RepositoryState::RebaseInteractive => Some(GitOperation::InteractiveRebase),
+        RepositoryState::Merge => Some(GitOperation::Merge),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence => {
+            Some(GitOperation::CherryPick)
+        }
+        _ => None,
+    }
+}
+
+/// Working directory of the repository at `path`, submodules being checked out relative to it,
+/// including in linked worktrees (`git worktree add`)
+fn repository_workdir(path: &Path) -> PathBuf {
+    let Ok(repo) = open_repository(path) else {
+        return path.to_path_buf();
+    };
+    if repo.is_worktree() {
+        debug!(
+            "{} is a worktree of {}",
+            path.display(),
+            repo.commondir().display()
+        );
+    }
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Options shared by the checks of a repository and of its nested submodules
+struct CheckOptions<'a> {
+    strict: bool,
+    allow_list: &'a [String],
+    ignore_policies: &'a HashMap<String, SubmoduleIgnore>,
+    /// Ignore policy of submodules not listed in `ignore_policies`
+    default_ignore: SubmoduleIgnore,
+    parallel: bool,
+    /// Reuse statuses cached in `.git/submodule-hook-cache` for top-level submodules
+    cache: bool,
+}
+
+impl CheckOptions<'_> {
+    /// Ignore policy of submodule `name`
+    fn ignore_policy(&self, name: &str) -> SubmoduleIgnore {
+        self.ignore_policies
+            .get(name)
+            .copied()
+            .unwrap_or(self.default_ignore)
+    }
+}
+
+/// Check submodules of the repository at `path`.
+///
+/// `ignore_policies` sets how deeply the working directory of a submodule is inspected,
+/// by submodule name, `default_ignore` being used for submodules not listed.
+///
+/// If `cache` is set, statuses are cached in `.git/submodule-hook-cache` and reused while
+/// the index and `HEAD` of the repository and the `HEAD` of the submodule are unchanged,
+/// file changes inside a submodule that do not move its `HEAD` being missed meanwhile.
+pub fn check_submodules(
+    strict: bool,
+    path: &Path,
+    allow_list: &[String],
+    ignore_policies: &HashMap<String, SubmoduleIgnore>,
+    default_ignore: SubmoduleIgnore,
+    cache: bool,
+) -> Result<Option<SubmodulesDiagnostic>, CheckError> {
+    let options = CheckOptions {
+        strict,
+        allow_list,
+        ignore_policies,
+        default_ignore,
+        parallel: false,
+        cache,
+    };
+    check_repository(&options, path, "", 1)
+}
+
+/// Same as `check_submodules` but also checks submodules of submodules, up to `max_depth` levels.
+///
+/// Nested submodules are reported as `outer/inner`.
+pub fn check_submodules_recursive(
+    strict: bool,
+    path: &Path,
+    allow_list: &[String],
+    ignore_policies: &HashMap<String, SubmoduleIgnore>,
+    default_ignore: SubmoduleIgnore,
+    max_depth: usize,
+    cache: bool,
+) -> Result<Option<SubmodulesDiagnostic>, CheckError> {
+    let options = CheckOptions {
+        strict,
+        allow_list,
+        ignore_policies,
+        default_ignore,
+        parallel: false,
+        cache,
+    };
+    check_repository(&options, path, "", max_depth)
+}
+
+/// Same as `check_submodules_recursive` but checks submodules in parallel, `max_depth` being
+/// `1` for a non-recursive check
+pub fn check_submodules_parallel(
+    strict: bool,
+    path: &Path,
+    allow_list: &[String],
+    ignore_policies: &HashMap<String, SubmoduleIgnore>,
+    default_ignore: SubmoduleIgnore,
+    max_depth: usize,
+    cache: bool,
+) -> Result<Option<SubmodulesDiagnostic>, CheckError> {
+    let options = CheckOptions {
+        strict,
+        allow_list,
+        ignore_policies,
+        default_ignore,
+        parallel: true,
+        cache,
+    };
+    check_repository(&options, path, "", max_depth)
+}
+
+/// Check submodules of the repository at `path`, `prefix` being the `/`-terminated
+/// path of this repository relative to the top-level one (empty for the top-level one)
+fn check_repository(
+    options: &CheckOptions,
+    path: &Path,
+    prefix: &str,
+    depth: usize,
+) -> Result<Option<SubmodulesDiagnostic>, CheckError> {
+    let span = info_span!("check_submodules", repo = %path.display());
+    let _entered = span.enter();
+    let repo = match open_repository(path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            error!("failed to open git repository");
+            if options.strict {
+                return Err(CheckError::RepoOpenFailed(e));
+            }
+            return Ok(None);
+        }
+    };
+    if repo.is_bare() {
+        warn!("bare repository, skipping submodule checks");
+        if options.strict {
+            return Err(CheckError::BareRepository(path.to_path_buf()));
+        }
+        return Ok(None);
+    }
+    // git2 fails to list the submodules of such a `.gitmodules`, only duplicates are reported
+    let duplicates = duplicate_submodules(&repo);
+    if !duplicates.is_empty() {
+        return Ok(Some(SubmodulesDiagnostic {
+            duplicate_submodules: duplicates,
+            ..SubmodulesDiagnostic::default()
+        }));
+    }
+    let submodules = match repo.submodules() {
+        Ok(submodules) => submodules,
+        Err(e) => {
+            error!("failed to list submodules");
+            if options.strict {
+                return Err(CheckError::SubmoduleListFailed(e));
+            }
+            return Ok(None);
+        }
+    };
+    // `None` if the index cannot be read, conflicts are then not detected
+    let index = repo.index().ok();
+    let mut path_conflicts = Vec::new();
+    let mut names = Vec::new();
+    for submodule in &submodules {
+        if let Some(name) = submodule.name() {
+            if is_allowed(&format!("{prefix}{name}"), options.allow_list) {
+                debug!("skipping allowed submodule: {prefix}{name}");
+                continue;
+            }
+            if !is_active(&repo, name, submodule.path()) {
+                debug!("skipping inactive submodule: {prefix}{name}");
+                continue;
+            }
+            if let Some(entry) = index
+                .as_ref()
+                .and_then(|index| index.get_path(submodule.path(), 0))
+                && entry.mode != u32::from(FileMode::Commit)
+            {
+                error!(submodule = name, "path is tracked as a regular file");
+                if options.strict {
+                    return Err(CheckError::PathConflict {
+                        name: format!("{prefix}{name}"),
+                        path: submodule.path().to_path_buf(),
+                    });
+                }
+                path_conflicts.push(String::from(name));
+                continue;
+            }
+            names.push(String::from(name));
+        } else {
+            warn!("submodule does not have a name");
+        }
+    }
+
+    // nested submodules statuses depend on the index of their parent submodule, not cached
+    let cache = if options.cache && prefix.is_empty() {
+        StatusCache::load(&repo).map(Mutex::new)
+    } else {
+        None
+    };
+    let checked = AtomicUsize::new(0);
+    let check = |repo: &Repository, name: &String| {
+        // rayon workers do not inherit the current span
+        let _entered = span.enter();
+        let diagnostic = check_submodule(options, repo, name, prefix, depth, cache.as_ref());
+        // nested submodules are part of the progress of their top-level submodule
+        if prefix.is_empty() {
+            let checked = checked.fetch_add(1, Ordering::SeqCst) + 1;
+            report_progress(checked, names.len(), name);
+        }
+        diagnostic
+    };
+    let diagnostics: Vec<Result<SubmodulesDiagnostic, CheckError>> = if options.parallel {
+        // `Repository` is not `Sync`: each worker thread opens its own handle
+        names
+            .par_iter()
+            .map_init(
+                || open_repository(path),
+                |worker_repo, name| match worker_repo {
+                    Ok(worker_repo) => check(worker_repo, name),
+                    Err(e) => Err(CheckError::RepoOpenFailed(git2::Error::new(
+                        e.code(),
+                        e.class(),
+                        e.message(),
+                    ))),
+                },
+            )
+            .collect()
+    } else {
+        names.iter().map(|name| check(&repo, name)).collect()
+    };
+
+    // nested findings are merged after the ones of this repository
+    let mut diagnostic = SubmodulesDiagnostic {
+        config_mismatch_submodules: config_mismatch_submodules(&repo),
+        url_mismatch_submodules: url_mismatch_submodules(&repo),
+        path_conflict_submodules: path_conflicts,
+        ..SubmodulesDiagnostic::default()
+    };
+    for submodule_diagnostic in diagnostics {
+        diagnostic.merge(submodule_diagnostic?, "");
+    }
+    if let Some(cache) = cache
+        && let Ok(cache) = cache.into_inner()
+        && let Err(e) = cache.save()
+    {
+        warn!("failed to write submodule status cache: {e}");
+    }
+    Ok(Some(diagnostic))
+}
+
+/// Repository checked out in a submodule, as inspected by the checks
+#[derive(Debug, Clone, Default)]
+pub struct SubmoduleCheckout {
+    pub head_detached: bool,
+    /// Whether `index_oid`, the commit recorded in the parent repository index, exists in the
+    /// submodule repository, `None` if no commit is recorded
+    pub has_index_commit: Option<bool>,
+    /// Working directory, in which nested submodules are checked
+    pub workdir: Option<PathBuf>,
+}
+
+/// Submodule operations of a repository the check of a submodule relies on, so that the check
+/// logic can be exercised without creating repositories
+pub trait RepositoryProvider {
+    /// Commits and branch of the submodule `name`, as reported in the diagnostic
+    fn submodule_entry(&self, name: &str) -> Result<SubmoduleEntry, git2::Error>;
+
+    /// Status of the submodule `name`, its working directory being inspected according to
+    /// `ignore`
+    fn submodule_status(
+        &self,
+        name: &str,
+        ignore: SubmoduleIgnore,
+    ) -> Result<SubmoduleStatus, git2::Error>;
+
+    /// Repository checked out in the submodule `name`, failing with `NotFound` if the
+    /// submodule directory does not contain a repository
+    fn submodule_checkout(
+        &self,
+        name: &str,
+        index_oid: Option<Oid>,
+    ) -> Result<SubmoduleCheckout, git2::Error>;
+}
+
+impl RepositoryProvider for Repository {
+    fn submodule_entry(&self, name: &str) -> Result<SubmoduleEntry, git2::Error> {
+        let submodule = self.find_submodule(name)?;
+        Ok(SubmoduleEntry {
+            name: String::from(name),
+            path: submodule.path().to_string_lossy().into_owned(),
+            head_oid: submodule.head_id(),
+            index_oid: submodule.index_id(),
+            workdir_head: submodule.workdir_id(),
+            branch: submodule.branch().map(String::from),
+            ahead_behind: None,
+            head_commit: None,
+            changed_files: None,
+        })
+    }
+
+    fn submodule_status(
+        &self,
+        name: &str,
+        ignore: SubmoduleIgnore,
+    ) -> Result<SubmoduleStatus, git2::Error> {
+        Repository::submodule_status(self, name, ignore)
+    }
+
+    fn submodule_checkout(
+        &self,
+        name: &str,
+        index_oid: Option<Oid>,
+    ) -> Result<SubmoduleCheckout, git2::Error> {
+        let inner_repo = self.find_submodule(name)?.open()?;
+        Ok(SubmoduleCheckout {
+            head_detached: inner_repo.head_detached().unwrap_or(false),
+            has_index_commit: index_oid.map(|oid| {
+                !matches!(inner_repo.find_commit(oid), Err(e) if e.code() == ErrorCode::NotFound)
+            }),
+            workdir: inner_repo.workdir().map(Path::to_path_buf),
+        })
+    }
+}
+
+/// Diagnostic of the single submodule `name` of `repo`, including its nested submodules
+/// while `depth > 1`
+fn check_submodule(
+    options: &CheckOptions,
+    repo: &(impl RepositoryProvider + ?Sized),
+    name: &str,
+    prefix: &str,
+    depth: usize,
+    cache: Option<&Mutex<StatusCache>>,
+) -> Result<SubmodulesDiagnostic, CheckError> {
+    if is_interrupted() {
+        return Err(CheckError::Interrupted);
+    }
+    let status_error = |source| CheckError::SubmoduleStatusFailed {
+        name: format!("{prefix}{name}"),
+        source,
+    };
+    let _span = debug_span!("check_submodule", submodule = %name).entered();
+    debug!("checking submodule");
+    let entry = repo.submodule_entry(name).map_err(status_error)?;
+    let mut diagnostic = SubmodulesDiagnostic::default();
+    // `NotFound` if the submodule directory does not contain a repository
+    let checkout = repo.submodule_checkout(name, entry.index_oid);
+    let not_initialized = || {
+        warn!(
+            "submodule `{prefix}{name}` is not initialized, run `git submodule update --init {}`",
+            entry.path
+        );
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        diagnostic.uninitialized_submodules.push(entry.clone());
+        diagnostic
+    };
+    let is_not_found = matches!(&checkout, Err(e) if e.code() == ErrorCode::NotFound);
+    let ignore = options.ignore_policy(&format!("{prefix}{name}"));
+    let cache = cache.zip(entry.workdir_head);
+    let cached = cache.and_then(|(cache, head)| cache.lock().ok()?.get(name, head, ignore));
+    let status = match cached {
+        Some(status) => {
+            debug!("using cached status of {name}");
+            status
+        }
+        None => match repo.submodule_status(name, ignore) {
+            Ok(status) => {
+                if let Some((cache, head)) = cache
+                    && let Ok(mut cache) = cache.lock()
+                {
+                    cache.insert(name, head, ignore, status);
+                }
+                status
+            }
+            Err(_) if is_not_found => return Ok(not_initialized()),
+            Err(source) => return Err(status_error(source)),
+        },
+    };
+    if status.contains(SubmoduleStatus::IN_WD)
+        && status.is_wd_deleted()
+        && !status.is_index_deleted()
+        && is_not_found
+    {
+        // the directory exists but is not a repository, e.g. it was created by hand
+        return Ok(not_initialized());
+    }
+    if status.is_wd_modified() {
+        debug!("modified but not staged");
+        diagnostic
+            .modified_not_staged_submodules
+            .push(entry.clone());
+    }
+    if status.is_index_modified() {
+        debug!("modified and staged");
+        diagnostic.modified_staged_submodules.push(entry.clone());
+    }
+    if status.is_wd_uninitialized() {
+        debug!("not initialized");
+        diagnostic.uninitialized_submodules.push(entry.clone());
+    }
+    if status.is_index_added() {
+        debug!("newly added to the index");
+        diagnostic.newly_added_submodules.push(entry.clone());
+    }
+    if status.is_index_deleted() {
+        debug!("removed from the index");
+        diagnostic.deleted_submodules.push(entry.clone());
+    }
+    let checkout = checkout.ok();
+    if let Some(checkout) = &checkout
+        && checkout.head_detached
+    {
+        debug!("in detached HEAD state");
+        diagnostic.detached_head_submodules.push(entry.clone());
+    }
+    if (status.is_index_modified() || status.is_index_added())
+        && let Some(checkout) = &checkout
+        && checkout.has_index_commit == Some(false)
+    {
+        debug!(commit = ?entry.index_oid, "staged commit not found in the submodule repository");
+        diagnostic
+            .missing_commit_submodules
+            .push(String::from(name));
+    }
+    if depth > 1
+        && let Some(inner_path) = checkout.as_ref().and_then(|c| c.workdir.as_deref())
+    {
+        let inner_prefix = format!("{prefix}{name}/");
+        if let Some(nested) = check_repository(options, inner_path, &inner_prefix, depth - 1)? {
+            diagnostic.merge(nested, name);
+        }
+    }
+    Ok(diagnostic)
+}
+
+/// Ahead/behind counts of `oid` relative to the upstream of `branch` on its configured remote
+/// (`origin` if none), or of the checked out branch when the submodule does not track any branch
+#[cfg(feature = "ahead-behind")]
+fn ahead_behind(repo: &Repository, oid: Oid, branch: Option<&str>) -> Option<AheadBehind> {
+    let upstream_name = match branch {
+        Some(branch) => {
+            let remote = repo
+                .config()
+                .ok()
+                .and_then(|config| config.get_string(&format!("branch.{branch}.remote")).ok())
+                .unwrap_or_else(|| "origin".to_string());
+            format!("refs/remotes/{remote}/{branch}")
+        }
+        None => {
+            let head = repo.head().ok()?;
+            let upstream = repo.branch_upstream_name(head.name()?).ok()?;
+            upstream.as_str()?.to_string()
+        }
+    };
+    let upstream = repo.refname_to_id(&upstream_name).ok()?;
+    let (ahead, behind) = repo.graph_ahead_behind(oid, upstream).ok()?;
+    Some(AheadBehind {
+        ahead,
+        behind,
+        upstream: upstream_name
+            .trim_start_matches("refs/remotes/")
+            .to_string(),
+    })
+}
+
+/// Compute ahead/behind counts of the staged commit of modified and staged submodules of the
+/// repository at `path`, relative to the last fetched state of their upstream branch
+#[cfg(feature = "ahead-behind")]
+pub fn add_ahead_behind(diagnostic: &mut SubmodulesDiagnostic, path: &Path) {
+    let workdir = repository_workdir(path);
+    for entry in &mut diagnostic.modified_staged_submodules {
+        let Some(index_oid) = entry.index_oid else {
+            continue;
+        };
+        match Repository::open(workdir.join(&entry.path)) {
+            Ok(repo) => {
+                entry.ahead_behind = ahead_behind(&repo, index_oid, entry.branch.as_deref());
+                debug!("{} ahead/behind: {:?}", entry.name, entry.ahead_behind);
+            }
+            Err(e) => warn!("failed to open submodule {}: {e}", entry.name),
+        }
+    }
+}
+
+/// Stage the commit checked out in modified but not staged submodules of the repository at `path`,
+/// as `git add <path>` does, and remove them from `diagnostic`.
+///
+/// Returns the names of the staged submodules. Submodules that cannot be staged are kept in
+/// `diagnostic`, or reported as an error in `strict` mode.
+pub fn stage_modified_submodules(
+    diagnostic: &mut SubmodulesDiagnostic,
+    path: &Path,
+    strict: bool,
+) -> Result<Vec<String>, CheckError> {
+    let repo = open_repository(path).map_err(CheckError::RepoOpenFailed)?;
+    let mut index = repo.index().map_err(CheckError::RepoOpenFailed)?;
+    let mut staged = vec![];
+    let mut not_staged = vec![];
+    for entry in std::mem::take(&mut diagnostic.modified_not_staged_submodules) {
+        match index.add_path(Path::new(&entry.path)) {
+            Ok(()) => {
+                debug!("staged {}", entry.name);
+                staged.push(entry.name);
+            }
+            Err(source) if strict => {
+                return Err(CheckError::StageFailed {
+                    name: entry.name,
+                    source,
+                });
+            }
+            Err(e) => {
+                warn!("failed to stage submodule {}: {e}", entry.name);
+                not_staged.push(entry);
+            }
+        }
+    }
+    diagnostic.modified_not_staged_submodules = not_staged;
+    if is_interrupted() {
+        // submodules were only staged in memory, leave the index file untouched
+        return Err(CheckError::Interrupted);
+    }
+    if !staged.is_empty() {
+        index.write().map_err(|source| CheckError::StageFailed {
+            name: staged.join(", "),
+            source,
+        })?;
+    }
+    Ok(staged)
+}
+
+/// Repair applied to submodules by `fix_submodules`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixAction {
+    /// Stage the commit checked out in modified but not staged submodules, as `git add <path>`
+    Stage,
+    /// Restore the `HEAD` commit of staged submodules in the index, as
+    /// `git restore --staged <path>`
+    Unstage,
+    /// Clone and check out uninitialized submodules, as `git submodule update --init <path>`
+    Init,
+}
+
+impl fmt::Display for FixAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixAction::Stage => write!(f, "stage"),
+            FixAction::Unstage => write!(f, "unstage"),
+            FixAction::Init => write!(f, "initialize"),
+        }
+    }
+}
+
+/// Repair applied, or that would be applied, to a submodule
+#[derive(Debug)]
+pub struct FixReport {
+    pub action: FixAction,
+    pub name: String,
+    pub result: Result<(), git2::Error>,
+}
+
+/// Apply `actions` to the submodules of the repository at `path` they apply to, whether a
+/// submodule needs a repair being decided from its status before any repair is applied.
+///
+/// With `dry_run` nothing is changed, the reports listing what would be done.
+pub fn fix_submodules(
+    path: &Path,
+    actions: &[FixAction],
+    dry_run: bool,
+) -> anyhow::Result<Vec<FixReport>> {
+    let repo = open_repository(path)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let mut index = repo.index()?;
+    let mut reports = vec![];
+    for mut submodule in repo.submodules()? {
+        let Some(name) = submodule.name().map(String::from) else {
+            warn!("submodule does not have a name");
+            continue;
+        };
+        let status = repo.submodule_status(&name, SubmoduleIgnore::None)?;
+        let uninitialized = status.is_wd_uninitialized()
+            || matches!(submodule.open(), Err(e) if e.code() == ErrorCode::NotFound);
+        let submodule_path = submodule.path().to_path_buf();
+        for &action in actions {
+            let applies = match action {
+                FixAction::Stage => !uninitialized && status.is_wd_modified(),
+                FixAction::Unstage => status.is_index_modified() || status.is_index_added(),
+                FixAction::Init => uninitialized,
+            };
+            if !applies {
+                continue;
+            }
+            let result = if dry_run {
+                Ok(())
+            } else {
+                debug!(submodule = %name, "{action}");
+                match action {
+                    FixAction::Stage => {
+                        index.add_path(&submodule_path).and_then(|()| index.write())
+                    }
+                    FixAction::Unstage => {
+                        // without a `HEAD` commit the submodule is removed from the index
+                        repo.reset_default(
+                            head.as_ref().map(|commit| commit.as_object()),
+                            [&submodule_path],
+                        )
+                        .and_then(|()| index.read(true))
+                    }
+                    FixAction::Init => submodule.update(true, None),
+                }
+            };
+            reports.push(FixReport {
+                action,
+                name: name.clone(),
+                result,
+            });
+        }
+    }
+    Ok(reports)
+}
+
+/// How `update_submodules` updated a submodule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateKind {
+    /// Cloned and checked out, as `git submodule update --init <path>`
+    Init,
+    /// Checked out the commit recorded in the index, as `git submodule update <path>`
+    Checkout,
+    /// Checked out the tip of the remote branch, as `git submodule update --remote <path>`
+    Remote,
+}
+
+impl fmt::Display for UpdateKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateKind::Init => write!(f, "initialize"),
+            UpdateKind::Checkout => write!(f, "check out"),
+            UpdateKind::Remote => write!(f, "update from remote"),
+        }
+    }
+}
+
+/// Update applied to a submodule, with the commit checked out if it succeeded
+#[derive(Debug)]
+pub struct UpdateReport {
+    pub kind: UpdateKind,
+    /// Path of the submodule relative to the repository being updated
+    pub name: String,
+    pub result: Result<Oid, git2::Error>,
+}
+
+/// Update the submodules of the repository at `path` as `git submodule update --init` does:
+/// uninitialized submodules are cloned and submodules whose checked out commit drifted from the
+/// one recorded in the index are checked out at it. Submodules already up to date are left
+/// untouched.
+///
+/// With `remote` the initialized submodules are instead fetched and checked out at the tip of
+/// their remote branch (`submodule.<name>.branch`, the default branch of the remote if unset),
+/// and with `recursive` the submodules of submodules are updated too.
+///
+/// `on_update` is called as soon as each submodule is updated, a failed update does not stop the
+/// others.
+pub fn update_submodules(
+    path: &Path,
+    recursive: bool,
+    remote: bool,
+    mut on_update: impl FnMut(UpdateReport),
+) -> anyhow::Result<()> {
+    let repo = open_repository(path)?;
+    update_repository_submodules(&repo, "", recursive, remote, &mut on_update)
+}
+
+fn update_repository_submodules(
+    repo: &Repository,
+    prefix: &str,
+    recursive: bool,
+    remote: bool,
+    on_update: &mut dyn FnMut(UpdateReport),
+) -> anyhow::Result<()> {
+    let config = repo.config()?;
+    for mut submodule in repo.submodules()? {
+        let Some(name) = submodule.name().map(String::from) else {
+            warn!("submodule does not have a name");
+            continue;
+        };
+        let status = repo.submodule_status(&name, SubmoduleIgnore::None)?;
+        let uninitialized = status.is_wd_uninitialized()
+            || matches!(submodule.open(), Err(e) if e.code() == ErrorCode::NotFound);
+        let kind = if uninitialized {
+            Some(UpdateKind::Init)
+        } else if remote {
+            Some(UpdateKind::Remote)
+        } else if status.is_wd_modified() {
+            Some(UpdateKind::Checkout)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            debug!(submodule = %name, "{kind}");
+            let result = match kind {
+                UpdateKind::Init | UpdateKind::Checkout => {
+                    let mut fetch_options = FetchOptions::new();
+                    fetch_options.remote_callbacks(remote_callbacks(&config));
+                    let mut update_options = SubmoduleUpdateOptions::new();
+                    update_options.fetch(fetch_options);
+                    submodule
+                        .update(kind == UpdateKind::Init, Some(&mut update_options))
+                        .and_then(|()| submodule.open()?.refname_to_id("HEAD"))
+                }
+                UpdateKind::Remote => submodule.open().and_then(|submodule_repo| {
+                    checkout_remote_branch(&submodule_repo, submodule.branch())
+                }),
+            };
+            on_update(UpdateReport {
+                kind,
+                name: format!("{prefix}{}", submodule.path().display()),
+                result,
+            });
+        }
+        if recursive && let Ok(submodule_repo) = submodule.open() {
+            let prefix = format!("{prefix}{}/", submodule.path().display());
+            update_repository_submodules(&submodule_repo, &prefix, recursive, remote, on_update)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch the remote `origin` of `repo` and check out the tip of its `branch` (the default
+/// branch of the remote if `None`) as a detached `HEAD`
+fn checkout_remote_branch(repo: &Repository, branch: Option<&str>) -> Result<Oid, git2::Error> {
+    let config = repo.config()?;
+    let mut remote = repo.find_remote("origin")?;
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(&config));
+    remote.fetch::<&str>(&[], Some(&mut fetch_options), None)?;
+    let reference = match branch {
+        Some(branch) => format!("refs/remotes/origin/{branch}"),
+        None => {
+            let default_branch = remote.default_branch()?;
+            let default_branch = default_branch.as_str().unwrap_or("refs/heads/master");
+            default_branch.replacen("refs/heads/", "refs/remotes/origin/", 1)
+        }
+    };
+    let oid = repo.refname_to_id(&reference)?;
+    let commit = repo.find_commit(oid)?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.set_head_detached(oid)?;
+    Ok(oid)
+}
+
+/// Compare the commit recorded in the index of the repository at `path` with the commit each
+/// submodule of `pins` is pinned to, mismatches being reported in `pinned_version_mismatch`.
+///
+/// In strict mode the first mismatch is an error.
+pub fn check_pinned_commits(
+    diagnostic: &mut SubmodulesDiagnostic,
+    path: &Path,
+    pins: &HashMap<String, Oid>,
+    strict: bool,
+) -> Result<(), CheckError> {
+    if pins.is_empty() {
+        return Ok(());
+    }
+    let repo = open_repository(path).map_err(CheckError::RepoOpenFailed)?;
+    let mut pins: Vec<(&String, &Oid)> = pins.iter().collect();
+    pins.sort();
+    for (name, pinned) in pins {
+        let index_oid = match repo.find_submodule(name) {
+            Ok(submodule) => submodule.index_id(),
+            Err(e) if e.code() == ErrorCode::NotFound => None,
+            Err(source) => {
+                return Err(CheckError::SubmoduleStatusFailed {
+                    name: name.clone(),
+                    source,
+                });
+            }
+        };
+        if index_oid == Some(*pinned) {
+            continue;
+        }
+        debug!(submodule = %name, pinned = %pinned, "index records {index_oid:?}, not the pinned commit");
+        let mismatch = PinnedMismatch {
+            name: name.clone(),
+            pinned: *pinned,
+            index_oid,
+        };
+        if strict {
+            return Err(CheckError::PinnedVersionMismatch {
+                name: mismatch.name,
+                pinned: mismatch.pinned,
+                index_oid: mismatch.index_oid,
+            });
+        }
+        diagnostic.pinned_version_mismatch.push(mismatch);
+    }
+    Ok(())
+}
+
+/// Scheme of a submodule URL: `https`, `http`, `ssh` (including scp-like `git@host:path`),
+/// `git`, `file`, `relative` (`./` or `../`), `local` (absolute path) or `other`
+pub fn url_scheme(url: &str) -> &'static str {
+    if url.starts_with("./") || url.starts_with("../") {
+        return "relative";
+    }
+    if url.starts_with('/') {
+        return "local";
+    }
+    if let Some((scheme, _)) = url.split_once("://") {
+        return match scheme.to_ascii_lowercase().as_str() {
+            "https" => "https",
+            "http" => "http",
+            "ssh" | "git+ssh" | "ssh+git" => "ssh",
+            "git" => "git",
+            "file" => "file",
+            _ => "other",
+        };
+    }
+    // scp-like syntax `[user@]host:path`, a single letter being a Windows drive
+    match url.split_once(':') {
+        Some((host, _)) if host.len() > 1 && !host.contains('/') => "ssh",
+        _ => "other",
+    }
+}
+
+/// Report the submodules of the repository at `path` whose URL scheme (see `url_scheme`) is
+/// not in `allowed_schemes` in `insecure_url_submodules`, nothing is checked if it is empty
+pub fn check_submodule_urls(
+    diagnostic: &mut SubmodulesDiagnostic,
+    path: &Path,
+    allowed_schemes: &[String],
+    strict: bool,
+) -> Result<(), CheckError> {
+    if allowed_schemes.is_empty() {
+        return Ok(());
+    }
+    let repo = open_repository(path).map_err(CheckError::RepoOpenFailed)?;
+    let submodules = repo.submodules().map_err(CheckError::SubmoduleListFailed)?;
+    for submodule in &submodules {
+        let (Some(name), Some(url)) = (submodule.name(), submodule.url()) else {
+            continue;
+        };
+        let scheme = url_scheme(url);
+        if allowed_schemes.iter().any(|allowed| allowed == scheme) {
+            continue;
+        }
+        debug!(submodule = name, url, scheme, "URL scheme not allowed");
+        if strict {
+            return Err(CheckError::DisallowedUrlScheme {
+                name: name.to_string(),
+                url: url.to_string(),
+                scheme,
+            });
+        }
+        diagnostic
+            .insecure_url_submodules
+            .push((name.to_string(), url.to_string()));
+    }
+    Ok(())
+}
+
+/// Look up the commit checked out in modified but not staged submodules of the repository
+/// at `path`, to remind the user what was committed in them
+pub fn add_commit_summaries(diagnostic: &mut SubmodulesDiagnostic, path: &Path) {
+    let workdir = repository_workdir(path);
+    for entry in &mut diagnostic.modified_not_staged_submodules {
+        let Some(workdir_head) = entry.workdir_head else {
+            continue;
+        };
+        match Repository::open(workdir.join(&entry.path)) {
+            Ok(repo) => match repo.find_commit(workdir_head) {
+                Ok(commit) => {
+                    entry.head_commit = Some(CommitSummary {
+                        summary: commit.summary().unwrap_or_default().to_string(),
+                        author: commit.author().name().unwrap_or_default().to_string(),
+                    });
+                }
+                Err(e) => warn!(
+                    "failed to find commit {workdir_head} of {}: {e}",
+                    entry.name
+                ),
+            },
+            Err(e) => warn!("failed to open submodule {}: {e}", entry.name),
+        }
+    }
+}
+
+/// List the files changed in the working directory of modified but not staged submodules of
+/// the repository at `path`, untracked files included and ignored files excluded
+pub fn add_changed_files(diagnostic: &mut SubmodulesDiagnostic, path: &Path) {
+    let workdir = repository_workdir(path);
+    for entry in &mut diagnostic.modified_not_staged_submodules {
+        let repo = match Repository::open(workdir.join(&entry.path)) {
+            Ok(repo) => repo,
+            Err(e) => {
+                warn!("failed to open submodule {}: {e}", entry.name);
+                continue;
+            }
+        };
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        match repo.statuses(Some(&mut options)) {
+            Ok(statuses) => {
+                entry.changed_files = Some(
+                    statuses
+                        .iter()
+                        .filter_map(|status| status.path().map(String::from))
+                        .collect(),
+                );
+            }
+            Err(e) => warn!("failed to list changed files of {}: {e}", entry.name),
+        }
+    }
+}
+
+/// Report the initialized submodules of the repository at `path` with uncommitted changes to
+/// tracked files (modified, deleted or staged, untracked files are not changes) in
+/// `uncommitted_changes_submodules`, submodules matching `allow_list` being skipped
+pub fn check_uncommitted_changes(
+    diagnostic: &mut SubmodulesDiagnostic,
+    path: &Path,
+    allow_list: &[String],
+) {
+    let repo = match open_repository(path) {
+        Ok(repo) => repo,
+        Err(e) => {
+            warn!("failed to open repository: {e}");
+            return;
+        }
+    };
+    let submodules = match repo.submodules() {
+        Ok(submodules) => submodules,
+        Err(e) => {
+            warn!("failed to list submodules: {e}");
+            return;
+        }
+    };
+    let changes = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE
+        | Status::WT_MODIFIED
+        | Status::WT_DELETED
+        | Status::WT_RENAMED
+        | Status::WT_TYPECHANGE;
+    for submodule in &submodules {
+        let Some(name) = submodule.name() else {
+            continue;
+        };
+        if is_allowed(name, allow_list) {
+            continue;
+        }
+        // uninitialized submodules are reported by `check_submodules`
+        let Ok(submodule_repo) = submodule.open() else {
+            continue;
+        };
+        let mut options = StatusOptions::new();
+        options.include_untracked(false).include_ignored(false);
+        match submodule_repo.statuses(Some(&mut options)) {
+            Ok(statuses)
+                if statuses
+                    .iter()
+                    .any(|status| status.status().intersects(changes)) =>
+            {
+                debug!(submodule = name, "uncommitted changes");
+                diagnostic
+                    .uncommitted_changes_submodules
+                    .push(name.to_string());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("failed to get status of files of {name}: {e}"),
+        }
+    }
+}
+
+/// Whether `oid` is one of, or an ancestor of, the remote tracking branches of `repo`
+fn is_on_remote(repo: &Repository, oid: Oid) -> anyhow::Result<bool> {
+    for reference in repo.references_glob("refs/remotes/*")? {
+        if let Some(remote_oid) = reference?.target()
+            && (remote_oid == oid || repo.graph_descendant_of(remote_oid, oid)?)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Report modified and staged submodules of the repository at `path` whose staged commit is not
+/// reachable from any remote tracking branch of the submodule, as of its last fetch.
+///
+/// Such commits cannot be fetched by others once the parent repository is pushed.
+pub fn check_unreachable_commits(diagnostic: &mut SubmodulesDiagnostic, path: &Path) {
+    let workdir = repository_workdir(path);
+    for entry in &diagnostic.modified_staged_submodules {
+        let Some(index_oid) = entry.index_oid else {
+            continue;
+        };
+        let on_remote = Repository::open(workdir.join(&entry.path))
+            .map_err(anyhow::Error::from)
+            .and_then(|repo| is_on_remote(&repo, index_oid));
+        match on_remote {
+            Ok(true) => {}
+            Ok(false) => {
+                debug!("{} commit {index_oid} is not on any remote", entry.name);
+                diagnostic.unreachable_commit_submodules.push(entry.clone());
+            }
+            Err(e) => warn!("failed to look up remotes of submodule {}: {e}", entry.name),
+        }
+    }
+}
+
+/// Whether the branch checked out in the repository at `path` has commits not reachable from
+/// its upstream branch, `false` in detached HEAD state or without upstream
+fn has_unpushed_commits(path: &Path) -> anyhow::Result<bool> {
+    let repo = Repository::open(path)?;
+    if repo.head_detached()? {
+        return Ok(false);
+    }
+    let head = repo.head()?;
+    let Some(head_oid) = head.target() else {
+        return Ok(false);
+    };
+    let upstream = match git2::Branch::wrap(head).upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(upstream_oid) = upstream.get().target() else {
+        return Ok(false);
+    };
+    Ok(upstream_oid != head_oid && !repo.graph_descendant_of(upstream_oid, head_oid)?)
+}
+
+/// Report modified and staged submodules of the repository at `path` whose checked out branch
+/// has commits not pushed to its upstream branch, as of its last fetch.
+///
+/// Submodules in detached HEAD state or whose branch has no upstream are not reported, see
+/// `check_unreachable_commits` for them.
+pub fn check_unpushed_commits(diagnostic: &mut SubmodulesDiagnostic, path: &Path) {
+    let workdir = repository_workdir(path);
+    for entry in &diagnostic.modified_staged_submodules {
+        match has_unpushed_commits(&workdir.join(&entry.path)) {
+            Ok(true) => {
+                debug!(submodule = %entry.name, "unpushed commits");
+                diagnostic.unpushed_submodules.push(entry.name.clone());
+            }
+            Ok(false) => {}
+            Err(e) => warn!(
+                "failed to look up upstream of submodule {}: {e}",
+                entry.name
+            ),
+        }
+    }
+}
+
+/// A ref being pushed, as read by a `pre-push` hook from stdin
+#[derive(Debug, PartialEq)]
+pub struct PushedRef {
+    pub local_ref: String,
+    pub local_oid: Oid,
+    pub remote_ref: String,
+    pub remote_oid: Oid,
+}
+
+/// Parse `<local ref> <local sha1> <remote ref> <remote sha1>` lines, ignoring invalid ones
+pub fn parse_pushed_refs(input: &str) -> Vec<PushedRef> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [local_ref, local_oid, remote_ref, remote_oid] => Some(PushedRef {
+                    local_ref: local_ref.to_string(),
+                    local_oid: Oid::from_str(local_oid).ok()?,
+                    remote_ref: remote_ref.to_string(),
+                    remote_oid: Oid::from_str(remote_oid).ok()?,
+                }),
+                _ => {
+                    warn!("invalid pre-push line: {line}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Submodule commits (gitlinks) recorded in the tree of `commit`, as `(path, oid)`
+fn gitlinks(repo: &Repository, commit: Oid) -> anyhow::Result<Vec<(String, Oid)>> {
+    let tree = repo.find_commit(commit)?.tree()?;
+    let mut gitlinks = vec![];
+    tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(ObjectType::Commit)
+            && let Some(name) = entry.name()
+        {
+            gitlinks.push((format!("{root}{name}"), entry.id()));
+        }
+        TreeWalkResult::Ok
+    })?;
+    Ok(gitlinks)
+}
+
+/// Submodule commits recorded by the commits of `pushed_ref` that are not on the remote yet:
+/// the gitlinks of the pushed commit and those updated by every outgoing commit, i.e. not
+/// reachable from the previous remote value of the ref nor from any remote tracking branch.
+fn outgoing_gitlinks(
+    repo: &Repository,
+    pushed_ref: &PushedRef,
+) -> anyhow::Result<Vec<(String, Oid)>> {
+    let mut gitlinks = gitlinks(repo, pushed_ref.local_oid)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(pushed_ref.local_oid)?;
+    // the previous remote value is unknown locally if someone else pushed since the last fetch
+    if !pushed_ref.remote_oid.is_zero() && repo.find_commit(pushed_ref.remote_oid).is_ok() {
+        revwalk.hide(pushed_ref.remote_oid)?;
+    }
+    revwalk.hide_glob("refs/remotes/*")?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for change in gitlink_changes(&diff) {
+            if let Some(new_oid) = change.new_oid
+                && !gitlinks.contains(&(change.name.clone(), new_oid))
+            {
+                gitlinks.push((change.name, new_oid));
+            }
+        }
+    }
+    Ok(gitlinks)
+}
+
+/// Callbacks authenticating with the SSH agent or the git credential helper of `config`
+fn remote_callbacks(config: &git2::Config) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(|url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) {
+            Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            Cred::credential_helper(config, url, username)
+        } else {
+            Cred::default()
+        }
+    });
+    callbacks
+}
+
+/// Tips of the refs currently advertised by the remotes of `repo` (as `git ls-remote`)
+///
+/// Remotes that cannot be reached are skipped with a warning.
+fn advertised_tips(repo: &Repository) -> anyhow::Result<Vec<Oid>> {
+    let config = repo.config()?;
+    let mut tips = vec![];
+    for name in repo.remotes()?.iter().flatten() {
+        let mut remote = repo.find_remote(name)?;
+        let callbacks = remote_callbacks(&config);
+        let connection = match remote.connect_auth(Direction::Fetch, Some(callbacks), None) {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("failed to list refs of remote {name}: {e}");
+                continue;
+            }
+        };
+        tips.extend(connection.list()?.iter().map(|head| head.oid()));
+    }
+    Ok(tips)
+}
+
+/// Whether `oid` is one of, or an ancestor of, the `tips` advertised by the remotes of `repo`
+///
+/// Tips unknown locally (not fetched yet) cannot be walked and only match `oid` exactly.
+fn is_on_advertised_tip(repo: &Repository, oid: Oid, tips: &[Oid]) -> bool {
+    tips.iter()
+        .any(|&tip| tip == oid || repo.graph_descendant_of(tip, oid).unwrap_or(false))
+}
+
+/// Check that the submodule commits recorded in the `pushed` commits of the repository at
+/// `path` are on a remote of the submodule, so that the pushed commits can be checked out by
+/// others.
+///
+/// Every outgoing commit is checked, not only the pushed one. A submodule commit is on a remote
+/// if it is reachable from a remote tracking branch of the submodule, otherwise the remotes of
+/// the submodule are listed (as `git ls-remote`) in case it was pushed since the last fetch.
+///
+/// Submodules whose commit is not on any remote are reported in `unreachable_commit_submodules`,
+/// with the pushed submodule commit as `index_oid`. Deleted refs are ignored.
+pub fn check_submodules_for_push(
+    strict: bool,
+    path: &Path,
+    allow_list: &[String],
+    pushed: &[PushedRef],
+) -> anyhow::Result<Option<SubmodulesDiagnostic>> {
+    let Ok(repo) = open_repository(path) else {
+        error!("failed to open git repository");
+        if strict {
+            anyhow::bail!("Unable to open repository");
+        }
+        return Ok(None);
+    };
+    let workdir = repo.workdir().unwrap_or(path);
+    let mut diagnostic = SubmodulesDiagnostic::default();
+    // remotes are listed at most once per submodule
+    let mut advertised: HashMap<String, Vec<Oid>> = HashMap::new();
+    for pushed_ref in pushed
+        .iter()
+        .filter(|pushed_ref| !pushed_ref.local_oid.is_zero())
+    {
+        debug!("checking pushed ref: {}", pushed_ref.local_ref);
+        for (name, oid) in outgoing_gitlinks(&repo, pushed_ref)? {
+            if is_allowed(&name, allow_list) {
+                debug!("skipping allowed submodule: {name}");
+                continue;
+            }
+            if diagnostic
+                .unreachable_commit_submodules
+                .iter()
+                .any(|entry| entry.name == name && entry.index_oid == Some(oid))
+            {
+                continue;
+            }
+            let submodule_repo = match Repository::open(workdir.join(&name)) {
+                Ok(submodule_repo) => submodule_repo,
+                Err(e) => {
+                    warn!("failed to open submodule {name}: {e}");
+                    continue;
+                }
+            };
+            if is_on_remote(&submodule_repo, oid)? {
+                continue;
+            }
+            if !advertised.contains_key(&name) {
+                advertised.insert(name.clone(), advertised_tips(&submodule_repo)?);
+            }
+            if !is_on_advertised_tip(&submodule_repo, oid, &advertised[&name]) {
+                debug!(submodule = %name, commit = %oid, "commit is not on any remote");
+                diagnostic
+                    .unreachable_commit_submodules
+                    .push(SubmoduleEntry {
+                        path: name.clone(),
+                        name,
+                        head_oid: None,
+                        index_oid: Some(oid),
+                        workdir_head: None,
+                        branch: None,
+                        ahead_behind: None,
+                        head_commit: None,
+                        changed_files: None,
+                    });
+            }
+        }
+    }
+    Ok(Some(diagnostic))
+}
+
+/// Check that the submodule commits recorded in `HEAD` of the repository at `path` exist in
+/// the submodule repositories, e.g. right after a commit.
+///
+/// Submodules whose commit is missing are reported in `missing_commit_submodules`, submodules
+/// that cannot be opened (e.g. not initialized) are not checked.
+pub fn check_committed_submodules(
+    path: &Path,
+    allow_list: &[String],
+) -> anyhow::Result<SubmodulesDiagnostic> {
+    let repo = open_repository(path)?;
+    let workdir = repo.workdir().unwrap_or(path);
+    let head = repo.head()?.peel_to_commit()?.id();
+    let mut diagnostic = SubmodulesDiagnostic::default();
+    for (name, oid) in gitlinks(&repo, head)? {
+        if is_allowed(&name, allow_list) {
+            debug!("skipping allowed submodule: {name}");
+            continue;
+        }
+        let submodule_repo = match Repository::open(workdir.join(&name)) {
+            Ok(submodule_repo) => submodule_repo,
+            Err(e) => {
+                warn!("failed to open submodule {name}: {e}");
+                continue;
+            }
+        };
+        match submodule_repo.find_commit(oid) {
+            Ok(_) => {}
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                debug!(submodule = %name, commit = %oid, "committed commit is missing");
+                diagnostic.missing_commit_submodules.push(name);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(diagnostic)
+}
+
+/// A submodule whose recorded commit differs between two commits of the parent repository
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmoduleChange {
+    pub name: String,
+    /// Commit recorded before, `None` if the submodule was added
+    #[serde(with = "optional_oid")]
+    pub old_oid: Option<Oid>,
+    /// Commit recorded after, `None` if the submodule was removed
+    #[serde(with = "optional_oid")]
+    pub new_oid: Option<Oid>,
+}
+
+/// How a submodule changed between two commits of the parent repository
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Updated,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Updated => "updated",
+        })
+    }
+}
+
+impl SubmoduleChange {
+    pub fn kind(&self) -> ChangeKind {
+        match (self.old_oid, self.new_oid) {
+            (None, _) => ChangeKind::Added,
+            (_, None) => ChangeKind::Removed,
+            _ => ChangeKind::Updated,
+        }
+    }
+}
+
+/// Order of the submodules within each kind of a diagnostic (`submodulehook.sort`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Path,
+    /// Most recent checked out commit first
+    Time,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SortOrder::Name => "name",
+            SortOrder::Path => "path",
+            SortOrder::Time => "time",
+        })
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "name" => Ok(SortOrder::Name),
+            "path" => Ok(SortOrder::Path),
+            "time" => Ok(SortOrder::Time),
+            _ => Err(format!("expected name, path or time, got `{value}`")),
+        }
+    }
+}
+
+/// Sort the submodules of each kind of `diagnostic`, submodules of the repository at `path`
+/// being looked up for the `Path` and `Time` orders
+pub fn sort_diagnostic(diagnostic: &mut SubmodulesDiagnostic, path: &Path, order: SortOrder) {
+    let repo = open_repository(path).ok();
+    let workdir = repository_workdir(path);
+    // submodules that cannot be looked up (e.g. nested ones) fall back to their name,
+    // or come last when sorted by time
+    let submodule_path = |name: &str| -> Option<PathBuf> {
+        let submodule = repo.as_ref()?.find_submodule(name).ok()?;
+        Some(submodule.path().to_path_buf())
+    };
+    let key = |name: &str| -> (i64, String) {
+        match order {
+            SortOrder::Name => (0, name.to_string()),
+            SortOrder::Path => {
+                let path = submodule_path(name).map(|path| path.display().to_string());
+                (0, path.unwrap_or_else(|| name.to_string()))
+            }
+            SortOrder::Time => {
+                let time = submodule_path(name).and_then(|path| {
+                    let repo = Repository::open(workdir.join(path)).ok()?;
+                    let commit = repo.head().ok()?.peel_to_commit().ok()?;
+                    Some(commit.time().seconds())
+                });
+                (time.map_or(i64::MAX, |time| -time), name.to_string())
+            }
+        }
+    };
+    let SubmodulesDiagnostic {
+        modified_not_staged_submodules,
+        modified_staged_submodules,
+        uninitialized_submodules,
+        newly_added_submodules,
+        deleted_submodules,
+        detached_head_submodules,
+        unreachable_commit_submodules,
+        pinned_version_mismatch,
+        config_mismatch_submodules,
+        path_conflict_submodules,
+        duplicate_submodules,
+        missing_commit_submodules,
+        insecure_url_submodules,
+        uncommitted_changes_submodules,
+        unpushed_submodules,
+        url_mismatch_submodules,
+    } = diagnostic;
+    for entries in [
+        modified_not_staged_submodules,
+        modified_staged_submodules,
+        uninitialized_submodules,
+        newly_added_submodules,
+        deleted_submodules,
+        detached_head_submodules,
+        unreachable_commit_submodules,
+    ] {
+        entries.sort_by_cached_key(|entry| key(&entry.name));
+    }
+    for names in [
+        config_mismatch_submodules,
+        path_conflict_submodules,
+        duplicate_submodules,
+        missing_commit_submodules,
+        uncommitted_changes_submodules,
+        unpushed_submodules,
+    ] {
+        names.sort_by_cached_key(|name| key(name));
+    }
+    pinned_version_mismatch.sort_by_cached_key(|mismatch| key(&mismatch.name));
+    insecure_url_submodules.sort_by_cached_key(|(name, _)| key(name));
+    url_mismatch_submodules.sort_by_cached_key(|(name, _, _)| key(name));
+}
+
+/// State of a submodule in the `status` overview
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmoduleState {
+    Clean,
+    /// Checked out commit differs from the index
+    Modified,
+    /// Index commit differs from `HEAD`
+    Staged,
+    Uninitialized,
+}
+
+impl fmt::Display for SubmoduleState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SubmoduleState::Clean => "clean",
+            SubmoduleState::Modified => "modified",
+            SubmoduleState::Staged => "staged",
+            SubmoduleState::Uninitialized => "uninitialized",
+        })
+    }
+}
+
+/// Overview of a submodule, whether it is modified or not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmoduleSummary {
+    pub name: String,
+    pub path: String,
+    /// Commit checked out in the submodule working directory
+    #[serde(with = "optional_oid")]
+    pub workdir_head: Option<Oid>,
+    /// Commit recorded in the parent repository index
+    #[serde(with = "optional_oid")]
+    pub index_oid: Option<Oid>,
+    /// Every state that applies, `[Clean]` if none does
+    pub states: Vec<SubmoduleState>,
+}
+
+/// Overview of every submodule of the repository at `path`, allowed ones included
+pub fn submodules_summary(path: &Path) -> anyhow::Result<Vec<SubmoduleSummary>> {
+    let repo = open_repository(path)?;
+    let mut summaries = vec![];
+    for submodule in repo.submodules()? {
+        let Some(name) = submodule.name() else {
+            warn!("submodule does not have a name");
+            continue;
+        };
+        let status = repo.submodule_status(name, SubmoduleIgnore::None)?;
+        let uninitialized = status.is_wd_uninitialized()
+            || matches!(submodule.open(), Err(e) if e.code() == ErrorCode::NotFound);
+        let mut states = vec![];
+        if uninitialized {
+            states.push(SubmoduleState::Uninitialized);
+        } else if status.is_wd_modified() {
+            states.push(SubmoduleState::Modified);
+        }
+        if status.is_index_modified() || status.is_index_added() {
+            states.push(SubmoduleState::Staged);
+        }
+        if states.is_empty() {
+            states.push(SubmoduleState::Clean);
+        }
+        summaries.push(SubmoduleSummary {
+            name: name.to_string(),
+            path: submodule.path().display().to_string(),
+            workdir_head: submodule.workdir_id(),
+            index_oid: submodule.index_id(),
+            states,
+        });
+    }
+    Ok(summaries)
+}
+
+/// Names of all the submodules of the repository at `path`, whatever their status
+pub fn submodule_names(path: &Path) -> anyhow::Result<Vec<String>> {
+    let repo = open_repository(path)?;
+    let mut names = vec![];
+    for submodule in repo.submodules()? {
+        match submodule.name() {
+            Some(name) => names.push(name.to_string()),
+            None => warn!("submodule does not have a name"),
+        }
+    }
+    Ok(names)
+}
+
+/// Submodule changes between two commits of the parent repository
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubmodulesDiff {
+    pub changes: Vec<SubmoduleChange>,
+    /// Whether `.gitmodules` differs, e.g. a submodule URL or branch changed
+    pub gitmodules_changed: bool,
+}
+
+/// Tree of commit `oid`, `None` for the zero oid (e.g. previous HEAD of a fresh clone)
+fn commit_tree(repo: &Repository, oid: Oid) -> anyhow::Result<Option<Tree<'_>>> {
+    if oid.is_zero() {
+        return Ok(None);
+    }
+    Ok(Some(repo.find_commit(oid)?.tree()?))
+}
+
+/// Submodules added, removed or updated between commits `old_oid` and `new_oid` of `repo`,
+/// e.g. by a `git checkout` from `old_oid` to `new_oid`
+pub fn check_submodules_checkout_diff(
+    old_oid: Oid,
+    new_oid: Oid,
+    repo: &Repository,
+) -> anyhow::Result<Vec<SubmoduleChange>> {
+    let old_tree = commit_tree(repo, old_oid)?;
+    let new_tree = commit_tree(repo, new_oid)?;
+    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), new_tree.as_ref(), None)?;
+    Ok(gitlink_changes(&diff))
+}
+
+/// Submodules added, removed or updated in the index of the repository at `path` compared to
+/// `HEAD`, i.e. the submodule changes of the next commit
+pub fn staged_submodule_changes(path: &Path) -> anyhow::Result<Vec<SubmoduleChange>> {
+    let repo = open_repository(path)?;
+    // unborn branch: everything in the index is added
+    let head_tree = repo.head().ok().and_then(|head| head.peel_to_tree().ok());
+    let index = repo.index()?;
+    let diff = repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), None)?;
+    Ok(gitlink_changes(&diff))
+}
+
+/// Submodule (gitlink) changes of `diff`
+fn gitlink_changes(diff: &git2::Diff) -> Vec<SubmoduleChange> {
+    let gitlink = |file: git2::DiffFile| (file.mode() == FileMode::Commit).then(|| file.id());
+    let mut changes = vec![];
+    for delta in diff.deltas() {
+        let old_oid = gitlink(delta.old_file());
+        let new_oid = gitlink(delta.new_file());
+        if old_oid.is_none() && new_oid.is_none() {
+            continue;
+        }
+        let path = delta.new_file().path().or(delta.old_file().path());
+        if let Some(name) = path.and_then(|path| path.to_str()) {
+            debug!("{name} changed: {old_oid:?} -> {new_oid:?}");
+            changes.push(SubmoduleChange {
+                name: name.to_string(),
+                old_oid,
+                new_oid,
+            });
+        }
+    }
+    changes
+}
+
+/// Submodules added, removed or updated between revisions `old_rev` and `new_rev` (anything
+/// accepted by `git rev-parse`, e.g. `main` or `HEAD~3`) of the repository at `path`
+pub fn check_submodules_diff(
+    path: &Path,
+    old_rev: &str,
+    new_rev: &str,
+) -> anyhow::Result<SubmodulesDiff> {
+    let repo = open_repository(path)?;
+    let resolve = |rev: &str| -> anyhow::Result<Oid> {
+        Ok(repo.revparse_single(rev)?.peel_to_commit()?.id())
+    };
+    let old_oid = resolve(old_rev)?;
+    let new_oid = resolve(new_rev)?;
+    let changes = check_submodules_checkout_diff(old_oid, new_oid, &repo)?;
+    let gitmodules = |oid: Oid| -> anyhow::Result<Option<Oid>> {
+        let tree = repo.find_commit(oid)?.tree()?;
+        Ok(tree
+            .get_path(Path::new(".gitmodules"))
+            .ok()
+            .map(|entry| entry.id()))
+    };
+    Ok(SubmodulesDiff {
+        changes,
+        gitmodules_changed: gitmodules(old_oid)? != gitmodules(new_oid)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repos::*;
+    use git2::{Oid, Signature};
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    // ========== Test Helpers ==========
+
+    /// Entry of the submodule `name` checked out at the path `name`, without any commit
+    fn entry(name: &str) -> SubmoduleEntry {
+        SubmoduleEntry {
+            path: name.to_string(),
+            ..SubmoduleEntry::named(name.to_string())
+        }
+    }
+
+    /// Adds a submodule checked out at `submodule_path` and named `submodule_name`, unlike the
+    /// submodules added by `add_submodule` whose name is their path
+    /// Returns the TempDir of the submodule repository, which must be kept alive
+    fn add_renamed_submodule(
+        parent_repo: &Repository,
+        submodule_name: &str,
+        submodule_path: &str,
+    ) -> anyhow::Result<TempDir> {
+        let (submodule_temp_dir, _) = add_submodule(parent_repo, submodule_path)?;
+        let workdir = parent_repo.workdir().unwrap();
+        for config in [
+            workdir.join(".gitmodules"),
+            parent_repo.path().join("config"),
+        ] {
+            let content = fs::read_to_string(&config)?.replace(
+                &format!("[submodule \"{submodule_path}\"]"),
+                &format!("[submodule \"{submodule_name}\"]"),
+            );
+            fs::write(&config, content)?;
+        }
+        let mut index = parent_repo.index()?;
+        index.add_path(std::path::Path::new(".gitmodules"))?;
+        index.write()?;
+        let sig = Signature::now("Test User", "test@example.com")?;
+        let tree = parent_repo.find_tree(index.write_tree()?)?;
+        let parent_commit = parent_repo.head()?.peel_to_commit()?;
+        parent_repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            &format!("Rename submodule {submodule_path} to {submodule_name}"),
+            &tree,
+            &[&parent_commit],
+        )?;
+        Ok(submodule_temp_dir)
+    }
+
+    /// Removes the submodule from the parent repository's index
+    fn unstage_submodule(parent_repo: &Repository, submodule_name: &str) -> anyhow::Result<()> {
+        let mut index = parent_repo.index()?;
+        index.remove_path(std::path::Path::new(submodule_name))?;
+        index.write()?;
+        Ok(())
+    }
+
+    /// Detaches the HEAD of the submodule's repository at its current commit
+    fn detach_submodule_head(parent_repo: &Repository, submodule_name: &str) -> anyhow::Result<()> {
+        let submodule_path = parent_repo.workdir().unwrap().join(submodule_name);
+        let submodule_repo = Repository::open(&submodule_path)?;
+        let head_oid = submodule_repo.head()?.peel_to_commit()?.id();
+        submodule_repo.set_head_detached(head_oid)?;
+        Ok(())
+    }
+
+    /// Configures the submodule to track `branch` in `.gitmodules`
+    fn set_submodule_branch(
+        parent_repo: &Repository,
+        submodule_name: &str,
+        branch: &str,
+    ) -> anyhow::Result<()> {
+        let mut repo = Repository::open(parent_repo.workdir().unwrap())?;
+        repo.submodule_set_branch(submodule_name, branch)?;
+        Ok(())
+    }
+
+    /// Names of the given submodule entries
+    fn names(entries: &[SubmoduleEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    // ========== Tests for Helpers ==========
+
+    #[test]
+    fn test_create_temp_repo() {
+        let result = create_temp_repo();
+        assert!(result.is_ok());
+
+        let (_temp_dir, repo) = result.unwrap();
+
+        // Verify repository is valid
+        assert!(!repo.is_bare());
+        assert!(repo.head().is_ok());
+
+        // Verify initial commit exists
+        let head = repo.head().unwrap();
+        let commit = head.peel_to_commit().unwrap();
+        assert_eq!(commit.message().unwrap(), "Initial commit");
+    }
+
+    #[test]
+    fn test_create_commit() {
+        let (_temp_dir, repo) = create_temp_repo().unwrap();
+
+        let result = create_commit(&repo, "Test commit");
+        assert!(result.is_ok());
+
+        // Verify commit was created
+        let commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(commit.message().unwrap(), "Test commit");
+    }
+
+    #[test]
+    fn test_stage_new_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+
+        let result = stage_new_submodule(&parent_repo, "test-submodule");
+        assert!(result.is_ok());
+
+        let (_submodule_temp_dir, submodule_name) = result.unwrap();
+
+        // Verify submodule is added to the index
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.is_index_added());
+    }
+
+    #[test]
+    fn test_add_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+
+        let result = add_submodule(&parent_repo, "test-submodule");
+        assert!(result.is_ok());
+
+        let (_submodule_temp_dir, submodule_name) = result.unwrap();
+
+        // Verify submodule was added
+        let submodules = parent_repo.submodules().unwrap();
+        assert_eq!(submodules.len(), 1);
+        assert_eq!(submodules[0].name().unwrap(), submodule_name);
+
+        // Verify submodule directory exists
+        let submodule_path = parent_repo.workdir().unwrap().join(&submodule_name);
+        assert!(submodule_path.exists());
+    }
+
+    #[test]
+    fn test_modify_submodule_wd() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        let result = modify_submodule_wd(&parent_repo, &submodule_name);
+        assert!(result.is_ok());
+
+        // Verify submodule status shows modification
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.is_wd_modified());
+    }
+
+    #[test]
+    fn test_stage_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        // Modify submodule first
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+
+        // Stage the submodule
+        let result = stage_submodule(&parent_repo, &submodule_name);
+        assert!(result.is_ok());
+
+        // Verify submodule is staged
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.is_index_modified());
+    }
+
+    #[test]
+    fn test_deinit_submodule_wd() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        let result = deinit_submodule_wd(&parent_repo, &submodule_name);
+        assert!(result.is_ok());
+
+        // Verify submodule status shows it is not initialized
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.is_wd_uninitialized());
+    }
+
+    #[test]
+    fn test_unstage_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        let result = unstage_submodule(&parent_repo, &submodule_name);
+        assert!(result.is_ok());
+
+        // Verify submodule is removed from the index
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.is_index_deleted());
+    }
+
+    #[test]
+    fn test_detach_submodule_head() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        let result = detach_submodule_head(&parent_repo, &submodule_name);
+        assert!(result.is_ok());
+
+        // Verify submodule repository HEAD is detached
+        let submodule_path = parent_repo.workdir().unwrap().join(&submodule_name);
+        let submodule_repo = Repository::open(&submodule_path).unwrap();
+        assert!(submodule_repo.head_detached().unwrap());
+    }
+
+    #[test]
+    fn test_set_submodule_branch() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "test-submodule").unwrap();
+
+        let result = set_submodule_branch(&parent_repo, &submodule_name, "main");
+        assert!(result.is_ok());
+
+        // Verify branch is recorded in .gitmodules
+        let submodule = parent_repo.find_submodule(&submodule_name).unwrap();
+        assert_eq!(submodule.branch(), Some("main"));
+    }
+
+    // ========== Tests for check_submodules ==========
+
+    #[test]
+    fn test_no_submodules() {
+        let (_temp_dir, repo) = create_temp_repo().unwrap();
+        let repo_path = repo.workdir().unwrap();
+
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap();
+        assert!(diagnostic.is_some());
+
+        let diagnostic = diagnostic.unwrap();
+        assert!(diagnostic.is_clean());
+        assert!(diagnostic.is_empty());
+    }
+
+    #[test]
+    fn test_unmodified_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, _submodule_name) =
+            add_submodule(&parent_repo, "clean-submodule").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.is_clean());
+    }
+
+    #[test]
+    fn test_modified_not_staged_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "modified-submodule").unwrap();
+
+        // Modify submodule but don't stage
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert_eq!(diagnostic.modified_not_staged_submodules.len(), 1);
+        assert_eq!(
+            diagnostic.modified_not_staged_submodules[0].name,
+            submodule_name
+        );
+        assert!(diagnostic.modified_staged_submodules.is_empty());
+    }
+
+    #[test]
+    fn test_modified_not_staged_submodule_oids() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "modified-submodule").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        let submodule_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&submodule_name)).unwrap();
+        let submodule_head = submodule_repo.head().unwrap().target();
+        let entry = &diagnostic.modified_not_staged_submodules[0];
+        assert_eq!(entry.workdir_head, submodule_head);
+        assert!(entry.index_oid.is_some());
+        assert_eq!(entry.head_oid, entry.index_oid);
+        assert_ne!(entry.index_oid, entry.workdir_head);
+    }
+
+    #[test]
+    fn test_modified_staged_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "staged-submodule").unwrap();
+
+        // Modify and stage submodule
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+        stage_submodule(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 1);
+        assert_eq!(
+            diagnostic.modified_staged_submodules[0].name,
+            submodule_name
+        );
+    }
+
+    #[test]
+    fn test_both_modified_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+
+        // Add first submodule - modified but not staged
+        let (_submodule1_temp_dir, submodule1_name) =
+            add_submodule(&parent_repo, "submodule1").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule1_name).unwrap();
+
+        // Add second submodule - modified and staged
+        let (_submodule2_temp_dir, submodule2_name) =
+            add_submodule(&parent_repo, "submodule2").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule2_name).unwrap();
+        stage_submodule(&parent_repo, &submodule2_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert_eq!(diagnostic.modified_not_staged_submodules.len(), 1);
+        assert_eq!(
+            diagnostic.modified_not_staged_submodules[0].name,
+            submodule1_name
+        );
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 1);
+        assert_eq!(
+            diagnostic.modified_staged_submodules[0].name,
+            submodule2_name
+        );
+    }
+
+    #[test]
+    fn test_uninitialized_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "uninitialized-submodule").unwrap();
+
+        deinit_submodule_wd(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert!(diagnostic.modified_staged_submodules.is_empty());
+        assert_eq!(diagnostic.uninitialized_submodules.len(), 1);
+        assert_eq!(diagnostic.uninitialized_submodules[0].name, submodule_name);
+    }
+
+    #[test]
+    fn test_newly_added_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            stage_new_submodule(&parent_repo, "new-submodule").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert!(diagnostic.modified_staged_submodules.is_empty());
+        assert_eq!(diagnostic.newly_added_submodules.len(), 1);
+        assert_eq!(diagnostic.newly_added_submodules[0].name, submodule_name);
+    }
+
+    #[test]
+    fn test_deleted_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "deleted-submodule").unwrap();
+
+        unstage_submodule(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.modified_staged_submodules.is_empty());
+        assert_eq!(diagnostic.deleted_submodules.len(), 1);
+        assert_eq!(diagnostic.deleted_submodules[0].name, submodule_name);
+    }
+
+    #[test]
+    fn test_detached_head_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "detached-submodule").unwrap();
+
+        detach_submodule_head(&parent_repo, &submodule_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert_eq!(diagnostic.detached_head_submodules.len(), 1);
+        assert_eq!(diagnostic.detached_head_submodules[0].name, submodule_name);
+    }
+
+    #[test]
+    fn test_submodule_branch() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule1_temp_dir, submodule1_name) =
+            add_submodule(&parent_repo, "tracking").unwrap();
+        set_submodule_branch(&parent_repo, &submodule1_name, "main").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule1_name).unwrap();
+
+        let (_submodule2_temp_dir, submodule2_name) =
+            add_submodule(&parent_repo, "pinned").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule2_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+
+        let branches: Vec<_> = diagnostic
+            .modified_not_staged_submodules
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.branch.as_deref()))
+            .collect();
+        assert!(branches.contains(&("tracking", Some("main"))));
+        assert!(branches.contains(&("pinned", None)));
+    }
+
+    #[cfg(feature = "ahead-behind")]
+    #[test]
+    fn test_ahead_behind() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        // checked out at a path differing from its name
+        let submodule_name = "staged-submodule";
+        let submodule_path = "libs/staged-submodule";
+        let _submodule_temp_dir =
+            add_renamed_submodule(&parent_repo, submodule_name, submodule_path).unwrap();
+        let submodule_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(submodule_path)).unwrap();
+        let branch = submodule_repo
+            .head()
+            .unwrap()
+            .shorthand()
+            .unwrap()
+            .to_string();
+        set_submodule_branch(&parent_repo, submodule_name, &branch).unwrap();
+        modify_submodule_wd(&parent_repo, submodule_path).unwrap();
+        stage_submodule(&parent_repo, submodule_path).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        add_ahead_behind(&mut diagnostic, repo_path);
+
+        assert_eq!(
+            diagnostic.modified_staged_submodules[0].ahead_behind,
+            Some(AheadBehind {
+                ahead: 1,
+                behind: 0,
+                upstream: format!("origin/{branch}"),
+            })
+        );
+    }
+
+    #[cfg(feature = "ahead-behind")]
+    #[test]
+    fn test_ahead_behind_configured_remote() {
+        let (_temp_dir, repo) = create_temp_repo().unwrap();
+        let head = repo.head().unwrap();
+        let branch = head.shorthand().unwrap().to_string();
+        let fetched = head.target().unwrap();
+        drop(head);
+        repo.reference(
+            &format!("refs/remotes/upstream/{branch}"),
+            fetched,
+            false,
+            "fetch",
+        )
+        .unwrap();
+        repo.config()
+            .unwrap()
+            .set_str(&format!("branch.{branch}.remote"), "upstream")
+            .unwrap();
+        let oid = create_commit(&repo, "Local change").unwrap();
+
+        assert_eq!(
+            ahead_behind(&repo, oid, Some(&branch)),
+            Some(AheadBehind {
+                ahead: 1,
+                behind: 0,
+                upstream: format!("upstream/{branch}"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unreachable_commit_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+
+        let (submodule2_temp_dir, submodule2_name) = add_submodule(&parent_repo, "pushed").unwrap();
+        // checked out at a path differing from its name
+        let _submodule1_temp_dir =
+            add_renamed_submodule(&parent_repo, "unpushed", "libs/unpushed").unwrap();
+
+        // Staged commit only exists in the submodule working directory
+        modify_submodule_wd(&parent_repo, "libs/unpushed").unwrap();
+        stage_submodule(&parent_repo, "libs/unpushed").unwrap();
+
+        // Staged commit was fetched from the submodule remote
+        let remote_repo = Repository::open(submodule2_temp_dir.path()).unwrap();
+        let remote_oid = create_commit(&remote_repo, "Remote commit").unwrap();
+        let submodule2_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&submodule2_name)).unwrap();
+        submodule2_repo
+            .find_remote("origin")
+            .unwrap()
+            .fetch(&[] as &[&str], None, None)
+            .unwrap();
+        submodule2_repo.set_head_detached(remote_oid).unwrap();
+        stage_submodule(&parent_repo, &submodule2_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 2);
+        check_unreachable_commits(&mut diagnostic, repo_path);
+
+        assert_eq!(
+            names(&diagnostic.unreachable_commit_submodules),
+            vec!["unpushed"]
+        );
+    }
+
+    #[test]
+    fn test_check_unpushed_commits() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (pushed_temp_dir, pushed_name) = add_submodule(&parent_repo, "pushed").unwrap();
+        // checked out at a path differing from its name
+        let _unpushed_temp_dir =
+            add_renamed_submodule(&parent_repo, "unpushed", "libs/unpushed").unwrap();
+
+        // new commit on the branch of the submodule only
+        modify_submodule_wd(&parent_repo, "libs/unpushed").unwrap();
+        stage_submodule(&parent_repo, "libs/unpushed").unwrap();
+
+        // branch fast-forwarded to a commit fetched from the submodule remote
+        let remote_repo = Repository::open(pushed_temp_dir.path()).unwrap();
+        let remote_oid = create_commit(&remote_repo, "Remote commit").unwrap();
+        let pushed_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&pushed_name)).unwrap();
+        pushed_repo
+            .find_remote("origin")
+            .unwrap()
+            .fetch(&[] as &[&str], None, None)
+            .unwrap();
+        let branch = pushed_repo.head().unwrap().name().unwrap().to_string();
+        pushed_repo
+            .reference(&branch, remote_oid, true, "fast-forward")
+            .unwrap();
+        stage_submodule(&parent_repo, &pushed_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 2);
+        check_unpushed_commits(&mut diagnostic, repo_path);
+        assert_eq!(diagnostic.unpushed_submodules, vec!["unpushed"]);
+    }
+
+    #[test]
+    fn test_sort_diagnostic() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        // checked out at a path differing from its name
+        let _b_temp_dir = add_renamed_submodule(&parent_repo, "b", "libs/b").unwrap();
+        let b_name = "b".to_string();
+        let (_a_temp_dir, a_name) = add_submodule(&parent_repo, "a").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = SubmodulesDiagnostic {
+            modified_not_staged_submodules: vec![entry(&b_name), entry("unknown"), entry(&a_name)],
+            unpushed_submodules: vec![b_name.clone(), a_name.clone()],
+            ..SubmodulesDiagnostic::default()
+        };
+
+        sort_diagnostic(&mut diagnostic, repo_path, SortOrder::Name);
+        let mut sorted = vec![a_name.clone(), b_name.clone(), "unknown".to_string()];
+        sorted.sort();
+        assert_eq!(names(&diagnostic.modified_not_staged_submodules), sorted);
+        let mut sorted = vec![a_name.clone(), b_name.clone()];
+        sorted.sort();
+        assert_eq!(diagnostic.unpushed_submodules, sorted);
+
+        // the most recent commit first, submodules without commit last
+        let b_repo = Repository::open(repo_path.join("libs/b")).unwrap();
+        let sig = Signature::new(
+            "Test User",
+            "test@example.com",
+            &git2::Time::new(4_000_000_000, 0),
+        )
+        .unwrap();
+        let tree = b_repo.head().unwrap().peel_to_tree().unwrap();
+        let parent = b_repo.head().unwrap().peel_to_commit().unwrap();
+        b_repo
+            .commit(Some("HEAD"), &sig, &sig, "Future commit", &tree, &[&parent])
+            .unwrap();
+        sort_diagnostic(&mut diagnostic, repo_path, SortOrder::Time);
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec![b_name.clone(), a_name.clone(), "unknown".to_string()]
+        );
+        assert_eq!(diagnostic.unpushed_submodules, vec![b_name, a_name]);
+    }
+
+    #[test]
+    fn test_parse_pushed_refs() {
+        let local = "0123456789abcdef0123456789abcdef01234567";
+        let input = format!(
+            "refs/heads/main {local} refs/heads/main {}\ninvalid line\n",
+            Oid::zero()
+        );
+        assert_eq!(
+            parse_pushed_refs(&input),
+            vec![PushedRef {
+                local_ref: "refs/heads/main".to_string(),
+                local_oid: Oid::from_str(local).unwrap(),
+                remote_ref: "refs/heads/main".to_string(),
+                remote_oid: Oid::zero(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_check_submodules_for_push() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "unpushed").unwrap();
+        let pushed_ref = |local_oid| PushedRef {
+            local_ref: "refs/heads/main".to_string(),
+            local_oid,
+            remote_ref: "refs/heads/main".to_string(),
+            remote_oid: Oid::zero(),
+        };
+        let repo_path = parent_repo.workdir().unwrap();
+
+        // Submodule commit was cloned from the remote
+        let head = parent_repo.head().unwrap().target().unwrap();
+        let diagnostic = check_submodules_for_push(false, repo_path, &[], &[pushed_ref(head)])
+            .unwrap()
+            .unwrap();
+        assert!(diagnostic.is_clean());
+
+        // Submodule commit only exists locally
+        let submodule_repo = Repository::open(repo_path.join(&submodule_name)).unwrap();
+        let cloned = submodule_repo.head().unwrap().target().unwrap();
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+        let local = submodule_repo.head().unwrap().target().unwrap();
+        stage_submodule(&parent_repo, &submodule_name).unwrap();
+        let head = create_commit(&parent_repo, "Update submodule").unwrap();
+        let diagnostic = check_submodules_for_push(false, repo_path, &[], &[pushed_ref(head)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            names(&diagnostic.unreachable_commit_submodules),
+            vec![submodule_name.as_str()]
+        );
+
+        // Outgoing commit before the pushed one records the local commit
+        let cloned_commit = submodule_repo.find_object(cloned, None).unwrap();
+        submodule_repo
+            .reset(&cloned_commit, git2::ResetType::Hard, None)
+            .unwrap();
+        stage_submodule(&parent_repo, &submodule_name).unwrap();
+        let head = create_commit(&parent_repo, "Revert submodule").unwrap();
+        let diagnostic = check_submodules_for_push(false, repo_path, &[], &[pushed_ref(head)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(diagnostic.unreachable_commit_submodules.len(), 1);
+        assert_eq!(
+            diagnostic.unreachable_commit_submodules[0].index_oid,
+            Some(local)
+        );
+
+        // Local commit pushed since the last fetch: only the live remote knows it
+        submodule_repo
+            .branch(
+                "feature",
+                &submodule_repo.find_commit(local).unwrap(),
+                false,
+            )
+            .unwrap();
+        let origin_url = submodule_repo
+            .find_remote("origin")
+            .unwrap()
+            .url()
+            .unwrap()
+            .to_string();
+        let origin_path = origin_url.strip_prefix("file://").unwrap_or(&origin_url);
+        let origin_repo = Repository::open(origin_path).unwrap();
+        let submodule_path = submodule_repo.path().to_str().unwrap();
+        origin_repo
+            .remote_anonymous(submodule_path)
+            .unwrap()
+            .fetch(&["refs/heads/feature:refs/heads/feature"], None, None)
+            .unwrap();
+        let diagnostic = check_submodules_for_push(false, repo_path, &[], &[pushed_ref(head)])
+            .unwrap()
+            .unwrap();
+        assert!(diagnostic.is_clean());
+
+        // Commits already on the remote are not checked again
+        let diagnostic = check_submodules_for_push(
+            false,
+            repo_path,
+            &[],
+            &[PushedRef {
+                remote_oid: head,
+                ..pushed_ref(head)
+            }],
+        )
+        .unwrap()
+        .unwrap();
+        assert!(diagnostic.is_clean());
+    }
+
+    #[test]
+    fn test_check_submodules_checkout_diff() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let initial = parent_repo.head().unwrap().target().unwrap();
+        let (_submodule1_temp_dir, submodule1_name) =
+            add_submodule(&parent_repo, "updated").unwrap();
+        let (_submodule2_temp_dir, submodule2_name) = add_submodule(&parent_repo, "added").unwrap();
+        let before = parent_repo.head().unwrap().target().unwrap();
+        modify_submodule_wd(&parent_repo, &submodule1_name).unwrap();
+        stage_submodule(&parent_repo, &submodule1_name).unwrap();
+        let after = create_commit(&parent_repo, "Update submodule").unwrap();
+
+        let changes = check_submodules_checkout_diff(before, after, &parent_repo).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].name, submodule1_name);
+        assert!(changes[0].old_oid.is_some());
+        assert_ne!(changes[0].old_oid, changes[0].new_oid);
+
+        let changes = check_submodules_checkout_diff(after, initial, &parent_repo).unwrap();
+        let removed: Vec<&str> = changes
+            .iter()
+            .filter(|change| change.new_oid.is_none())
+            .map(|change| change.name.as_str())
+            .collect();
+        assert_eq!(
+            removed,
+            vec![submodule2_name.as_str(), submodule1_name.as_str()]
+        );
+
+        let changes = check_submodules_checkout_diff(Oid::zero(), before, &parent_repo).unwrap();
+        assert!(changes.iter().all(|change| change.old_oid.is_none()));
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn test_check_committed_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let (_other_temp_dir, other_name) = add_submodule(&parent_repo, "other").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        assert!(
+            check_committed_submodules(repo_path, &[])
+                .unwrap()
+                .is_clean()
+        );
+
+        // record a commit that does not exist in the submodules
+        let missing = Oid::from_str("1234567890123456789012345678901234567890").unwrap();
+        let mut index = parent_repo.index().unwrap();
+        for name in [&sub_name, &other_name] {
+            let mut entry = index.get_path(Path::new(name), 0).unwrap();
+            entry.id = missing;
+            index.add(&entry).unwrap();
+        }
+        index.write().unwrap();
+        let tree = parent_repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("Test User", "test@example.com").unwrap();
+        let parent = parent_repo.head().unwrap().peel_to_commit().unwrap();
+        parent_repo
+            .commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "Missing commits",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+
+        let diagnostic = check_committed_submodules(repo_path, &[other_name]).unwrap();
+        assert_eq!(diagnostic.missing_commit_submodules, vec![sub_name]);
+        assert_eq!(diagnostic.all_affected().count(), 1);
+    }
+
+    #[test]
+    fn test_staged_submodule_changes() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_updated_temp_dir, updated) = add_submodule(&parent_repo, "updated").unwrap();
+        let (_modified_temp_dir, modified) = add_submodule(&parent_repo, "modified").unwrap();
+        let (_added_temp_dir, added) = stage_new_submodule(&parent_repo, "added").unwrap();
+        modify_submodule_wd(&parent_repo, &updated).unwrap();
+        stage_submodule(&parent_repo, &updated).unwrap();
+        // not staged, not part of the next commit
+        modify_submodule_wd(&parent_repo, &modified).unwrap();
+
+        let mut changes = staged_submodule_changes(parent_repo.workdir().unwrap()).unwrap();
+        changes.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].name, added);
+        assert_eq!(changes[0].kind(), ChangeKind::Added);
+        assert_eq!(changes[1].name, updated);
+        assert_eq!(changes[1].kind(), ChangeKind::Updated);
+    }
+
+    #[test]
+    fn test_check_submodules_diff() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let initial = parent_repo.head().unwrap().target().unwrap();
+        let (_submodule_temp_dir, submodule_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let before = parent_repo.head().unwrap().target().unwrap();
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+        stage_submodule(&parent_repo, &submodule_name).unwrap();
+        create_commit(&parent_repo, "Update submodule").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+
+        let diff = check_submodules_diff(repo_path, &before.to_string(), "HEAD").unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].kind(), ChangeKind::Updated);
+        assert!(!diff.gitmodules_changed);
+
+        let diff = check_submodules_diff(repo_path, &initial.to_string(), "HEAD").unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].kind(), ChangeKind::Added);
+        assert!(diff.gitmodules_changed);
+
+        let diff = check_submodules_diff(repo_path, "HEAD", &initial.to_string()).unwrap();
+        assert_eq!(diff.changes[0].kind(), ChangeKind::Removed);
+
+        assert!(check_submodules_diff(repo_path, "no-such-ref", "HEAD").is_err());
+    }
+
+    #[test]
+    fn test_check_pinned_commits() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let staged = parent_repo.find_submodule(&sub_name).unwrap().index_id();
+        let other = Oid::from_str("3ca86a808c39e9cb0604346bcc304338bd0acf5c").unwrap();
+
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        let pins = HashMap::from([(sub_name.clone(), staged.unwrap())]);
+        check_pinned_commits(&mut diagnostic, repo_path, &pins, true).unwrap();
+        assert!(diagnostic.is_clean());
+
+        let pins = HashMap::from([(sub_name.clone(), other), ("gone".to_string(), other)]);
+        check_pinned_commits(&mut diagnostic, repo_path, &pins, false).unwrap();
+        assert_eq!(
+            diagnostic.pinned_version_mismatch,
+            vec![
+                PinnedMismatch {
+                    name: "gone".to_string(),
+                    pinned: other,
+                    index_oid: None,
+                },
+                PinnedMismatch {
+                    name: sub_name.clone(),
+                    pinned: other,
+                    index_oid: staged,
+                },
+            ]
+        );
+
+        let error =
+            check_pinned_commits(&mut SubmodulesDiagnostic::default(), repo_path, &pins, true)
+                .unwrap_err();
+        assert!(matches!(
+            error,
+            CheckError::PinnedVersionMismatch { name, .. } if name == "gone"
+        ));
+    }
+
+    #[test]
+    fn test_submodules_summary() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_clean_temp_dir, clean_name) = add_submodule(&parent_repo, "clean").unwrap();
+        let (_modified_temp_dir, modified_name) = add_submodule(&parent_repo, "modified").unwrap();
+        modify_submodule_wd(&parent_repo, &modified_name).unwrap();
+        let (_uninit_temp_dir, uninit_name) = add_submodule(&parent_repo, "uninit").unwrap();
+        deinit_submodule_wd(&parent_repo, &uninit_name).unwrap();
+
+        let summaries = submodules_summary(parent_repo.workdir().unwrap()).unwrap();
+        let states: HashMap<&str, &[SubmoduleState]> = summaries
+            .iter()
+            .map(|summary| (summary.name.as_str(), summary.states.as_slice()))
+            .collect();
+        assert_eq!(states[clean_name.as_str()], [SubmoduleState::Clean]);
+        assert_eq!(states[modified_name.as_str()], [SubmoduleState::Modified]);
+        assert_eq!(
+            states[uninit_name.as_str()],
+            [SubmoduleState::Uninitialized]
+        );
+        let clean = summaries.iter().find(|s| s.name == clean_name).unwrap();
+        assert_eq!(clean.path, clean_name);
+        assert_eq!(clean.workdir_head, clean.index_oid);
+    }
+
+    #[test]
+    fn test_submodule_names() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        assert!(
+            submodule_names(parent_repo.workdir().unwrap())
+                .unwrap()
+                .is_empty()
+        );
+        let (_first_temp_dir, first_name) = add_submodule(&parent_repo, "first").unwrap();
+        let (_second_temp_dir, second_name) = add_submodule(&parent_repo, "second").unwrap();
+        modify_submodule_wd(&parent_repo, &second_name).unwrap();
+
+        let mut names = submodule_names(parent_repo.workdir().unwrap()).unwrap();
+        names.sort();
+        let mut expected = vec![first_name, second_name];
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_check_exit_code() {
+        let entry = entry("sub");
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        diagnostic.uninitialized_submodules.push(entry.clone());
+        assert_eq!(check_exit_code(&diagnostic), 0);
+        diagnostic
+            .modified_not_staged_submodules
+            .push(entry.clone());
+        assert_eq!(check_exit_code(&diagnostic), 1);
+        diagnostic.modified_staged_submodules.push(entry.clone());
+        assert_eq!(check_exit_code(&diagnostic), 3);
+        diagnostic.modified_not_staged_submodules.clear();
+        assert_eq!(check_exit_code(&diagnostic), 2);
+    }
+
+    #[test]
+    fn test_ignore_policy_all() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        modify_submodule_wd(&parent_repo, &sub_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let ignore_policies = HashMap::from([(sub_name.clone(), SubmoduleIgnore::All)]);
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &ignore_policies,
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(diagnostic.is_clean());
+
+        let ignore_policies = HashMap::from([(sub_name, SubmoduleIgnore::Dirty)]);
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &ignore_policies,
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["sub"]
+        );
+    }
+
+    #[test]
+    fn test_default_ignore_policy() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_a_temp_dir, a_name) = add_submodule(&parent_repo, "sub_a").unwrap();
+        let (_b_temp_dir, b_name) = add_submodule(&parent_repo, "sub_b").unwrap();
+        modify_submodule_wd(&parent_repo, &a_name).unwrap();
+        modify_submodule_wd(&parent_repo, &b_name).unwrap();
+
+        // per-submodule policies take precedence over the default one
+        let repo_path = parent_repo.workdir().unwrap();
+        let ignore_policies = HashMap::from([(b_name, SubmoduleIgnore::None)]);
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &ignore_policies,
+            SubmoduleIgnore::All,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["sub_b"]
+        );
+    }
+
+    #[test]
+    fn test_directory_without_repository_is_uninitialized() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let sub_path = parent_repo.workdir().unwrap().join(&sub_name);
+        fs::remove_dir_all(&sub_path).unwrap();
+        fs::create_dir(&sub_path).unwrap();
+        fs::write(sub_path.join(".git"), "gitdir: ../.git/modules/missing\n").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(names(&diagnostic.uninitialized_submodules), vec!["sub"]);
+        assert!(diagnostic.deleted_submodules.is_empty());
+    }
+
+    #[test]
+    fn test_add_commit_summaries() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        // checked out at a path differing from its name
+        let _sub_temp_dir = add_renamed_submodule(&parent_repo, "sub", "libs/sub").unwrap();
+        modify_submodule_wd(&parent_repo, "libs/sub").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        add_commit_summaries(&mut diagnostic, repo_path);
+
+        assert_eq!(
+            diagnostic.modified_not_staged_submodules[0].head_commit,
+            Some(CommitSummary {
+                summary: "Submodule modification".to_string(),
+                author: "Test User".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_add_changed_files() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        // checked out at a path differing from its name
+        let _sub_temp_dir = add_renamed_submodule(&parent_repo, "sub", "libs/sub").unwrap();
+        modify_submodule_wd(&parent_repo, "libs/sub").unwrap();
+        let sub_path = parent_repo.workdir().unwrap().join("libs/sub");
+        fs::write(sub_path.join("file_Submodule_modification.txt"), "edited").unwrap();
+        fs::write(sub_path.join("untracked.txt"), "untracked").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let mut diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            diagnostic.modified_not_staged_submodules[0].changed_files,
+            None
+        );
+        add_changed_files(&mut diagnostic, repo_path);
+
+        let mut changed_files = diagnostic.modified_not_staged_submodules[0]
+            .changed_files
+            .clone()
+            .unwrap();
+        changed_files.sort();
+        assert_eq!(
+            changed_files,
+            vec!["file_Submodule_modification.txt", "untracked.txt"]
+        );
+    }
+
+    #[test]
+    fn test_stage_modified_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        modify_submodule_wd(&parent_repo, &sub_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                repo_path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let mut diagnostic = check();
+        let staged = stage_modified_submodules(&mut diagnostic, repo_path, true).unwrap();
+        assert_eq!(staged, vec!["sub"]);
+        assert!(diagnostic.is_clean());
+
+        let diagnostic = check();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert_eq!(names(&diagnostic.modified_staged_submodules), vec!["sub"]);
+    }
+
+    #[test]
+    fn test_stage_modified_renamed_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let _sub_temp_dir = add_renamed_submodule(&parent_repo, "mylib", "libs/mylib").unwrap();
+        modify_submodule_wd(&parent_repo, "libs/mylib").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                repo_path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let mut diagnostic = check();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["mylib"]
+        );
+        let staged = stage_modified_submodules(&mut diagnostic, repo_path, true).unwrap();
+        assert_eq!(staged, vec!["mylib"]);
+        assert!(diagnostic.is_clean());
+
+        let diagnostic = check();
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert_eq!(names(&diagnostic.modified_staged_submodules), vec!["mylib"]);
+    }
+
+    #[test]
+    fn test_update_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_drifted_temp_dir, drifted) = add_submodule(&parent_repo, "drifted").unwrap();
+        let (_clean_temp_dir, clean) = add_submodule(&parent_repo, "clean").unwrap();
+        let (_uninit_temp_dir, uninit) = add_submodule(&parent_repo, "uninit").unwrap();
+        deinit_submodule_wd(&parent_repo, &uninit).unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let recorded = |name: &str| {
+            Repository::open(repo_path)
+                .unwrap()
+                .find_submodule(name)
+                .unwrap()
+                .index_id()
+                .unwrap()
+        };
+        let checked_out = |name: &str| {
+            Repository::open(repo_path.join(name))
+                .unwrap()
+                .refname_to_id("HEAD")
+                .unwrap()
+        };
+        let update = |remote| {
+            let mut reports = vec![];
+            update_submodules(repo_path, false, remote, |report| {
+                let oid = report.result.unwrap();
+                reports.push((report.kind, report.name, oid));
+            })
+            .unwrap();
+            reports.sort_by(|a, b| a.1.cmp(&b.1));
+            reports
+        };
+        modify_submodule_wd(&parent_repo, &drifted).unwrap();
+        assert_ne!(checked_out(&drifted), recorded(&drifted));
+
+        assert_eq!(
+            update(false),
+            vec![
+                (UpdateKind::Checkout, drifted.clone(), recorded(&drifted)),
+                (UpdateKind::Init, uninit.clone(), recorded(&uninit)),
+            ]
+        );
+        assert_eq!(checked_out(&drifted), recorded(&drifted));
+        assert_eq!(checked_out(&uninit), recorded(&uninit));
+        assert!(update(false).is_empty());
+
+        // new commit on the remote of `clean`
+        let origin_url = Repository::open(repo_path.join(&clean))
+            .unwrap()
+            .find_remote("origin")
+            .unwrap()
+            .url()
+            .unwrap()
+            .to_string();
+        let origin_path = origin_url.strip_prefix("file://").unwrap_or(&origin_url);
+        let remote_tip = create_commit(&Repository::open(origin_path).unwrap(), "Remote").unwrap();
+        let reports = update(true);
+        assert_eq!(reports.len(), 3);
+        assert!(reports.iter().all(|report| report.0 == UpdateKind::Remote));
+        assert_eq!(reports[0], (UpdateKind::Remote, clean.clone(), remote_tip));
+        assert_eq!(checked_out(&clean), remote_tip);
+    }
+
+    #[test]
+    fn test_fix_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_modified_temp_dir, modified) = add_submodule(&parent_repo, "modified").unwrap();
+        modify_submodule_wd(&parent_repo, &modified).unwrap();
+        let (_staged_temp_dir, staged) = add_submodule(&parent_repo, "staged").unwrap();
+        modify_submodule_wd(&parent_repo, &staged).unwrap();
+        let (_uninit_temp_dir, uninit) = add_submodule(&parent_repo, "uninit").unwrap();
+        deinit_submodule_wd(&parent_repo, &uninit).unwrap();
+        // staged after the last commit
+        stage_submodule(&parent_repo, &staged).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let fix = |actions: &[FixAction], dry_run| {
+            let mut reports: Vec<(FixAction, String)> = fix_submodules(repo_path, actions, dry_run)
+                .unwrap()
+                .into_iter()
+                .map(|report| {
+                    assert!(report.result.is_ok(), "{report:?}");
+                    (report.action, report.name)
+                })
+                .collect();
+            reports.sort_by(|a, b| a.1.cmp(&b.1));
+            reports
+        };
+        // fresh handle: the index of `parent_repo` is cached
+        let status = |name: &str| {
+            Repository::open(repo_path)
+                .unwrap()
+                .submodule_status(name, SubmoduleIgnore::None)
+                .unwrap()
+        };
+        let all = [FixAction::Stage, FixAction::Init];
+        let expected = vec![
+            (FixAction::Stage, modified.clone()),
+            (FixAction::Init, uninit.clone()),
+        ];
+        assert_eq!(fix(&all, true), expected);
+        assert!(status(&modified).is_wd_modified());
+        assert!(status(&uninit).is_wd_uninitialized());
+
+        assert_eq!(fix(&all, false), expected);
+        assert!(status(&modified).is_index_modified());
+        assert!(!status(&modified).is_wd_modified());
+        assert!(!status(&uninit).is_wd_uninitialized());
+        assert!(fix(&all, false).is_empty());
+
+        assert_eq!(
+            fix(&[FixAction::Unstage], false),
+            vec![
+                (FixAction::Unstage, modified.clone()),
+                (FixAction::Unstage, staged.clone()),
+            ]
+        );
+        assert!(!status(&modified).is_index_modified());
+        assert!(status(&modified).is_wd_modified());
+        assert!(!status(&staged).is_index_modified());
+    }
+
+    #[test]
+    fn test_all_affected() {
+        // no `..Default::default()`: a new field has to be added here, and to `all_affected`
+        let diagnostic = SubmodulesDiagnostic {
+            modified_not_staged_submodules: vec![entry("modified_not_staged")],
+            modified_staged_submodules: vec![entry("modified_staged")],
+            uninitialized_submodules: vec![entry("uninitialized")],
+            newly_added_submodules: vec![entry("newly_added")],
+            deleted_submodules: vec![entry("deleted")],
+            detached_head_submodules: vec![entry("detached_head")],
+            unreachable_commit_submodules: vec![entry("unreachable_commit")],
+            pinned_version_mismatch: vec![PinnedMismatch {
+                name: "pinned_version".to_string(),
+                pinned: Oid::zero(),
+                index_oid: None,
+            }],
+            config_mismatch_submodules: vec!["config_mismatch".to_string()],
+            path_conflict_submodules: vec!["path_conflict".to_string()],
+            duplicate_submodules: vec!["duplicate".to_string()],
+            missing_commit_submodules: vec!["missing_commit".to_string()],
+            insecure_url_submodules: vec![(
+                "insecure_url".to_string(),
+                "http://example.com".to_string(),
+            )],
+            uncommitted_changes_submodules: vec!["uncommitted_changes".to_string()],
+            unpushed_submodules: vec!["unpushed".to_string()],
+            url_mismatch_submodules: vec![(
+                "url_mismatch".to_string(),
+                "../url_mismatch.git".to_string(),
+                "https://example.com/url_mismatch.git".to_string(),
+            )],
+        };
+        // one submodule per field
+        let fields = serde_json::to_value(&diagnostic)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .len();
+        assert_eq!(diagnostic.all_affected().count(), fields);
+        let mut names: Vec<&str> = diagnostic.all_affected().collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), fields);
+        assert_eq!(SubmodulesDiagnostic::default().all_affected().count(), 0);
+    }
+
+    #[test]
+    fn test_into_iter_extend() {
+        let sections = [
+            Section::NotStaged,
+            Section::Staged,
+            Section::Uninitialized,
+            Section::NewlyAdded,
+            Section::Deleted,
+            Section::DetachedHead,
+            Section::Unreachable,
+            Section::Pinned,
+            Section::ConfigMismatch,
+            Section::PathConflict,
+            Section::Duplicate,
+            Section::MissingCommit,
+            Section::InsecureUrl,
+            Section::Uncommitted,
+            Section::Unpushed,
+            Section::UrlMismatch,
+        ];
+        // submodules added in reverse order are yielded in the order of the sections
+        let entries: Vec<DiagnosticEntry> = sections
+            .iter()
+            .enumerate()
+            .map(|(i, &status)| DiagnosticEntry {
+                name: format!("sub{i}"),
+                status,
+            })
+            .collect();
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        diagnostic.extend(entries.iter().rev().cloned());
+
+        // one submodule per field
+        let fields = serde_json::to_value(&diagnostic).unwrap();
+        let fields = fields.as_object().unwrap();
+        assert_eq!(fields.len(), sections.len());
+        assert!(
+            fields
+                .values()
+                .all(|field| field.as_array().unwrap().len() == 1)
+        );
+
+        let mut iterated = vec![];
+        for entry in diagnostic {
+            iterated.push(entry);
+        }
+        assert_eq!(iterated, entries);
+        assert_eq!(SubmodulesDiagnostic::default().into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_filter_by_name() {
+        let diagnostic = SubmodulesDiagnostic {
+            modified_staged_submodules: vec![entry("vendor/zlib"), entry("lib")],
+            pinned_version_mismatch: vec![PinnedMismatch {
+                name: "vendor/zlib".to_string(),
+                pinned: Oid::zero(),
+                index_oid: None,
+            }],
+            insecure_url_submodules: vec![("lib".to_string(), "http://example.com".to_string())],
+            unpushed_submodules: vec!["vendor/zlib".to_string(), "lib".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        let filtered =
+            diagnostic.filter_by_name(|name| !is_allowed(name, &["vendor/*".to_string()]));
+        assert_eq!(names(&filtered.modified_staged_submodules), vec!["lib"]);
+        assert!(filtered.pinned_version_mismatch.is_empty());
+        assert_eq!(
+            filtered.insecure_url_submodules,
+            diagnostic.insecure_url_submodules
+        );
+        assert_eq!(filtered.unpushed_submodules, vec!["lib"]);
+
+        assert_eq!(diagnostic.filter_by_name(|_| true), diagnostic);
+        assert!(diagnostic.filter_by_name(|_| false).is_clean());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        diagnostic.modified_staged_submodules.push(entry("outer"));
+        let mut nested = SubmodulesDiagnostic::default();
+        nested.modified_staged_submodules.push(entry("inner"));
+        nested.deleted_submodules.push(entry("gone"));
+        nested.pinned_version_mismatch.push(PinnedMismatch {
+            name: "pinned".to_string(),
+            pinned: Oid::zero(),
+            index_oid: None,
+        });
+        nested
+            .insecure_url_submodules
+            .push(("insecure".to_string(), "http://example.com".to_string()));
+
+        diagnostic.merge(nested.clone(), "outer");
+        assert_eq!(
+            names(&diagnostic.modified_staged_submodules),
+            vec!["outer", "outer/inner"]
+        );
+        assert_eq!(names(&diagnostic.deleted_submodules), vec!["outer/gone"]);
+        assert_eq!(diagnostic.pinned_version_mismatch[0].name, "outer/pinned");
+        assert_eq!(
+            diagnostic.insecure_url_submodules,
+            vec![(
+                "outer/insecure".to_string(),
+                "http://example.com".to_string()
+            )]
+        );
+
+        diagnostic.merge(nested, "");
+        assert_eq!(
+            names(&diagnostic.deleted_submodules),
+            vec!["outer/gone", "gone"]
+        );
+    }
+
+    #[test]
+    fn test_status_cache() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "cached-submodule").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let check = |cache| {
+            check_submodules(
+                false,
+                repo_path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                cache,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let diagnostic = check(true);
+        assert_eq!(diagnostic.modified_not_staged_submodules.len(), 1);
+        let cache_path = parent_repo.path().join("submodule-hook-cache");
+        let content = fs::read_to_string(&cache_path).unwrap();
+        assert!(
+            content
+                .lines()
+                .nth(1)
+                .unwrap()
+                .ends_with(" none cached-submodule")
+        );
+
+        // a cached status is reused while the submodule HEAD is unchanged
+        let clean = SubmoduleStatus::IN_HEAD
+            | SubmoduleStatus::IN_INDEX
+            | SubmoduleStatus::IN_CONFIG
+            | SubmoduleStatus::IN_WD;
+        let (key, line) = content.split_once('\n').unwrap();
+        let (head, _) = line.split_once(' ').unwrap();
+        fs::write(
+            &cache_path,
+            format!("{key}\n{head} {} none cached-submodule\n", clean.bits()),
+        )
+        .unwrap();
+        assert!(check(true).modified_not_staged_submodules.is_empty());
+        assert_eq!(check(false).modified_not_staged_submodules.len(), 1);
+
+        // changing the index invalidates the cache
+        stage_submodule(&parent_repo, &submodule_name).unwrap();
+        let diagnostic = check(true);
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 1);
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+    }
+
+    #[test]
+    fn test_status_cache_misses_file_changes() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "cached-submodule").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let check = |cache| {
+            check_submodules(
+                false,
+                repo_path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                cache,
+            )
+            .unwrap()
+        };
+        let cache_path = parent_repo.path().join("submodule-hook-cache");
+        let cached_bits = || {
+            let content = fs::read_to_string(&cache_path).unwrap();
+            let line = content.lines().nth(1).unwrap().to_string();
+            line.split(' ').nth(1).unwrap().parse::<u32>().unwrap()
+        };
+
+        check(true);
+        let bits = cached_bits();
+        assert_eq!(
+            bits & SubmoduleStatus::WD_WD_MODIFIED.bits(),
+            0,
+            "clean submodule cached as dirty"
+        );
+
+        // editing a file does not move the submodule HEAD: the cached clean status is reused,
+        // which is why the cache is opt-in
+        fs::write(
+            repo_path.join(&submodule_name).join("README.md"),
+            "# Edited\n",
+        )
+        .unwrap();
+        let status = parent_repo
+            .submodule_status(&submodule_name, SubmoduleIgnore::None)
+            .unwrap();
+        assert!(status.contains(SubmoduleStatus::WD_WD_MODIFIED));
+        check(true);
+        assert_eq!(cached_bits(), bits);
+    }
+
+    #[test]
+    fn test_allowed_submodule_is_skipped() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+
+        let (_submodule1_temp_dir, submodule1_name) =
+            add_submodule(&parent_repo, "vendor").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule1_name).unwrap();
+
+        let (_submodule2_temp_dir, submodule2_name) =
+            add_submodule(&parent_repo, "checked").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule2_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let allow_list = vec![submodule1_name];
+        let result = check_submodules(
+            false,
+            repo_path,
+            &allow_list,
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+
+        let diagnostic = result.unwrap().unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec![submodule2_name]
+        );
+    }
+
+    #[test]
+    fn test_nested_submodule_not_checked_by_default() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&outer_name)).unwrap();
+        let (_inner_temp_dir, inner_name) = add_submodule(&outer_repo, "inner").unwrap();
+        modify_submodule_wd(&outer_repo, &inner_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let diagnostic = check_submodules(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["outer"]
+        );
+    }
+
+    #[test]
+    fn test_recursive_nested_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&outer_name)).unwrap();
+        let (_inner_temp_dir, inner_name) = add_submodule(&outer_repo, "inner").unwrap();
+        modify_submodule_wd(&outer_repo, &inner_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let result = check_submodules_recursive(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        );
+        assert!(result.is_ok());
+
+        // outer is modified since a commit adding inner was created in it
+        let diagnostic = result.unwrap().unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["outer", "outer/inner"]
+        );
+    }
+
+    #[test]
+    fn test_recursive_allow_nested_submodule() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&outer_name)).unwrap();
+        let (_inner_temp_dir, inner_name) = add_submodule(&outer_repo, "inner").unwrap();
+        modify_submodule_wd(&outer_repo, &inner_name).unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let allow_list = vec!["outer/inner".to_string()];
+        let diagnostic = check_submodules_recursive(
+            false,
+            repo_path,
+            &allow_list,
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            vec!["outer"]
+        );
+    }
+
+    #[test]
+    fn test_parallel_same_as_serial() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let mut temp_dirs = Vec::new();
+        for name in ["sub_a", "sub_b", "sub_c", "outer"] {
+            temp_dirs.push(add_submodule(&parent_repo, name).unwrap().0);
+        }
+        let outer_repo = Repository::open(parent_repo.workdir().unwrap().join("outer")).unwrap();
+        let (_inner_temp_dir, inner_name) = add_submodule(&outer_repo, "inner").unwrap();
+        modify_submodule_wd(&outer_repo, &inner_name).unwrap();
+        modify_submodule_wd(&parent_repo, "sub_a").unwrap();
+        modify_submodule_wd(&parent_repo, "sub_c").unwrap();
+        stage_submodule(&parent_repo, "sub_c").unwrap();
+
+        let repo_path = parent_repo.workdir().unwrap();
+        let serial = check_submodules_recursive(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        let parallel = check_submodules_parallel(
+            false,
+            repo_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            names(&parallel.modified_not_staged_submodules),
+            vec!["outer", "outer/inner", "sub_a"]
+        );
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn test_is_allowed_exact_name() {
+        let allow_list = vec!["vendor/libfoo".to_string()];
+        assert!(is_allowed("vendor/libfoo", &allow_list));
+        assert!(!is_allowed("vendor/libbar", &allow_list));
+        assert!(!is_allowed("deps/a/b", &allow_list));
+    }
+
+    #[test]
+    fn test_is_allowed_glob_patterns() {
+        let allow_list = vec!["vendor/*".to_string(), "deps/**".to_string()];
+        assert!(is_allowed("vendor/libfoo", &allow_list));
+        assert!(is_allowed("deps/a/b", &allow_list));
+        assert!(!is_allowed("third_party/libfoo", &allow_list));
+        assert!(!is_allowed("vendor", &allow_list));
+    }
+
+    #[test]
+    fn test_is_allowed_invalid_pattern() {
+        let allow_list = vec!["deps/[".to_string()];
+        assert!(is_allowed("deps/[", &allow_list));
+        assert!(!is_allowed("deps/a", &allow_list));
+    }
+
+    #[test]
+    fn test_worktree() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_submodule_temp_dir, submodule_name) =
+            add_submodule(&parent_repo, "worktree-submodule").unwrap();
+        modify_submodule_wd(&parent_repo, &submodule_name).unwrap();
+
+        let worktree_temp_dir = TempDir::new().unwrap();
+        let worktree_path = worktree_temp_dir.path().join("worktree");
+        parent_repo
+            .worktree("worktree", &worktree_path, None)
+            .unwrap();
+        let worktree_repo = Repository::open(&worktree_path).unwrap();
+        assert!(worktree_repo.is_worktree());
+        let check = |path: &Path| {
+            check_submodules(
+                false,
+                path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        // the submodule is only checked out in the main checkout
+        let diagnostic = check(parent_repo.workdir().unwrap());
+        assert_eq!(diagnostic.modified_not_staged_submodules.len(), 1);
+        assert!(diagnostic.uninitialized_submodules.is_empty());
+        let diagnostic = check(&worktree_path);
+        assert!(diagnostic.modified_not_staged_submodules.is_empty());
+        assert_eq!(diagnostic.uninitialized_submodules.len(), 1);
+
+        // once checked out in the worktree, it is inspected relative to the worktree
+        let mut submodule = worktree_repo.find_submodule(&submodule_name).unwrap();
+        submodule.update(true, None).unwrap();
+        modify_submodule_wd(&worktree_repo, &submodule_name).unwrap();
+        let mut diagnostic = check(&worktree_path);
+        assert_eq!(diagnostic.modified_not_staged_submodules.len(), 1);
+        add_commit_summaries(&mut diagnostic, &worktree_path);
+        assert_eq!(
+            diagnostic.modified_not_staged_submodules[0]
+                .head_commit
+                .as_ref()
+                .unwrap()
+                .summary,
+            "Submodule modification"
+        );
+    }
+
+    #[test]
+    fn test_operation_in_progress() {
+        let (_temp_dir, repo) = create_temp_repo().unwrap();
+        let path = repo.workdir().unwrap();
+        assert_eq!(operation_in_progress(path), None);
+
+        let head = repo.head().unwrap().target().unwrap().to_string();
+        fs::write(repo.path().join("MERGE_HEAD"), &head).unwrap();
+        assert_eq!(operation_in_progress(path), Some(GitOperation::Merge));
+        fs::remove_file(repo.path().join("MERGE_HEAD")).unwrap();
+
+        fs::create_dir(repo.path().join("rebase-merge")).unwrap();
+        assert_eq!(operation_in_progress(path), Some(GitOperation::Rebase));
+        fs::write(repo.path().join("rebase-merge/interactive"), "").unwrap();
+        let operation = operation_in_progress(path).unwrap();
+        assert_eq!(operation, GitOperation::InteractiveRebase);
+        assert!(operation.is_rebase());
+    }
+
+    #[test]
+    fn test_open_repository_with_git_env() {
+        let (_temp_dir, repo) = create_temp_repo().unwrap();
+        let work_tree = TempDir::new().unwrap();
+
+        let opened = open_repository_with(
+            Path::new("."),
+            Some(repo.path().into()),
+            Some(work_tree.path().into()),
+        )
+        .unwrap();
+        assert_eq!(opened.path(), repo.path());
+        assert_eq!(
+            opened.workdir().unwrap().canonicalize().unwrap(),
+            work_tree.path().canonicalize().unwrap()
+        );
+
+        // an explicit path is opened as is
+        let opened =
+            open_repository_with(repo.workdir().unwrap(), Some(work_tree.path().into()), None)
+                .unwrap();
+        assert_eq!(opened.path(), repo.path());
+    }
+
+    #[test]
+    fn test_inactive_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_first_temp_dir, first_name) = add_submodule(&parent_repo, "first").unwrap();
+        modify_submodule_wd(&parent_repo, &first_name).unwrap();
+        let (_second_temp_dir, second_name) = add_submodule(&parent_repo, "second").unwrap();
+        modify_submodule_wd(&parent_repo, &second_name).unwrap();
+        let check = || {
+            let mut names: Vec<String> = check_submodules(
+                false,
+                parent_repo.workdir().unwrap(),
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+            .modified_not_staged_submodules
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+            names.sort();
+            names
+        };
+        let mut both = vec![first_name.clone(), second_name.clone()];
+        both.sort();
+        assert_eq!(check(), both);
+
+        let mut config = parent_repo.config().unwrap();
+        config.set_str("submodule.active", &first_name).unwrap();
+        assert_eq!(check(), vec![first_name.clone()]);
+
+        // the per-submodule setting overrides the pathspecs
+        config
+            .set_bool(&format!("submodule.{first_name}.active"), false)
+            .unwrap();
+        assert!(check().is_empty());
+    }
+
+    #[test]
+    fn test_progress_callback() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_first_temp_dir, first_name) = add_submodule(&parent_repo, "first").unwrap();
+        let (_second_temp_dir, second_name) = add_submodule(&parent_repo, "second").unwrap();
+        let reports = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let recorded = reports.clone();
+        // the callback is global: only record the checks run by this test
+        let thread = std::thread::current().id();
+        set_progress_callback(Some(Box::new(move |checked, total, name: &str| {
+            if std::thread::current().id() == thread {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((checked, total, name.to_string()));
+            }
+        })));
+        check_submodules(
+            false,
+            parent_repo.workdir().unwrap(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        )
+        .unwrap();
+        set_progress_callback(None);
+
+        let reports = reports.lock().unwrap().clone();
+        assert_eq!(reports.len(), 2);
+        assert_eq!((reports[0].0, reports[0].1), (1, 2));
+        assert_eq!((reports[1].0, reports[1].1), (2, 2));
+        let mut names: Vec<String> = reports.into_iter().map(|(_, _, name)| name).collect();
+        names.sort();
+        let mut expected = vec![first_name, second_name];
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_url_scheme() {
+        assert_eq!(url_scheme("https://github.com/a/b.git"), "https");
+        assert_eq!(url_scheme("HTTP://example.com/b.git"), "http");
+        assert_eq!(url_scheme("ssh://git@github.com/a/b.git"), "ssh");
+        assert_eq!(url_scheme("git@github.com:a/b.git"), "ssh");
+        assert_eq!(url_scheme("git://example.com/b.git"), "git");
+        assert_eq!(url_scheme("file:///srv/b.git"), "file");
+        assert_eq!(url_scheme("../b.git"), "relative");
+        assert_eq!(url_scheme("/srv/b.git"), "local");
+        assert_eq!(url_scheme("C:\\repos\\b"), "other");
+        assert_eq!(url_scheme("b"), "other");
+    }
+
+    #[test]
+    fn test_check_submodule_urls() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        // submodules of the tests are added from `file://` URLs
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let path = parent_repo.workdir().unwrap();
+
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        check_submodule_urls(&mut diagnostic, path, &[], true).unwrap();
+        check_submodule_urls(&mut diagnostic, path, &["file".to_string()], true).unwrap();
+        assert!(diagnostic.is_clean());
+
+        let allowed = ["https".to_string(), "ssh".to_string()];
+        check_submodule_urls(&mut diagnostic, path, &allowed, false).unwrap();
+        assert_eq!(diagnostic.insecure_url_submodules.len(), 1);
+        assert_eq!(diagnostic.insecure_url_submodules[0].0, sub_name);
+        assert!(matches!(
+            check_submodule_urls(&mut diagnostic, path, &allowed, true),
+            Err(CheckError::DisallowedUrlScheme { scheme: "file", .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_uncommitted_changes() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let path = parent_repo.workdir().unwrap();
+        let sub_path = path.join(&sub_name);
+
+        // untracked files are not uncommitted changes
+        fs::write(sub_path.join("untracked.txt"), "untracked\n").unwrap();
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        check_uncommitted_changes(&mut diagnostic, path, &[]);
+        assert!(diagnostic.uncommitted_changes_submodules.is_empty());
+
+        let sub_repo = Repository::open(&sub_path).unwrap();
+        let tracked = sub_repo.index().unwrap().get(0).unwrap().path;
+        fs::write(
+            sub_path.join(String::from_utf8(tracked).unwrap()),
+            "modified\n",
+        )
+        .unwrap();
+        check_uncommitted_changes(&mut diagnostic, path, &[]);
+        assert_eq!(
+            diagnostic.uncommitted_changes_submodules,
+            vec![sub_name.clone()]
+        );
+
+        let mut diagnostic = SubmodulesDiagnostic::default();
+        check_uncommitted_changes(&mut diagnostic, path, &[sub_name]);
+        assert!(diagnostic.uncommitted_changes_submodules.is_empty());
+    }
+
+    #[test]
+    fn test_missing_commit_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        modify_submodule_wd(&parent_repo, &sub_name).unwrap();
+        stage_submodule(&parent_repo, &sub_name).unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                parent_repo.workdir().unwrap(),
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let diagnostic = check();
+        assert_eq!(diagnostic.modified_staged_submodules.len(), 1);
+        assert!(diagnostic.missing_commit_submodules.is_empty());
+
+        // stage a commit the submodule repository does not have
+        let mut index = parent_repo.index().unwrap();
+        let mut entry = index.get_path(Path::new(&sub_name), 0).unwrap();
+        entry.id = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        index.add(&entry).unwrap();
+        index.write().unwrap();
+        assert_eq!(check().missing_commit_submodules, vec![sub_name]);
+    }
+
+    #[test]
+    fn test_duplicate_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                parent_repo.workdir().unwrap(),
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+            .duplicate_submodules
+        };
+        assert!(check().is_empty());
+
+        let gitmodules_path = parent_repo.workdir().unwrap().join(".gitmodules");
+        let gitmodules = fs::read_to_string(&gitmodules_path).unwrap();
+        fs::write(&gitmodules_path, format!("{gitmodules}{gitmodules}")).unwrap();
+        assert_eq!(check(), vec![sub_name]);
+    }
+
+    #[test]
+    fn test_path_conflict_submodules() {
+        let (temp_dir, repo) = create_temp_repo().unwrap();
+        fs::write(
+            temp_dir.path().join(".gitmodules"),
+            "[submodule \"conflict\"]\n\tpath = conflict\n\turl = https://example.com/conflict.git\n",
+        )
+        .unwrap();
+        // a regular file at the submodule path
+        fs::write(temp_dir.path().join("conflict"), "not a submodule\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitmodules")).unwrap();
+        index.add_path(Path::new("conflict")).unwrap();
+        index.write().unwrap();
+
+        let check = |strict| {
+            check_submodules(
+                strict,
+                temp_dir.path(),
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+        };
+        let diagnostic = check(false).unwrap().unwrap();
+        assert_eq!(
+            diagnostic.path_conflict_submodules,
+            vec!["conflict".to_string()]
+        );
+        assert!(diagnostic.uninitialized_submodules.is_empty());
+        assert!(matches!(
+            check(true),
+            Err(CheckError::PathConflict { ref name, ref path })
+                if name == "conflict" && path == Path::new("conflict")
+        ));
+    }
+
+    #[test]
+    fn test_recursive_path_conflict_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_path = parent_repo.workdir().unwrap().join(&outer_name);
+        let outer_repo = Repository::open(&outer_path).unwrap();
+        fs::write(
+            outer_path.join(".gitmodules"),
+            "[submodule \"conflict\"]\n\tpath = conflict\n\turl = https://example.com/conflict.git\n",
+        )
+        .unwrap();
+        // a regular file at the path of the nested submodule
+        fs::write(outer_path.join("conflict"), "not a submodule\n").unwrap();
+        let mut index = outer_repo.index().unwrap();
+        index.add_path(Path::new(".gitmodules")).unwrap();
+        index.add_path(Path::new("conflict")).unwrap();
+        index.write().unwrap();
+
+        let diagnostic = check_submodules_recursive(
+            false,
+            parent_repo.workdir().unwrap(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            diagnostic.path_conflict_submodules,
+            vec!["outer/conflict".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_url_mismatch_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let repo_path = parent_repo.workdir().unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                repo_path,
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+            .url_mismatch_submodules
+        };
+        assert!(check().is_empty());
+
+        let key = format!("submodule.{sub_name}.url");
+        let mut gitmodules = git2::Config::open(&repo_path.join(".gitmodules")).unwrap();
+        let mut config = parent_repo.config().unwrap();
+        config
+            .set_str(&key, "https://example.com/org/sub.git")
+            .unwrap();
+        gitmodules
+            .set_str(&key, "https://example.com/org/moved.git")
+            .unwrap();
+        assert_eq!(
+            check(),
+            vec![(
+                sub_name.clone(),
+                "https://example.com/org/moved.git".to_string(),
+                "https://example.com/org/sub.git".to_string()
+            )]
+        );
+
+        // relative URL resolved against the remote of the parent repository
+        parent_repo
+            .remote("origin", "https://example.com/org/parent.git")
+            .unwrap();
+        gitmodules.set_str(&key, "../sub.git").unwrap();
+        assert!(check().is_empty());
+    }
+
+    #[test]
+    fn test_recursive_url_mismatch_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&outer_name)).unwrap();
+        let (_inner_temp_dir, inner_name) = add_submodule(&outer_repo, "inner").unwrap();
+        // `git submodule set-url` in the nested repository without `git submodule sync`
+        let key = format!("submodule.{inner_name}.url");
+        let gitmodules_url = git2::Config::open(&outer_repo.workdir().unwrap().join(".gitmodules"))
+            .unwrap()
+            .get_string(&key)
+            .unwrap();
+        outer_repo
+            .config()
+            .unwrap()
+            .set_str(&key, "https://example.com/inner.git")
+            .unwrap();
+
+        let diagnostic = check_submodules_recursive(
+            false,
+            parent_repo.workdir().unwrap(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            diagnostic.url_mismatch_submodules,
+            vec![(
+                format!("outer/{inner_name}"),
+                gitmodules_url,
+                "https://example.com/inner.git".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_url() {
+        assert_eq!(
+            resolve_relative_url("https://example.com/org/app.git", "../lib.git"),
+            "https://example.com/org/lib.git"
+        );
+        assert_eq!(
+            resolve_relative_url("https://example.com/org/app/", "./lib"),
+            "https://example.com/org/app/lib"
+        );
+        assert_eq!(
+            resolve_relative_url("git@example.com:org/app.git", "../../other/lib.git"),
+            "git@example.com:other/lib.git"
+        );
+        assert_eq!(
+            resolve_relative_url("/srv/git/app", "../lib"),
+            "/srv/git/lib"
+        );
+    }
+
+    #[test]
+    fn test_config_mismatch_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_sub_temp_dir, sub_name) = add_submodule(&parent_repo, "sub").unwrap();
+        let check = || {
+            check_submodules(
+                false,
+                parent_repo.workdir().unwrap(),
+                &[],
+                &HashMap::new(),
+                SubmoduleIgnore::None,
+                false,
+            )
+            .unwrap()
+            .unwrap()
+            .config_mismatch_submodules
+        };
+        assert!(check().is_empty());
+
+        // left over after removing the submodule from `.gitmodules`
+        let mut config = parent_repo.config().unwrap();
+        config
+            .set_str("submodule.old.url", "https://example.com/old.git")
+            .unwrap();
+        assert_eq!(check(), vec!["old".to_string()]);
+
+        // registered in both
+        config
+            .set_str(
+                &format!("submodule.{sub_name}.url"),
+                "https://example.com/sub.git",
+            )
+            .unwrap();
+        assert_eq!(check(), vec!["old".to_string()]);
+    }
+
+    #[test]
+    fn test_recursive_config_mismatch_submodules() {
+        let (_parent_temp_dir, parent_repo) = create_temp_repo().unwrap();
+        let (_outer_temp_dir, outer_name) = add_submodule(&parent_repo, "outer").unwrap();
+        let outer_repo =
+            Repository::open(parent_repo.workdir().unwrap().join(&outer_name)).unwrap();
+        // stale sections in the parent repository and in the nested one
+        parent_repo
+            .config()
+            .unwrap()
+            .set_str("submodule.top.url", "https://example.com/top.git")
+            .unwrap();
+        outer_repo
+            .config()
+            .unwrap()
+            .set_str("submodule.old.url", "https://example.com/old.git")
+            .unwrap();
+
+        let diagnostic = check_submodules_recursive(
+            false,
+            parent_repo.workdir().unwrap(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            DEFAULT_MAX_DEPTH,
+            false,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            diagnostic.config_mismatch_submodules,
+            vec!["top".to_string(), "outer/old".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_bare_repository() {
+        let temp_dir = TempDir::new().unwrap();
+        Repository::init_bare(temp_dir.path()).unwrap();
+
+        let result = check_submodules(
+            true,
+            temp_dir.path(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(
+            matches!(result, Err(CheckError::BareRepository(ref path)) if path == temp_dir.path())
+        );
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("is a bare repository")
+        );
+
+        let result = check_submodules(
+            false,
+            temp_dir.path(),
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(matches!(result, Ok(None)));
+    }
+
+    #[test]
+    fn test_strict_mode_invalid_repo() {
+        let temp_dir = TempDir::new().unwrap();
+        let invalid_path = temp_dir.path();
+
+        // This should fail in strict mode
+        let result = check_submodules(
+            true,
+            invalid_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(matches!(result, Err(CheckError::RepoOpenFailed(_))));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Unable to open repository")
+        );
+    }
+
+    #[test]
+    fn test_non_strict_mode_invalid_repo() {
+        let temp_dir = TempDir::new().unwrap();
+        let invalid_path = temp_dir.path();
+
+        // This should return Ok(None) in non-strict mode
+        let result = check_submodules(
+            false,
+            invalid_path,
+            &[],
+            &HashMap::new(),
+            SubmoduleIgnore::None,
+            false,
+        );
+        assert!(result.is_ok());
+        assert!(result.unwrap().is_none());
+    }
+
+    // ========== Fake repository ==========
+
+    /// Predetermined submodule of a [`FakeRepositoryProvider`]
+    struct FakeSubmodule {
+        status: SubmoduleStatus,
+        /// `None` if the submodule directory does not contain a repository
+        checkout: Option<SubmoduleCheckout>,
+    }
+
+    /// Repository whose submodules have predetermined statuses, checked without touching the
+    /// file system
+    #[derive(Default)]
+    struct FakeRepositoryProvider {
+        submodules: HashMap<String, FakeSubmodule>,
+    }
+
+    impl FakeRepositoryProvider {
+        fn with(
+            mut self,
+            name: &str,
+            status: SubmoduleStatus,
+            checkout: Option<SubmoduleCheckout>,
+        ) -> Self {
+            self.submodules
+                .insert(String::from(name), FakeSubmodule { status, checkout });
+            self
+        }
+
+        fn find(&self, name: &str) -> Result<&FakeSubmodule, git2::Error> {
+            self.submodules.get(name).ok_or_else(|| {
+                git2::Error::new(
+                    ErrorCode::NotFound,
+                    git2::ErrorClass::Submodule,
+                    format!("no submodule named '{name}'"),
+                )
+            })
+        }
+    }
+
+    impl RepositoryProvider for FakeRepositoryProvider {
+        fn submodule_entry(&self, name: &str) -> Result<SubmoduleEntry, git2::Error> {
+            self.find(name)?;
+            Ok(SubmoduleEntry {
+                name: String::from(name),
+                path: format!("libs/{name}"),
+                head_oid: Some(Oid::from_bytes(&[1; 20]).unwrap()),
+                index_oid: Some(Oid::from_bytes(&[2; 20]).unwrap()),
+                workdir_head: Some(Oid::from_bytes(&[3; 20]).unwrap()),
+                branch: None,
+                ahead_behind: None,
+                head_commit: None,
+                changed_files: None,
+            })
+        }
+
+        fn submodule_status(
+            &self,
+            name: &str,
+            _ignore: SubmoduleIgnore,
+        ) -> Result<SubmoduleStatus, git2::Error> {
+            Ok(self.find(name)?.status)
+        }
+
+        fn submodule_checkout(
+            &self,
+            name: &str,
+            _index_oid: Option<Oid>,
+        ) -> Result<SubmoduleCheckout, git2::Error> {
+            self.find(name)?.checkout.clone().ok_or_else(|| {
+                git2::Error::new(
+                    ErrorCode::NotFound,
+                    git2::ErrorClass::Repository,
+                    format!("submodule '{name}' is not a repository"),
+                )
+            })
+        }
+    }
+
+    /// Diagnostic of the submodule `name` of `provider`, nested submodules being ignored
+    fn check_fake(provider: &FakeRepositoryProvider, name: &str) -> SubmodulesDiagnostic {
+        let ignore_policies = HashMap::new();
+        let options = CheckOptions {
+            strict: false,
+            allow_list: &[],
+            ignore_policies: &ignore_policies,
+            default_ignore: SubmoduleIgnore::None,
+            parallel: false,
+            cache: false,
+        };
+        check_submodule(&options, provider, name, "", 1, None).unwrap()
+    }
+
+    fn checkout() -> Option<SubmoduleCheckout> {
+        Some(SubmoduleCheckout::default())
+    }
+
+    #[test]
+    fn test_fake_clean_submodule() {
+        let provider = FakeRepositoryProvider::default().with(
+            "clean",
+            SubmoduleStatus::IN_HEAD
+                | SubmoduleStatus::IN_INDEX
+                | SubmoduleStatus::IN_CONFIG
+                | SubmoduleStatus::IN_WD,
+            checkout(),
+        );
+        assert!(check_fake(&provider, "clean").is_clean());
+    }
+
+    #[test]
+    fn test_fake_status_flags() {
+        let provider = FakeRepositoryProvider::default()
+            .with("not_staged", SubmoduleStatus::WD_MODIFIED, checkout())
+            .with("staged", SubmoduleStatus::INDEX_MODIFIED, checkout())
+            .with("added", SubmoduleStatus::INDEX_ADDED, checkout())
+            .with("deleted", SubmoduleStatus::INDEX_DELETED, checkout())
+            .with("uninitialized", SubmoduleStatus::WD_UNINITIALIZED, None);
+
+        let diagnostic = check_fake(&provider, "not_staged");
+        assert_eq!(
+            names(&diagnostic.modified_not_staged_submodules),
+            ["not_staged"]
+        );
+        assert_eq!(diagnostic.all_affected().count(), 1);
+
+        let diagnostic = check_fake(&provider, "staged");
+        assert_eq!(names(&diagnostic.modified_staged_submodules), ["staged"]);
+        assert_eq!(diagnostic.all_affected().count(), 1);
+
+        let diagnostic = check_fake(&provider, "added");
+        assert_eq!(names(&diagnostic.newly_added_submodules), ["added"]);
+
+        let diagnostic = check_fake(&provider, "deleted");
+        assert_eq!(names(&diagnostic.deleted_submodules), ["deleted"]);
+
+        let diagnostic = check_fake(&provider, "uninitialized");
+        assert_eq!(
+            names(&diagnostic.uninitialized_submodules),
+            ["uninitialized"]
+        );
+    }
+
+    #[test]
+    fn test_fake_not_a_repository() {
+        // directory created by hand in place of the submodule
+        let provider = FakeRepositoryProvider::default().with(
+            "sub",
+            SubmoduleStatus::IN_HEAD
+                | SubmoduleStatus::IN_INDEX
+                | SubmoduleStatus::IN_WD
+                | SubmoduleStatus::WD_DELETED,
+            None,
+        );
+        let diagnostic = check_fake(&provider, "sub");
+        assert_eq!(names(&diagnostic.uninitialized_submodules), ["sub"]);
+        assert_eq!(diagnostic.uninitialized_submodules[0].path, "libs/sub");
+        assert_eq!(diagnostic.all_affected().count(), 1);
+    }
+
+    #[test]
+    fn test_fake_detached_head() {
+        let provider = FakeRepositoryProvider::default().with(
+            "sub",
+            SubmoduleStatus::IN_WD,
+            Some(SubmoduleCheckout {
+                head_detached: true,
+                ..SubmoduleCheckout::default()
+            }),
+        );
+        let diagnostic = check_fake(&provider, "sub");
+        assert_eq!(names(&diagnostic.detached_head_submodules), ["sub"]);
+    }
+
+    #[test]
+    fn test_fake_missing_commit() {
+        let missing = Some(SubmoduleCheckout {
+            has_index_commit: Some(false),
+            ..SubmoduleCheckout::default()
+        });
+        let provider = FakeRepositoryProvider::default()
+            .with("staged", SubmoduleStatus::INDEX_MODIFIED, missing.clone())
+            .with("added", SubmoduleStatus::INDEX_ADDED, missing.clone())
+            .with("not_staged", SubmoduleStatus::WD_MODIFIED, missing);
+
+        assert_eq!(
+            check_fake(&provider, "staged").missing_commit_submodules,
+            ["staged"]
+        );
+        assert_eq!(
+            check_fake(&provider, "added").missing_commit_submodules,
+            ["added"]
+        );
+        // only commits recorded in the index are looked up
+        assert!(
+            check_fake(&provider, "not_staged")
+                .missing_commit_submodules
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_fake_unknown_submodule() {
+        let ignore_policies = HashMap::new();
+        let options = CheckOptions {
+            strict: false,
+            allow_list: &[],
+            ignore_policies: &ignore_policies,
+            default_ignore: SubmoduleIgnore::None,
+            parallel: false,
+            cache: false,
+        };
+        let provider = FakeRepositoryProvider::default();
+        assert!(matches!(
+            check_submodule(&options, &provider, "unknown", "", 1, None),
+            Err(CheckError::SubmoduleStatusFailed { name, .. }) if name == "unknown"
+        ));
+    }
+
+    // ========== Property-based tests ==========
+
+    mod properties {
+        use super::*;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        fn name() -> impl Strategy<Value = String> {
+            "[a-z]{1,3}(/[a-z]{1,3})?"
+        }
+
+        fn oid() -> impl Strategy<Value = Option<Oid>> {
+            proptest::option::of(
+                any::<[u8; 20]>().prop_map(|bytes| Oid::from_bytes(&bytes).unwrap()),
+            )
+        }
+
+        fn entries() -> impl Strategy<Value = Vec<SubmoduleEntry>> {
+            let entry = (
+                name(),
+                oid(),
+                oid(),
+                oid(),
+                proptest::option::of("[a-z]{1,5}"),
+            )
+                .prop_map(
+                    |(name, head_oid, index_oid, workdir_head, branch)| SubmoduleEntry {
+                        path: name.clone(),
+                        name,
+                        head_oid,
+                        index_oid,
+                        workdir_head,
+                        branch,
+                        ahead_behind: None,
+                        head_commit: None,
+                        changed_files: None,
+                    },
+                );
+            vec(entry, 0..3)
+        }
+
+        fn names() -> impl Strategy<Value = Vec<String>> {
+            vec(name(), 0..3)
+        }
+
+        fn diagnostic() -> impl Strategy<Value = SubmodulesDiagnostic> {
+            let pinned = vec(
+                (name(), any::<[u8; 20]>(), oid()).prop_map(|(name, pinned, index_oid)| {
+                    PinnedMismatch {
+                        name,
+                        pinned: Oid::from_bytes(&pinned).unwrap(),
+                        index_oid,
+                    }
+                }),
+                0..3,
+            );
+            (
+                (
+                    entries(),
+                    entries(),
+                    entries(),
+                    entries(),
+                    entries(),
+                    entries(),
+                    entries(),
+                ),
+                (names(), names(), names(), names(), names(), names()),
+                pinned,
+                vec((name(), "[a-z]{1,8}"), 0..3),
+                vec((name(), "[a-z]{1,8}", "[a-z]{1,8}"), 0..3),
+            )
+                .prop_map(
+                    |(
+                        (
+                            modified_not_staged_submodules,
+                            modified_staged_submodules,
+                            uninitialized_submodules,
+                            newly_added_submodules,
+                            deleted_submodules,
+                            detached_head_submodules,
+                            unreachable_commit_submodules,
+                        ),
+                        (
+                            config_mismatch_submodules,
+                            path_conflict_submodules,
+                            duplicate_submodules,
+                            missing_commit_submodules,
+                            uncommitted_changes_submodules,
+                            unpushed_submodules,
+                        ),
+                        pinned_version_mismatch,
+                        insecure_url_submodules,
+                        url_mismatch_submodules,
+                    )| SubmodulesDiagnostic {
+                        modified_not_staged_submodules,
+                        modified_staged_submodules,
+                        uninitialized_submodules,
+                        newly_added_submodules,
+                        deleted_submodules,
+                        detached_head_submodules,
+                        unreachable_commit_submodules,
+                        pinned_version_mismatch,
+                        config_mismatch_submodules,
+                        path_conflict_submodules,
+                        duplicate_submodules,
+                        missing_commit_submodules,
+                        insecure_url_submodules,
+                        uncommitted_changes_submodules,
+                        unpushed_submodules,
+                        url_mismatch_submodules,
+                    },
+                )
+        }
+
+        /// Number of reported submodules, summed over every field
+        fn len(diagnostic: &SubmodulesDiagnostic) -> usize {
+            // destructured so that new fields cannot be forgotten
+            let SubmodulesDiagnostic {
+                modified_not_staged_submodules,
+                modified_staged_submodules,
+                uninitialized_submodules,
+                newly_added_submodules,
+                deleted_submodules,
+                detached_head_submodules,
+                unreachable_commit_submodules,
+                pinned_version_mismatch,
+                config_mismatch_submodules,
+                path_conflict_submodules,
+                duplicate_submodules,
+                missing_commit_submodules,
+                insecure_url_submodules,
+                uncommitted_changes_submodules,
+                unpushed_submodules,
+                url_mismatch_submodules,
+            } = diagnostic;
+            modified_not_staged_submodules.len()
+                + modified_staged_submodules.len()
+                + uninitialized_submodules.len()
+                + newly_added_submodules.len()
+                + deleted_submodules.len()
+                + detached_head_submodules.len()
+                + unreachable_commit_submodules.len()
+                + pinned_version_mismatch.len()
+                + config_mismatch_submodules.len()
+                + path_conflict_submodules.len()
+                + duplicate_submodules.len()
+                + missing_commit_submodules.len()
+                + insecure_url_submodules.len()
+                + uncommitted_changes_submodules.len()
+                + unpushed_submodules.len()
+                + url_mismatch_submodules.len()
+        }
+
+        /// Affected names sorted, as a multiset
+        fn affected(diagnostic: &SubmodulesDiagnostic) -> Vec<String> {
+            let mut names: Vec<String> = diagnostic.all_affected().map(String::from).collect();
+            names.sort();
+            names
+        }
+
+        proptest! {
+            #[test]
+            fn is_clean_iff_empty(diagnostic in diagnostic()) {
+                prop_assert_eq!(diagnostic.is_clean(), len(&diagnostic) == 0);
+            }
+
+            #[test]
+            fn all_affected_counts_every_field(diagnostic in diagnostic()) {
+                prop_assert_eq!(diagnostic.all_affected().count(), len(&diagnostic));
+            }
+
+            #[test]
+            fn merge_keeps_every_entry(
+                mut diagnostic in diagnostic(),
+                other in diagnostic(),
+                prefix in "([a-z]{1,3})?",
+            ) {
+                let expected = len(&diagnostic) + len(&other);
+                diagnostic.merge(other, &prefix);
+                prop_assert_eq!(len(&diagnostic), expected);
+                prop_assert_eq!(diagnostic.all_affected().count(), expected);
+            }
+
+            #[test]
+            fn filter_by_name_only_keeps_original_entries(
+                diagnostic in diagnostic(),
+                letter in "[a-z]",
+            ) {
+                let predicate = |name: &str| name.contains(letter.as_str());
+                let filtered = diagnostic.filter_by_name(predicate);
+                let original = affected(&diagnostic);
+                let kept = affected(&filtered);
+                prop_assert!(kept.iter().all(|name| predicate(name)));
+                let expected: Vec<String> = original
+                    .into_iter()
+                    .filter(|name| predicate(name))
+                    .collect();
+                prop_assert_eq!(kept, expected);
+            }
+
+            #[test]
+            fn into_iter_yields_every_affected_submodule(diagnostic in diagnostic()) {
+                let mut names: Vec<String> = diagnostic
+                    .clone()
+                    .into_iter()
+                    .map(|entry| entry.name)
+                    .collect();
+                names.sort();
+                prop_assert_eq!(names, affected(&diagnostic));
+            }
+
+            #[test]
+            fn extend_restores_iterated_entries(diagnostic in diagnostic()) {
+                let entries: Vec<DiagnosticEntry> = diagnostic.into_iter().collect();
+                let mut rebuilt = SubmodulesDiagnostic::default();
+                rebuilt.extend(entries.clone());
+                prop_assert_eq!(rebuilt.into_iter().collect::<Vec<_>>(), entries);
+            }
+        }
+    }
+}
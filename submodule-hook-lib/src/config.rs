@@ -0,0 +1,1695 @@
+use crate::check_submodules::{SortOrder, open_repository};
+use git2::{Config as GitConfig, Oid, SubmoduleIgnore};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+#[derive(Default, serde::Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    pub strict: Option<bool>,
+    pub confirm_staging: Option<bool>,
+    pub confirm_not_staging: Option<bool>,
+    pub confirm_uninitialized: Option<bool>,
+    pub confirm_new_submodule: Option<bool>,
+    pub confirm_deletion: Option<bool>,
+    pub confirm_detached_head: Option<bool>,
+    pub non_interactive: Option<bool>,
+    pub allow_list: Vec<String>,
+    /// Submodule URL schemes allowed, see `check_submodules::url_scheme`, all if empty
+    pub allowed_schemes: Vec<String>,
+    pub recursive: Option<bool>,
+    pub max_depth: Option<usize>,
+    pub prompt_message: Option<String>,
+    pub timeout_secs: Option<u64>,
+    pub timeout_default: Option<bool>,
+    pub lock_timeout_secs: Option<u64>,
+    pub dry_run: Option<bool>,
+    pub warn_untracked_branch: Option<bool>,
+    pub show_ahead_behind: Option<bool>,
+    pub verify_remote: Option<bool>,
+    pub parallel: Option<bool>,
+    pub allow_dirty: Option<bool>,
+    pub show_commit_message: Option<bool>,
+    pub auto_stage: Option<bool>,
+    pub warn_only: Option<bool>,
+    pub skip_rebase: Option<bool>,
+    pub warn_amending: Option<bool>,
+    pub multi_select: Option<bool>,
+    pub show_files: Option<bool>,
+    pub check_dirty: Option<bool>,
+    pub check_unpushed: Option<bool>,
+    pub progress: Option<bool>,
+    pub cache: Option<bool>,
+    pub default_confirm: Option<bool>,
+    pub ascii: Option<bool>,
+    pub sort: Option<SortOrder>,
+    /// JSON Lines file each hook run is appended to (`submodulehook.auditlog`)
+    pub audit_log: Option<PathBuf>,
+    /// Command long confirmation messages are piped to, disabled if empty
+    /// (`submodulehook.pager`)
+    pub pager: Option<String>,
+    pub exit_code_declined: Option<u8>,
+    pub exit_code_cancelled: Option<u8>,
+    pub exit_code_check_error: Option<u8>,
+    /// Ignore policy of submodules by name (`submodulehook.<name>.ignore`)
+    #[serde(with = "ignore_policies_serde")]
+    pub ignore_policies: HashMap<String, SubmoduleIgnore>,
+    /// Commit submodules are pinned to by name (`submodulehook.<name>.pin`)
+    #[serde(with = "pins_serde")]
+    pub pins: HashMap<String, Oid>,
+}
+
+/// Parse a submodule ignore policy, same values as git `submodule.<name>.ignore`
+pub fn parse_ignore_policy(value: &str) -> Option<SubmoduleIgnore> {
+    match value.trim() {
+        "none" => Some(SubmoduleIgnore::None),
+        "untracked" => Some(SubmoduleIgnore::Untracked),
+        "dirty" => Some(SubmoduleIgnore::Dirty),
+        "all" => Some(SubmoduleIgnore::All),
+        _ => None,
+    }
+}
+
+/// Name of `policy` as accepted by `parse_ignore_policy`
+pub(crate) fn ignore_policy_name(policy: SubmoduleIgnore) -> &'static str {
+    match policy {
+        SubmoduleIgnore::Untracked => "untracked",
+        SubmoduleIgnore::Dirty => "dirty",
+        SubmoduleIgnore::All => "all",
+        SubmoduleIgnore::None | SubmoduleIgnore::Unspecified => "none",
+    }
+}
+
+/// Parse the ignore policy of submodule `name`, ignoring invalid values
+fn read_ignore_policy(
+    policies: &mut HashMap<String, SubmoduleIgnore>,
+    option: &str,
+    name: &str,
+    value: &str,
+) {
+    match parse_ignore_policy(value) {
+        Some(policy) => {
+            policies.insert(name.to_string(), policy);
+        }
+        None => {
+            warn!("invalid value for {option}: {value} (expected none, untracked, dirty or all)")
+        }
+    }
+}
+
+/// Parse the commit submodule `name` is pinned to, ignoring invalid values
+fn read_pin(pins: &mut HashMap<String, Oid>, option: &str, name: &str, value: &str) {
+    match Oid::from_str(value.trim()) {
+        Ok(oid) => {
+            pins.insert(name.to_string(), oid);
+        }
+        Err(e) => warn!("invalid value for {option}: {value} ({})", e.message()),
+    }
+}
+
+/// (De)serialize pinned commits by name as hex strings, e.g. `{"vendor/lib": "3ca86a8..."}`
+mod pins_serde {
+    use git2::Oid;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        pins: &HashMap<String, Oid>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(pins.iter().map(|(name, oid)| (name, oid.to_string())))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, Oid>, D::Error> {
+        HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| {
+                Oid::from_str(&value)
+                    .map(|oid| (name, oid))
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect()
+    }
+}
+
+/// (De)serialize ignore policies by name, e.g. `{"vendor/lib": "dirty"}`
+mod ignore_policies_serde {
+    use git2::SubmoduleIgnore;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        policies: &HashMap<String, SubmoduleIgnore>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            policies
+                .iter()
+                .map(|(name, policy)| (name, super::ignore_policy_name(*policy))),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<String, SubmoduleIgnore>, D::Error> {
+        HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| match super::parse_ignore_policy(&value) {
+                Some(policy) => Ok((name, policy)),
+                None => Err(serde::de::Error::custom(format!(
+                    "invalid ignore policy `{value}`"
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Prompt shown when `submodulehook.prompt` is not set
+pub const DEFAULT_PROMPT: &str = "Do you wish to continue anyway?";
+
+/// Read a boolean override from the environment variable `name`
+fn read_env(name: &str) -> Option<bool> {
+    let value = std::env::var(name).ok();
+    if let Some(value) = &value {
+        debug!("found env: {name} = {value}");
+    }
+    parse_env_bool(value.as_deref())
+}
+
+/// Boolean override of an environment variable `value`, `None` if the variable is not set
+fn parse_env_bool(value: Option<&str>) -> Option<bool> {
+    value.map(|value| value == "true")
+}
+
+/// Parse a numeric or enumerated value (depth, seconds, sort order...), ignoring invalid values
+fn parse_number<T>(option: &str, value: &str) -> Option<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    match value.trim().parse() {
+        Ok(depth) => Some(depth),
+        Err(e) => {
+            warn!("invalid value for {option}: {value} ({e})");
+            None
+        }
+    }
+}
+
+/// Path of `value`, a leading `~/` standing for the home directory
+fn expand_home(value: &str) -> PathBuf {
+    match (value.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(relative), Some(home)) => PathBuf::from(home).join(relative),
+        _ => PathBuf::from(value),
+    }
+}
+
+/// Parse a comma-separated list of submodule names
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Where a configuration value comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    System,
+    Global,
+    GlobalFile,
+    LocalFile,
+    Local,
+    Env,
+    Cli,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self {
+            ConfigSource::Default => "default",
+            ConfigSource::System => "system",
+            ConfigSource::Global => "global",
+            ConfigSource::GlobalFile => "global file",
+            ConfigSource::LocalFile => "local file",
+            ConfigSource::Local => "local",
+            ConfigSource::Env => "env",
+            ConfigSource::Cli => "cli",
+        };
+        write!(f, "{source}")
+    }
+}
+
+impl HookConfig {
+    /// Combine two configurations, the options set in `higher_priority` overriding the ones
+    /// set in `self`.
+    ///
+    /// A non-empty `allow_list` (or `allowed_schemes`) replaces the lower priority one.
+    pub fn merge(self, higher_priority: HookConfig) -> HookConfig {
+        HookConfig {
+            strict: higher_priority.strict.or(self.strict),
+            confirm_staging: higher_priority.confirm_staging.or(self.confirm_staging),
+            confirm_not_staging: higher_priority
+                .confirm_not_staging
+                .or(self.confirm_not_staging),
+            confirm_uninitialized: higher_priority
+                .confirm_uninitialized
+                .or(self.confirm_uninitialized),
+            confirm_new_submodule: higher_priority
+                .confirm_new_submodule
+                .or(self.confirm_new_submodule),
+            confirm_deletion: higher_priority.confirm_deletion.or(self.confirm_deletion),
+            confirm_detached_head: higher_priority
+                .confirm_detached_head
+                .or(self.confirm_detached_head),
+            non_interactive: higher_priority.non_interactive.or(self.non_interactive),
+            allow_list: if higher_priority.allow_list.is_empty() {
+                self.allow_list
+            } else {
+                higher_priority.allow_list
+            },
+            allowed_schemes: if higher_priority.allowed_schemes.is_empty() {
+                self.allowed_schemes
+            } else {
+                higher_priority.allowed_schemes
+            },
+            recursive: higher_priority.recursive.or(self.recursive),
+            max_depth: higher_priority.max_depth.or(self.max_depth),
+            prompt_message: higher_priority.prompt_message.or(self.prompt_message),
+            timeout_secs: higher_priority.timeout_secs.or(self.timeout_secs),
+            timeout_default: higher_priority.timeout_default.or(self.timeout_default),
+            lock_timeout_secs: higher_priority.lock_timeout_secs.or(self.lock_timeout_secs),
+            dry_run: higher_priority.dry_run.or(self.dry_run),
+            warn_untracked_branch: higher_priority
+                .warn_untracked_branch
+                .or(self.warn_untracked_branch),
+            show_ahead_behind: higher_priority.show_ahead_behind.or(self.show_ahead_behind),
+            verify_remote: higher_priority.verify_remote.or(self.verify_remote),
+            parallel: higher_priority.parallel.or(self.parallel),
+            allow_dirty: higher_priority.allow_dirty.or(self.allow_dirty),
+            show_commit_message: higher_priority
+                .show_commit_message
+                .or(self.show_commit_message),
+            auto_stage: higher_priority.auto_stage.or(self.auto_stage),
+            warn_only: higher_priority.warn_only.or(self.warn_only),
+            skip_rebase: higher_priority.skip_rebase.or(self.skip_rebase),
+            warn_amending: higher_priority.warn_amending.or(self.warn_amending),
+            multi_select: higher_priority.multi_select.or(self.multi_select),
+            show_files: higher_priority.show_files.or(self.show_files),
+            check_dirty: higher_priority.check_dirty.or(self.check_dirty),
+            check_unpushed: higher_priority.check_unpushed.or(self.check_unpushed),
+            progress: higher_priority.progress.or(self.progress),
+            cache: higher_priority.cache.or(self.cache),
+            default_confirm: higher_priority.default_confirm.or(self.default_confirm),
+            ascii: higher_priority.ascii.or(self.ascii),
+            sort: higher_priority.sort.or(self.sort),
+            audit_log: higher_priority.audit_log.or(self.audit_log),
+            pager: higher_priority.pager.or(self.pager),
+            exit_code_declined: higher_priority
+                .exit_code_declined
+                .or(self.exit_code_declined),
+            exit_code_cancelled: higher_priority
+                .exit_code_cancelled
+                .or(self.exit_code_cancelled),
+            exit_code_check_error: higher_priority
+                .exit_code_check_error
+                .or(self.exit_code_check_error),
+            ignore_policies: {
+                let mut ignore_policies = self.ignore_policies;
+                ignore_policies.extend(higher_priority.ignore_policies);
+                ignore_policies
+            },
+            pins: {
+                let mut pins = self.pins;
+                pins.extend(higher_priority.pins);
+                pins
+            },
+        }
+    }
+
+    /// Git config key and value of each option, `None` if the option is not set
+    fn entries(&self) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("strict", to_string(&self.strict)),
+            ("staging", to_string(&self.confirm_staging)),
+            ("notstaging", to_string(&self.confirm_not_staging)),
+            ("uninitialized", to_string(&self.confirm_uninitialized)),
+            ("newsubmodule", to_string(&self.confirm_new_submodule)),
+            ("deletion", to_string(&self.confirm_deletion)),
+            ("detachedhead", to_string(&self.confirm_detached_head)),
+            ("noninteractive", to_string(&self.non_interactive)),
+            (
+                "allow",
+                (!self.allow_list.is_empty()).then(|| self.allow_list.join(",")),
+            ),
+            (
+                "allowedschemes",
+                (!self.allowed_schemes.is_empty()).then(|| self.allowed_schemes.join(",")),
+            ),
+            ("recursive", to_string(&self.recursive)),
+            ("maxdepth", to_string(&self.max_depth)),
+            ("prompt", self.prompt_message.clone()),
+            ("timeout", to_string(&self.timeout_secs)),
+            ("timeoutdefault", to_string(&self.timeout_default)),
+            ("locktimeout", to_string(&self.lock_timeout_secs)),
+            ("dryrun", to_string(&self.dry_run)),
+            (
+                "warnuntrackedbranch",
+                to_string(&self.warn_untracked_branch),
+            ),
+            ("aheadbehind", to_string(&self.show_ahead_behind)),
+            ("verifyremote", to_string(&self.verify_remote)),
+            ("parallel", to_string(&self.parallel)),
+            ("allowdirty", to_string(&self.allow_dirty)),
+            ("showcommitmessage", to_string(&self.show_commit_message)),
+            ("autostage", to_string(&self.auto_stage)),
+            ("warnonly", to_string(&self.warn_only)),
+            ("skiprebase", to_string(&self.skip_rebase)),
+            ("warnamending", to_string(&self.warn_amending)),
+            ("multiselect", to_string(&self.multi_select)),
+            ("showfiles", to_string(&self.show_files)),
+            ("checkdirty", to_string(&self.check_dirty)),
+            ("checkunpushed", to_string(&self.check_unpushed)),
+            ("progress", to_string(&self.progress)),
+            ("cache", to_string(&self.cache)),
+            ("defaultconfirm", to_string(&self.default_confirm)),
+            ("ascii", to_string(&self.ascii)),
+            ("sort", to_string(&self.sort)),
+            (
+                "auditlog",
+                self.audit_log
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            ("pager", self.pager.clone()),
+            ("exitcode.declined", to_string(&self.exit_code_declined)),
+            ("exitcode.cancelled", to_string(&self.exit_code_cancelled)),
+            (
+                "exitcode.checkerror",
+                to_string(&self.exit_code_check_error),
+            ),
+            ("pin", {
+                let mut pins: Vec<String> = self
+                    .pins
+                    .iter()
+                    .map(|(name, oid)| format!("{name}={oid}"))
+                    .collect();
+                pins.sort();
+                (!pins.is_empty()).then(|| pins.join(","))
+            }),
+            ("ignore", {
+                let mut policies: Vec<String> = self
+                    .ignore_policies
+                    .iter()
+                    .map(|(name, policy)| format!("{name}={}", ignore_policy_name(*policy)))
+                    .collect();
+                policies.sort();
+                (!policies.is_empty()).then(|| policies.join(","))
+            }),
+        ]
+    }
+}
+
+/// `HookConfigBuilder` state before `confirm_staging` or `confirm_not_staging` is set
+pub struct NoStagingConfig;
+
+/// `HookConfigBuilder` state once `confirm_staging` or `confirm_not_staging` is set
+pub struct HasStagingConfig;
+
+/// Builder of a `HookConfig`, `build` being only available once `confirm_staging` or
+/// `confirm_not_staging` is set, so that library callers do not rely on their defaults.
+///
+/// ```
+/// use submodule_hook_lib::HookConfig;
+///
+/// let config = HookConfig::builder()
+///     .confirm_staging(true)
+///     .strict(true)
+///     .allow_list(vec!["vendor/*".to_string()])
+///     .build();
+/// assert_eq!(config.confirm_staging, Some(true));
+/// assert_eq!(config.confirm_not_staging, None);
+/// ```
+///
+/// ```compile_fail
+/// use submodule_hook_lib::HookConfig;
+///
+/// // neither `confirm_staging` nor `confirm_not_staging` is set
+/// let config = HookConfig::builder().strict(true).build();
+/// ```
+pub struct HookConfigBuilder<State> {
+    config: HookConfig,
+    state: std::marker::PhantomData<State>,
+}
+
+/// Setters of the optional `HookConfig` fields
+macro_rules! setters {
+    ($($field:ident: $type:ty),* $(,)?) => {
+        $(
+            pub fn $field(mut self, value: $type) -> Self {
+                self.config.$field = Some(value);
+                self
+            }
+        )*
+    };
+}
+
+impl HookConfig {
+    pub fn builder() -> HookConfigBuilder<NoStagingConfig> {
+        HookConfigBuilder {
+            config: HookConfig::default(),
+            state: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<State> HookConfigBuilder<State> {
+    pub fn confirm_staging(self, value: bool) -> HookConfigBuilder<HasStagingConfig> {
+        let mut config = self.config;
+        config.confirm_staging = Some(value);
+        HookConfigBuilder {
+            config,
+            state: std::marker::PhantomData,
+        }
+    }
+
+    pub fn confirm_not_staging(self, value: bool) -> HookConfigBuilder<HasStagingConfig> {
+        let mut config = self.config;
+        config.confirm_not_staging = Some(value);
+        HookConfigBuilder {
+            config,
+            state: std::marker::PhantomData,
+        }
+    }
+
+    setters!(
+        strict: bool,
+        confirm_uninitialized: bool,
+        confirm_new_submodule: bool,
+        confirm_deletion: bool,
+        confirm_detached_head: bool,
+        non_interactive: bool,
+        recursive: bool,
+        max_depth: usize,
+        prompt_message: String,
+        timeout_secs: u64,
+        timeout_default: bool,
+        lock_timeout_secs: u64,
+        dry_run: bool,
+        warn_untracked_branch: bool,
+        show_ahead_behind: bool,
+        verify_remote: bool,
+        parallel: bool,
+        allow_dirty: bool,
+        show_commit_message: bool,
+        auto_stage: bool,
+        warn_only: bool,
+        skip_rebase: bool,
+        warn_amending: bool,
+        multi_select: bool,
+        show_files: bool,
+        check_dirty: bool,
+        check_unpushed: bool,
+        progress: bool,
+        cache: bool,
+        default_confirm: bool,
+        ascii: bool,
+        sort: SortOrder,
+        audit_log: PathBuf,
+        pager: String,
+        exit_code_declined: u8,
+        exit_code_cancelled: u8,
+        exit_code_check_error: u8,
+    );
+
+    pub fn allow_list(mut self, allow_list: Vec<String>) -> Self {
+        self.config.allow_list = allow_list;
+        self
+    }
+
+    pub fn allowed_schemes(mut self, allowed_schemes: Vec<String>) -> Self {
+        self.config.allowed_schemes = allowed_schemes;
+        self
+    }
+
+    pub fn ignore_policy(mut self, name: &str, policy: SubmoduleIgnore) -> Self {
+        self.config.ignore_policies.insert(name.to_string(), policy);
+        self
+    }
+
+    pub fn pin(mut self, name: &str, oid: Oid) -> Self {
+        self.config.pins.insert(name.to_string(), oid);
+        self
+    }
+}
+
+impl HookConfigBuilder<HasStagingConfig> {
+    pub fn build(self) -> HookConfig {
+        self.config
+    }
+}
+
+fn to_string<T: ToString>(value: &Option<T>) -> Option<String> {
+    value.as_ref().map(T::to_string)
+}
+
+/// Value used by the hook when `key` is not set by any source
+fn default_value(key: &str) -> String {
+    match key {
+        "strict"
+        | "newsubmodule"
+        | "detachedhead"
+        | "recursive"
+        | "timeoutdefault"
+        | "dryrun"
+        | "warnuntrackedbranch"
+        | "aheadbehind"
+        | "verifyremote"
+        | "parallel"
+        | "allowdirty"
+        | "showcommitmessage"
+        | "autostage"
+        | "warnonly"
+        | "skiprebase"
+        | "multiselect"
+        | "showfiles"
+        | "checkdirty"
+        | "checkunpushed"
+        | "cache"
+        | "defaultconfirm"
+        | "ascii" => "false".to_string(),
+        "staging" | "notstaging" | "uninitialized" | "deletion" | "warnamending" => {
+            "true".to_string()
+        }
+        "noninteractive" => "$CI == true".to_string(),
+        "maxdepth" => crate::check_submodules::DEFAULT_MAX_DEPTH.to_string(),
+        "prompt" => DEFAULT_PROMPT.to_string(),
+        "timeout" => "0".to_string(),
+        "locktimeout" => "10".to_string(),
+        "sort" => SortOrder::default().to_string(),
+        "exitcode.declined" => "1".to_string(),
+        "exitcode.cancelled" => "2".to_string(),
+        "exitcode.checkerror" => "3".to_string(),
+        "ignore" | "pin" => "none".to_string(),
+        "allowedschemes" => "any".to_string(),
+        "progress" => "true".to_string(),
+        "auditlog" => "disabled".to_string(),
+        "pager" => "$PAGER or less -R".to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Read the hook options set in `git_config`, `scope` being used for logging only
+fn read_git_config(git_config: &GitConfig, scope: &str) -> HookConfig {
+    let mut config = HookConfig::default();
+    let config_name = "submodulehook".to_string();
+    let read = |option: &str| -> Option<String> {
+        let key = format!("{config_name}.{option}");
+        let value = git_config.get_string(key.as_str()).ok()?;
+        debug!("found {scope} config: {key} = {value}");
+        Some(value)
+    };
+    config.strict = read("strict").map(|value| value == "true");
+    config.confirm_staging = read("staging").map(|value| value == "true");
+    config.confirm_not_staging = read("notstaging").map(|value| value == "true");
+    config.confirm_uninitialized = read("uninitialized").map(|value| value == "true");
+    config.confirm_new_submodule = read("newsubmodule").map(|value| value == "true");
+    config.confirm_deletion = read("deletion").map(|value| value == "true");
+    config.confirm_detached_head = read("detachedhead").map(|value| value == "true");
+    config.non_interactive = read("noninteractive").map(|value| value == "true");
+    if let Some(value) = read("allow") {
+        config.allow_list = parse_list(&value);
+    }
+    if let Some(value) = read("allowedschemes") {
+        config.allowed_schemes = parse_list(&value);
+    }
+    config.recursive = read("recursive").map(|value| value == "true");
+    config.max_depth =
+        read("maxdepth").and_then(|value| parse_number(&format!("{config_name}.maxdepth"), &value));
+    config.prompt_message = read("prompt");
+    config.timeout_secs =
+        read("timeout").and_then(|value| parse_number(&format!("{config_name}.timeout"), &value));
+    config.timeout_default = read("timeoutdefault").map(|value| value == "true");
+    config.lock_timeout_secs = read("locktimeout")
+        .and_then(|value| parse_number(&format!("{config_name}.locktimeout"), &value));
+    config.dry_run = read("dryrun").map(|value| value == "true");
+    config.warn_untracked_branch = read("warnuntrackedbranch").map(|value| value == "true");
+    config.show_ahead_behind = read("aheadbehind").map(|value| value == "true");
+    config.verify_remote = read("verifyremote").map(|value| value == "true");
+    config.parallel = read("parallel").map(|value| value == "true");
+    config.allow_dirty = read("allowdirty").map(|value| value == "true");
+    config.show_commit_message = read("showcommitmessage").map(|value| value == "true");
+    config.auto_stage = read("autostage").map(|value| value == "true");
+    config.warn_only = read("warnonly").map(|value| value == "true");
+    config.skip_rebase = read("skiprebase").map(|value| value == "true");
+    config.warn_amending = read("warnamending").map(|value| value == "true");
+    config.multi_select = read("multiselect").map(|value| value == "true");
+    config.show_files = read("showfiles").map(|value| value == "true");
+    config.check_dirty = read("checkdirty").map(|value| value == "true");
+    config.check_unpushed = read("checkunpushed").map(|value| value == "true");
+    config.progress = read("progress").map(|value| value == "true");
+    config.cache = read("cache").map(|value| value == "true");
+    config.default_confirm = read("defaultconfirm").map(|value| value == "true");
+    config.ascii = read("ascii").map(|value| value == "true");
+    config.sort =
+        read("sort").and_then(|value| parse_number(&format!("{config_name}.sort"), &value));
+    config.audit_log = read("auditlog").map(|value| expand_home(&value));
+    config.pager = read("pager");
+    config.exit_code_declined = read("exitcode.declined")
+        .and_then(|value| parse_number(&format!("{config_name}.exitcode.declined"), &value));
+    config.exit_code_cancelled = read("exitcode.cancelled")
+        .and_then(|value| parse_number(&format!("{config_name}.exitcode.cancelled"), &value));
+    config.exit_code_check_error = read("exitcode.checkerror")
+        .and_then(|value| parse_number(&format!("{config_name}.exitcode.checkerror"), &value));
+    if let Ok(mut entries) =
+        git_config.entries(Some(&format!(r"^{config_name}\..+\.(ignore|pin)$")))
+    {
+        while let Some(entry) = entries.next() {
+            let Ok(entry) = entry else { continue };
+            let (Some(key), Some(value)) = (entry.name(), entry.value()) else {
+                continue;
+            };
+            debug!("found {scope} config: {key} = {value}");
+            let Some(option) = key.strip_prefix(&format!("{config_name}.")) else {
+                continue;
+            };
+            if let Some(name) = option.strip_suffix(".ignore") {
+                read_ignore_policy(&mut config.ignore_policies, key, name, value);
+            } else if let Some(name) = option.strip_suffix(".pin") {
+                read_pin(&mut config.pins, key, name, value);
+            }
+        }
+    }
+    config
+}
+
+/// Hook options of a TOML configuration file, keys are the same as the git config ones
+/// except that `allow` is a list.
+///
+/// Unknown keys are ignored so that files written for newer versions can still be read.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub strict: Option<bool>,
+    pub staging: Option<bool>,
+    pub notstaging: Option<bool>,
+    pub uninitialized: Option<bool>,
+    pub newsubmodule: Option<bool>,
+    pub deletion: Option<bool>,
+    pub detachedhead: Option<bool>,
+    pub noninteractive: Option<bool>,
+    pub allow: Vec<String>,
+    pub allowedschemes: Vec<String>,
+    pub recursive: Option<bool>,
+    pub maxdepth: Option<usize>,
+    pub prompt: Option<String>,
+    pub timeout: Option<u64>,
+    pub timeoutdefault: Option<bool>,
+    pub locktimeout: Option<u64>,
+    pub dryrun: Option<bool>,
+    pub warnuntrackedbranch: Option<bool>,
+    pub aheadbehind: Option<bool>,
+    pub verifyremote: Option<bool>,
+    pub parallel: Option<bool>,
+    pub allowdirty: Option<bool>,
+    pub showcommitmessage: Option<bool>,
+    pub autostage: Option<bool>,
+    pub warnonly: Option<bool>,
+    pub skiprebase: Option<bool>,
+    pub warnamending: Option<bool>,
+    pub multiselect: Option<bool>,
+    pub showfiles: Option<bool>,
+    pub checkdirty: Option<bool>,
+    pub checkunpushed: Option<bool>,
+    pub progress: Option<bool>,
+    pub cache: Option<bool>,
+    pub defaultconfirm: Option<bool>,
+    pub ascii: Option<bool>,
+    pub sort: Option<SortOrder>,
+    pub auditlog: Option<String>,
+    pub pager: Option<String>,
+    /// Exit codes, `[exitcode]` table
+    pub exitcode: ExitCodeConfig,
+    /// Ignore policy of submodules by name
+    pub ignore: HashMap<String, String>,
+    /// Commit submodules are pinned to by name
+    pub pin: HashMap<String, String>,
+}
+
+/// `[exitcode]` table of a TOML configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExitCodeConfig {
+    pub declined: Option<u8>,
+    pub cancelled: Option<u8>,
+    pub checkerror: Option<u8>,
+}
+
+impl From<Config> for HookConfig {
+    fn from(config: Config) -> Self {
+        HookConfig {
+            strict: config.strict,
+            confirm_staging: config.staging,
+            confirm_not_staging: config.notstaging,
+            confirm_uninitialized: config.uninitialized,
+            confirm_new_submodule: config.newsubmodule,
+            confirm_deletion: config.deletion,
+            confirm_detached_head: config.detachedhead,
+            non_interactive: config.noninteractive,
+            allow_list: config.allow,
+            allowed_schemes: config.allowedschemes,
+            recursive: config.recursive,
+            max_depth: config.maxdepth,
+            prompt_message: config.prompt,
+            timeout_secs: config.timeout,
+            timeout_default: config.timeoutdefault,
+            lock_timeout_secs: config.locktimeout,
+            dry_run: config.dryrun,
+            warn_untracked_branch: config.warnuntrackedbranch,
+            show_ahead_behind: config.aheadbehind,
+            verify_remote: config.verifyremote,
+            parallel: config.parallel,
+            allow_dirty: config.allowdirty,
+            show_commit_message: config.showcommitmessage,
+            auto_stage: config.autostage,
+            warn_only: config.warnonly,
+            skip_rebase: config.skiprebase,
+            warn_amending: config.warnamending,
+            multi_select: config.multiselect,
+            show_files: config.showfiles,
+            check_dirty: config.checkdirty,
+            check_unpushed: config.checkunpushed,
+            progress: config.progress,
+            cache: config.cache,
+            default_confirm: config.defaultconfirm,
+            ascii: config.ascii,
+            sort: config.sort,
+            audit_log: config.auditlog.as_deref().map(expand_home),
+            pager: config.pager,
+            exit_code_declined: config.exitcode.declined,
+            exit_code_cancelled: config.exitcode.cancelled,
+            exit_code_check_error: config.exitcode.checkerror,
+            ignore_policies: {
+                let mut ignore_policies = HashMap::new();
+                for (name, value) in &config.ignore {
+                    read_ignore_policy(
+                        &mut ignore_policies,
+                        &format!("ignore.{name}"),
+                        name,
+                        value,
+                    );
+                }
+                ignore_policies
+            },
+            pins: {
+                let mut pins = HashMap::new();
+                for (name, value) in &config.pin {
+                    read_pin(&mut pins, &format!("pin.{name}"), name, value);
+                }
+                pins
+            },
+        }
+    }
+}
+
+/// Name of the repository configuration file, in the repository root
+pub const CONFIG_FILE_NAME: &str = ".submodule-hook.toml";
+
+/// Parse the content of a TOML configuration file
+fn parse_toml_config(content: &str) -> anyhow::Result<HookConfig> {
+    let config: Config = toml::from_str(content)?;
+    Ok(config.into())
+}
+
+/// Read the TOML configuration file at `path`, `None` if it does not exist or is invalid
+fn read_toml_config(path: &Path) -> Option<HookConfig> {
+    let content = std::fs::read_to_string(path).ok()?;
+    debug!("found config file: {}", path.display());
+    match parse_toml_config(&content) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            warn!("invalid config file {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Existing TOML configuration files, global then in the repository `workdir`, with their
+/// parse result
+pub fn check_config_files(workdir: Option<&Path>) -> Vec<(PathBuf, anyhow::Result<()>)> {
+    global_config_file()
+        .into_iter()
+        .chain(workdir.map(|workdir| workdir.join(CONFIG_FILE_NAME)))
+        .filter_map(|path| {
+            let content = std::fs::read_to_string(&path).ok()?;
+            let result = parse_toml_config(&content).map(|_| ());
+            Some((path, result))
+        })
+        .collect()
+}
+
+/// User configuration file, relative to the user configuration directory
+const USER_CONFIG_FILE: &str = "submodule-hook/config.toml";
+
+/// User configuration file: `$XDG_CONFIG_HOME/submodule-hook/config.toml`, in the platform
+/// configuration directory (`~/.config` on Linux) if `XDG_CONFIG_HOME` is not set
+fn global_config_file() -> Option<PathBuf> {
+    user_config_file(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        dirs::config_dir(),
+        dirs::home_dir(),
+    )
+}
+
+/// `global_config_file` from the value of `XDG_CONFIG_HOME`, the platform configuration
+/// directory and the home directory
+fn user_config_file(
+    xdg_config_home: Option<OsString>,
+    config_dir: Option<PathBuf>,
+    home_dir: Option<PathBuf>,
+) -> Option<PathBuf> {
+    // relative paths are ignored, as required by the XDG Base Directory specification
+    let config_dir = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or(config_dir)?;
+    let path = config_dir.join(USER_CONFIG_FILE);
+    // `~/.config` used to be read on all platforms, e.g. on macOS instead of
+    // `~/Library/Application Support`
+    if !path.exists()
+        && let Some(legacy_path) = home_dir.map(|home| home.join(".config").join(USER_CONFIG_FILE))
+        && legacy_path.exists()
+    {
+        return Some(legacy_path);
+    }
+    Some(path)
+}
+
+/// Read the hook options set by `SUBMODULE_HOOK_*` environment variables
+fn read_env_config() -> HookConfig {
+    let mut config = HookConfig {
+        strict: read_env("SUBMODULE_HOOK_STRICT"),
+        confirm_staging: read_env("SUBMODULE_HOOK_STAGING"),
+        confirm_not_staging: read_env("SUBMODULE_HOOK_NOT_STAGING"),
+        confirm_uninitialized: read_env("SUBMODULE_HOOK_UNINITIALIZED"),
+        confirm_new_submodule: read_env("SUBMODULE_HOOK_NEW_SUBMODULE"),
+        confirm_deletion: read_env("SUBMODULE_HOOK_DELETION"),
+        confirm_detached_head: read_env("SUBMODULE_HOOK_DETACHED_HEAD"),
+        non_interactive: read_env("SUBMODULE_HOOK_NON_INTERACTIVE"),
+        recursive: read_env("SUBMODULE_HOOK_RECURSIVE"),
+        timeout_default: read_env("SUBMODULE_HOOK_TIMEOUT_DEFAULT"),
+        dry_run: read_env("SUBMODULE_HOOK_DRY_RUN"),
+        warn_untracked_branch: read_env("SUBMODULE_HOOK_WARN_UNTRACKED_BRANCH"),
+        show_ahead_behind: read_env("SUBMODULE_HOOK_AHEAD_BEHIND"),
+        verify_remote: read_env("SUBMODULE_HOOK_VERIFY_REMOTE"),
+        parallel: read_env("SUBMODULE_HOOK_PARALLEL"),
+        allow_dirty: read_env("SUBMODULE_HOOK_ALLOW_DIRTY"),
+        show_commit_message: read_env("SUBMODULE_HOOK_SHOW_COMMIT_MESSAGE"),
+        auto_stage: read_env("SUBMODULE_HOOK_AUTO_STAGE"),
+        warn_only: read_env("SUBMODULE_HOOK_WARN_ONLY"),
+        skip_rebase: read_env("SUBMODULE_HOOK_SKIP_REBASE"),
+        warn_amending: read_env("SUBMODULE_HOOK_WARN_AMENDING"),
+        multi_select: read_env("SUBMODULE_HOOK_MULTI_SELECT"),
+        show_files: read_env("SUBMODULE_HOOK_SHOW_FILES"),
+        check_dirty: read_env("SUBMODULE_HOOK_CHECK_DIRTY"),
+        check_unpushed: read_env("SUBMODULE_HOOK_CHECK_UNPUSHED"),
+        progress: read_env("SUBMODULE_HOOK_PROGRESS"),
+        cache: read_env("SUBMODULE_HOOK_CACHE"),
+        default_confirm: read_env("SUBMODULE_HOOK_DEFAULT_CONFIRM"),
+        ascii: read_env("SUBMODULE_HOOK_ASCII"),
+        ..HookConfig::default()
+    };
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_ALLOW") {
+        debug!("found env: SUBMODULE_HOOK_ALLOW = {value}");
+        config.allow_list = parse_list(&value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_ALLOWED_SCHEMES") {
+        debug!("found env: SUBMODULE_HOOK_ALLOWED_SCHEMES = {value}");
+        config.allowed_schemes = parse_list(&value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_MAX_DEPTH") {
+        debug!("found env: SUBMODULE_HOOK_MAX_DEPTH = {value}");
+        config.max_depth = parse_number("SUBMODULE_HOOK_MAX_DEPTH", &value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_PROMPT") {
+        debug!("found env: SUBMODULE_HOOK_PROMPT = {value}");
+        config.prompt_message = Some(value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_TIMEOUT") {
+        debug!("found env: SUBMODULE_HOOK_TIMEOUT = {value}");
+        config.timeout_secs = parse_number("SUBMODULE_HOOK_TIMEOUT", &value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_SORT") {
+        debug!("found env: SUBMODULE_HOOK_SORT = {value}");
+        config.sort = parse_number("SUBMODULE_HOOK_SORT", &value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_AUDIT_LOG") {
+        debug!("found env: SUBMODULE_HOOK_AUDIT_LOG = {value}");
+        config.audit_log = Some(expand_home(&value));
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_PAGER") {
+        debug!("found env: SUBMODULE_HOOK_PAGER = {value}");
+        config.pager = Some(value);
+    }
+    if let Ok(value) = std::env::var("SUBMODULE_HOOK_LOCK_TIMEOUT") {
+        debug!("found env: SUBMODULE_HOOK_LOCK_TIMEOUT = {value}");
+        config.lock_timeout_secs = parse_number("SUBMODULE_HOOK_LOCK_TIMEOUT", &value);
+    }
+    for (name, exit_code) in [
+        (
+            "SUBMODULE_HOOK_EXIT_CODE_DECLINED",
+            &mut config.exit_code_declined,
+        ),
+        (
+            "SUBMODULE_HOOK_EXIT_CODE_CANCELLED",
+            &mut config.exit_code_cancelled,
+        ),
+        (
+            "SUBMODULE_HOOK_EXIT_CODE_CHECK_ERROR",
+            &mut config.exit_code_check_error,
+        ),
+    ] {
+        if let Ok(value) = std::env::var(name) {
+            debug!("found env: {name} = {value}");
+            *exit_code = parse_number(name, &value);
+        }
+    }
+    config
+}
+
+/// Git config levels the hook options are read from (`--config-level`)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum GitConfigLevel {
+    /// System config only (`/etc/gitconfig`)
+    System,
+    /// User config only (`~/.gitconfig` and `$XDG_CONFIG_HOME/git/config`)
+    Global,
+    /// Repository config only (`.git/config`)
+    Local,
+    /// All of them, local overriding global overriding system
+    #[default]
+    All,
+}
+
+impl GitConfigLevel {
+    fn includes(self, level: GitConfigLevel) -> bool {
+        self == GitConfigLevel::All || self == level
+    }
+}
+
+impl std::str::FromStr for GitConfigLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "system" => Ok(GitConfigLevel::System),
+            "global" => Ok(GitConfigLevel::Global),
+            "local" => Ok(GitConfigLevel::Local),
+            "all" => Ok(GitConfigLevel::All),
+            _ => Err(format!(
+                "expected global, local, system or all, got `{value}`"
+            )),
+        }
+    }
+}
+
+/// Git config made of the `files` found, `None` if none is found
+fn open_git_config_files<const N: usize>(
+    files: [(Result<PathBuf, git2::Error>, git2::ConfigLevel); N],
+) -> Option<GitConfig> {
+    let mut git_config = GitConfig::new().ok()?;
+    let mut found = false;
+    for (path, level) in files {
+        let Ok(path) = path else {
+            continue;
+        };
+        match git_config.add_file(&path, level, false) {
+            Ok(()) => found = true,
+            Err(e) => warn!("failed to read git config {}: {e}", path.display()),
+        }
+    }
+    found.then_some(git_config)
+}
+
+/// Hook configuration of each source, from lowest to highest priority:
+///
+/// 0. system git config (`/etc/gitconfig`)
+/// 1. global git config (`$XDG_CONFIG_HOME/git/config` then `~/.gitconfig`)
+/// 2. global configuration file (`$XDG_CONFIG_HOME/submodule-hook/config.toml`, see
+///    `global_config_file`)
+/// 3. repository configuration file (`.submodule-hook.toml`)
+/// 4. local git config (`.git/config`)
+/// 5. environment variables (`SUBMODULE_HOOK_*`)
+///
+/// Git config levels not included in `level` are not read. Repository sources are read from
+/// the repository at `repo_path`, see `open_repository`.
+pub fn get_config_layers(
+    repo_path: &Path,
+    level: GitConfigLevel,
+) -> Vec<(ConfigSource, HookConfig)> {
+    config_layers(repo_path, level, None)
+}
+
+/// Hook configuration of each source as `get_config_layers`, the global git config being read
+/// from `global_git_config` only if set
+fn config_layers(
+    repo_path: &Path,
+    level: GitConfigLevel,
+    global_git_config: Option<&Path>,
+) -> Vec<(ConfigSource, HookConfig)> {
+    let mut layers = vec![];
+
+    // 0 try reading from system config
+    if level.includes(GitConfigLevel::System)
+        && let Some(system_config) =
+            open_git_config_files([(GitConfig::find_system(), git2::ConfigLevel::System)])
+    {
+        layers.push((
+            ConfigSource::System,
+            read_git_config(&system_config, "system"),
+        ));
+    }
+
+    // 1 try reading from global config
+    if level.includes(GitConfigLevel::Global)
+        && let Some(global_config) = match global_git_config {
+            Some(path) => {
+                open_git_config_files([(Ok(path.to_path_buf()), git2::ConfigLevel::Global)])
+            }
+            None => open_git_config_files([
+                (GitConfig::find_xdg(), git2::ConfigLevel::XDG),
+                (GitConfig::find_global(), git2::ConfigLevel::Global),
+            ]),
+        }
+    {
+        layers.push((
+            ConfigSource::Global,
+            read_git_config(&global_config, "global"),
+        ));
+    }
+
+    // 2 try reading from global configuration file
+    if let Some(config) = global_config_file().and_then(|path| read_toml_config(&path)) {
+        layers.push((ConfigSource::GlobalFile, config));
+    }
+
+    let repo = open_repository(repo_path).ok();
+
+    // 3 try reading from repository configuration file
+    if let Some(workdir) = repo.as_ref().and_then(|repo| repo.workdir())
+        && let Some(config) = read_toml_config(&workdir.join(CONFIG_FILE_NAME))
+    {
+        layers.push((ConfigSource::LocalFile, config));
+    }
+
+    // 4 try reading from local config
+    if level.includes(GitConfigLevel::Local)
+        && let Some(repo) = &repo
+        && let Ok(local_config) = repo
+            .config()
+            .and_then(|config| config.open_level(git2::ConfigLevel::Local))
+    {
+        layers.push((ConfigSource::Local, read_git_config(&local_config, "local")));
+    }
+
+    // 5 try reading from environment variables
+    layers.push((ConfigSource::Env, read_env_config()));
+    layers
+}
+
+/// Resolve the hook configuration, later sources override earlier ones (see `get_config_layers`).
+///
+/// CLI parameters are applied on top of the result by the caller.
+pub fn get_config(repo_path: &Path) -> HookConfig {
+    get_config_at_level(repo_path, GitConfigLevel::All)
+}
+
+/// Resolve the hook configuration as `get_config`, reading only the git config levels of `level`
+pub fn get_config_at_level(repo_path: &Path, level: GitConfigLevel) -> HookConfig {
+    merge_layers(get_config_layers(repo_path, level))
+}
+
+/// Resolve the hook configuration as `get_config`, the global git config being read from
+/// `global_git_config` instead of `$XDG_CONFIG_HOME/git/config` and `~/.gitconfig` if set,
+/// e.g. for tests.
+///
+/// A file that is missing or cannot be parsed is reported as a warning and ignored.
+pub fn get_config_with_global(repo_path: &Path, global_git_config: Option<&Path>) -> HookConfig {
+    merge_layers(config_layers(
+        repo_path,
+        GitConfigLevel::All,
+        global_git_config,
+    ))
+}
+
+/// Configuration resulting from `layers`, ordered from lowest to highest priority
+fn merge_layers(layers: Vec<(ConfigSource, HookConfig)>) -> HookConfig {
+    layers
+        .into_iter()
+        .fold(HookConfig::default(), |config, (_, layer)| {
+            config.merge(layer)
+        })
+}
+
+/// Git config key of the hook option `key`, which may already have the `submodulehook.` prefix
+fn git_config_key(key: &str) -> String {
+    if key.starts_with("submodulehook.") {
+        key.to_string()
+    } else {
+        format!("submodulehook.{key}")
+    }
+}
+
+/// Whether `key` (with or without the `submodulehook.` prefix) is an option read by the hook,
+/// including the per-submodule `<name>.ignore` and `<name>.pin` options
+pub fn is_known_key(key: &str) -> bool {
+    let key = key.strip_prefix("submodulehook.").unwrap_or(key);
+    let per_submodule = ["ignore", "pin"];
+    if let Some((name, option)) = key.rsplit_once('.')
+        && per_submodule.contains(&option)
+        && !name.is_empty()
+    {
+        return true;
+    }
+    HookConfig::default()
+        .entries()
+        .iter()
+        .any(|(known, _)| *known == key && !per_submodule.contains(known))
+}
+
+/// Write `key` (with or without the `submodulehook.` prefix) to the local git config of the
+/// repository at `repo_path`
+pub fn set_git_config(repo_path: &Path, key: &str, value: &str) -> anyhow::Result<()> {
+    let repo = open_repository(repo_path)?;
+    let mut local_config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+    local_config.set_str(&git_config_key(key), value)?;
+    Ok(())
+}
+
+/// Value of `key` (with or without the `submodulehook.` prefix) in the git config of the
+/// repository at `repo_path`, local config overriding global one
+pub fn get_git_config(repo_path: &Path, key: &str) -> anyhow::Result<Option<String>> {
+    let repo = open_repository(repo_path)?;
+    match repo.config()?.get_string(&git_config_key(key)) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// All `submodulehook.*` entries of the git config of the repository at `repo_path`, sorted by
+/// key, local config overriding global one
+pub fn list_git_config(repo_path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let repo = open_repository(repo_path)?;
+    let git_config = repo.config()?;
+    let mut values = std::collections::BTreeMap::new();
+    let mut entries = git_config.entries(Some(r"^submodulehook\."))?;
+    // entries are iterated from lowest to highest priority
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        if let (Some(key), Some(value)) = (entry.name(), entry.value()) {
+            values.insert(key.to_string(), value.to_string());
+        }
+    }
+    Ok(values.into_iter().collect())
+}
+
+/// `key = value` table of the resolved configuration, with the source each value comes from
+pub fn show_config(layers: &[(ConfigSource, HookConfig)]) -> String {
+    let layer_entries: Vec<_> = layers
+        .iter()
+        .map(|(source, config)| (*source, config.entries()))
+        .collect();
+    let rows: Vec<(&str, String, ConfigSource)> = HookConfig::default()
+        .entries()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key, _))| {
+            layer_entries
+                .iter()
+                .rev()
+                .find_map(|(source, entries)| entries[i].1.clone().map(|value| (value, *source)))
+                .map(|(value, source)| (key, value, source))
+                .unwrap_or_else(|| (key, default_value(key), ConfigSource::Default))
+        })
+        .collect();
+    let key_width = rows.iter().map(|(key, _, _)| key.len()).max().unwrap_or(0);
+    let value_width = rows
+        .iter()
+        .map(|(_, value, _)| value.len())
+        .max()
+        .unwrap_or(0)
+        .max("value".len());
+    let mut lines = vec![format!(
+        "{:key_width$}   {:value_width$}  source",
+        "key", "value"
+    )];
+    for (key, value, source) in rows {
+        lines.push(format!("{key:key_width$} = {value:value_width$}  {source}"));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_config_serde() {
+        let config = HookConfig {
+            strict: Some(true),
+            allow_list: vec!["vendor/*".to_string()],
+            max_depth: Some(3),
+            ..HookConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: HookConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.entries(), config.entries());
+
+        let parsed: HookConfig = serde_json::from_str(r#"{"dry_run": true}"#).unwrap();
+        assert_eq!(parsed.dry_run, Some(true));
+        assert_eq!(parsed.strict, None);
+    }
+
+    #[test]
+    fn test_parse_env_bool() {
+        assert_eq!(parse_env_bool(None), None);
+        assert_eq!(parse_env_bool(Some("true")), Some(true));
+        assert_eq!(parse_env_bool(Some("false")), Some(false));
+        assert_eq!(parse_env_bool(Some("1")), Some(false));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("vendor, libfoo,,"), vec!["vendor", "libfoo"]);
+        assert!(parse_list("").is_empty());
+    }
+
+    #[test]
+    fn test_builder() {
+        let config = HookConfig::builder()
+            .strict(true)
+            .confirm_not_staging(false)
+            .sort(SortOrder::Time)
+            .allow_list(vec!["vendor/*".to_string()])
+            .ignore_policy("lib", SubmoduleIgnore::Dirty)
+            .pin("lib", Oid::zero())
+            .build();
+        assert_eq!(config.strict, Some(true));
+        assert_eq!(config.confirm_staging, None);
+        assert_eq!(config.confirm_not_staging, Some(false));
+        assert_eq!(config.sort, Some(SortOrder::Time));
+        assert_eq!(config.allow_list, vec!["vendor/*"]);
+        assert_eq!(config.ignore_policies["lib"], SubmoduleIgnore::Dirty);
+        assert_eq!(config.pins["lib"], Oid::zero());
+        assert_eq!(config.max_depth, None);
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = PathBuf::from(std::env::var_os("HOME").unwrap());
+        assert_eq!(
+            expand_home("~/.local/share/submodule-hook/audit.log"),
+            home.join(".local/share/submodule-hook/audit.log")
+        );
+        assert_eq!(
+            expand_home("/var/log/audit.log"),
+            PathBuf::from("/var/log/audit.log")
+        );
+        assert_eq!(
+            expand_home("~user/audit.log"),
+            PathBuf::from("~user/audit.log")
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(
+            parse_number::<usize>("submodulehook.maxdepth", "3"),
+            Some(3)
+        );
+        assert_eq!(
+            parse_number::<usize>("submodulehook.maxdepth", "deep"),
+            None
+        );
+        assert_eq!(
+            parse_number::<u64>("submodulehook.timeout", " 30 "),
+            Some(30)
+        );
+        assert_eq!(parse_number::<u64>("submodulehook.timeout", "-1"), None);
+    }
+
+    #[test]
+    fn test_parse_toml_config() {
+        let config = parse_toml_config(
+            r#"
+            strict = true
+            allow = ["vendor/*", "deps/**"]
+            maxdepth = 3
+            prompt = "Continue?"
+            unknown = "ignored"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.strict, Some(true));
+        assert_eq!(config.allow_list, vec!["vendor/*", "deps/**"]);
+        assert_eq!(config.max_depth, Some(3));
+        assert_eq!(config.prompt_message.as_deref(), Some("Continue?"));
+        assert_eq!(config.confirm_staging, None);
+
+        assert!(parse_toml_config("strict = \"yes\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_ignore_policy() {
+        assert_eq!(parse_ignore_policy("dirty"), Some(SubmoduleIgnore::Dirty));
+        assert_eq!(parse_ignore_policy(" all "), Some(SubmoduleIgnore::All));
+        assert_eq!(parse_ignore_policy("sometimes"), None);
+    }
+
+    #[test]
+    fn test_read_git_config_ignore_policies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut git_config = GitConfig::open(&temp_dir.path().join("config")).unwrap();
+        git_config
+            .set_str("submodulehook.vendor/lib.ignore", "dirty")
+            .unwrap();
+        git_config
+            .set_str("submodulehook.docs.ignore", "all")
+            .unwrap();
+        git_config
+            .set_str("submodulehook.broken.ignore", "sometimes")
+            .unwrap();
+        git_config.set_bool("submodulehook.strict", true).unwrap();
+
+        let config = read_git_config(&git_config, "test");
+        assert_eq!(config.strict, Some(true));
+        assert_eq!(
+            config.ignore_policies,
+            HashMap::from([
+                ("vendor/lib".to_string(), SubmoduleIgnore::Dirty),
+                ("docs".to_string(), SubmoduleIgnore::All),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_config_ignore_policies() {
+        let config = parse_toml_config(
+            r#"
+            [ignore]
+            "vendor/lib" = "untracked"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.ignore_policies,
+            HashMap::from([("vendor/lib".to_string(), SubmoduleIgnore::Untracked)])
+        );
+    }
+
+    #[test]
+    fn test_merge_ignore_policies() {
+        let lower = HookConfig {
+            ignore_policies: HashMap::from([
+                ("a".to_string(), SubmoduleIgnore::Dirty),
+                ("b".to_string(), SubmoduleIgnore::Dirty),
+            ]),
+            ..HookConfig::default()
+        };
+        let higher = HookConfig {
+            ignore_policies: HashMap::from([("b".to_string(), SubmoduleIgnore::All)]),
+            ..HookConfig::default()
+        };
+        let merged = lower.merge(higher);
+        assert_eq!(merged.ignore_policies["a"], SubmoduleIgnore::Dirty);
+        assert_eq!(merged.ignore_policies["b"], SubmoduleIgnore::All);
+        assert_eq!(
+            merged.entries().last().unwrap(),
+            &("ignore", Some("a=dirty,b=all".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_is_known_key() {
+        assert!(is_known_key("strict"));
+        assert!(is_known_key("submodulehook.notstaging"));
+        assert!(is_known_key("exitcode.declined"));
+        assert!(is_known_key("vendor/lib.ignore"));
+        assert!(is_known_key("submodulehook.vendor/lib.pin"));
+        assert!(!is_known_key("pin"));
+        assert!(!is_known_key("stict"));
+        assert!(!is_known_key("vendor/lib.other"));
+    }
+
+    #[test]
+    fn test_set_get_list_git_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(temp_dir.path()).unwrap();
+        assert_eq!(get_git_config(temp_dir.path(), "strict").unwrap(), None);
+
+        set_git_config(temp_dir.path(), "strict", "true").unwrap();
+        set_git_config(temp_dir.path(), "submodulehook.vendor/lib.ignore", "all").unwrap();
+        assert_eq!(
+            get_git_config(temp_dir.path(), "submodulehook.strict").unwrap(),
+            Some("true".to_string())
+        );
+        let local = list_git_config(temp_dir.path())
+            .unwrap()
+            .into_iter()
+            .filter(|(key, _)| key == "submodulehook.strict" || key.ends_with(".ignore"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            local,
+            [
+                ("submodulehook.strict".to_string(), "true".to_string()),
+                (
+                    "submodulehook.vendor/lib.ignore".to_string(),
+                    "all".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_config_at_level() {
+        assert_eq!("system".parse(), Ok(GitConfigLevel::System));
+        assert_eq!("all".parse(), Ok(GitConfigLevel::All));
+        assert!("worktree".parse::<GitConfigLevel>().is_err());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(temp_dir.path()).unwrap();
+        set_git_config(temp_dir.path(), "sort", "time").unwrap();
+        set_git_config(temp_dir.path(), "dryrun", "true").unwrap();
+        let sort = |level| get_config_at_level(temp_dir.path(), level).sort;
+        assert_eq!(sort(GitConfigLevel::All), Some(SortOrder::Time));
+        assert_eq!(
+            get_config_at_level(temp_dir.path(), GitConfigLevel::Local).dry_run,
+            Some(true)
+        );
+        assert_eq!(sort(GitConfigLevel::Local), Some(SortOrder::Time));
+        assert_eq!(sort(GitConfigLevel::Global), None);
+        assert_eq!(sort(GitConfigLevel::System), None);
+
+        let sources: Vec<ConfigSource> = get_config_layers(temp_dir.path(), GitConfigLevel::Local)
+            .into_iter()
+            .map(|(source, _)| source)
+            .collect();
+        assert!(!sources.contains(&ConfigSource::System));
+        assert!(!sources.contains(&ConfigSource::Global));
+        assert!(sources.contains(&ConfigSource::Local));
+    }
+
+    #[test]
+    fn test_get_config_with_global() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let global = temp_dir.path().join("gitconfig");
+        std::fs::write(&global, "[submodulehook]\n\tsort = time\n\ttimeout = 5\n").unwrap();
+        let config = get_config_with_global(temp_dir.path(), Some(&global));
+        assert_eq!(config.sort, Some(SortOrder::Time));
+        assert_eq!(config.timeout_secs, Some(5));
+
+        // malformed files are ignored
+        for content in [
+            &b"[submodulehook\n\tsort = time"[..],
+            b"[submodulehook]\n\ttimeout = -1\n\tmaxdepth = 99999999999999999999\n",
+            b"\xff\xfe[\x00]",
+        ] {
+            std::fs::write(&global, content).unwrap();
+            let config = get_config_with_global(temp_dir.path(), Some(&global));
+            assert_eq!(config.sort, None);
+        }
+        let missing = temp_dir.path().join("missing");
+        assert_eq!(
+            get_config_with_global(temp_dir.path(), Some(&missing)).sort,
+            None
+        );
+    }
+
+    #[test]
+    fn test_user_config_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let xdg = temp_dir.path().join("xdg");
+        let config_dir = temp_dir.path().join("config");
+        let home = temp_dir.path().join("home");
+        let resolve = |xdg_config_home: Option<&Path>| {
+            user_config_file(
+                xdg_config_home.map(OsString::from),
+                Some(config_dir.clone()),
+                Some(home.clone()),
+            )
+        };
+
+        assert_eq!(resolve(Some(&xdg)), Some(xdg.join(USER_CONFIG_FILE)));
+        assert_eq!(resolve(None), Some(config_dir.join(USER_CONFIG_FILE)));
+        // relative `XDG_CONFIG_HOME` is ignored
+        assert_eq!(
+            resolve(Some(Path::new("relative"))),
+            Some(config_dir.join(USER_CONFIG_FILE))
+        );
+        assert_eq!(user_config_file(None, None, None), None);
+
+        // file left in `~/.config` while the platform configuration directory is elsewhere
+        let legacy_path = home.join(".config").join(USER_CONFIG_FILE);
+        std::fs::create_dir_all(legacy_path.parent().unwrap()).unwrap();
+        std::fs::write(&legacy_path, "strict = true\n").unwrap();
+        assert_eq!(resolve(None), Some(legacy_path));
+        let path = config_dir.join(USER_CONFIG_FILE);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "strict = true\n").unwrap();
+        assert_eq!(resolve(None), Some(path));
+    }
+
+    #[test]
+    fn test_read_pins() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut git_config = GitConfig::open(&temp_dir.path().join("config")).unwrap();
+        let oid = "3ca86a808c39e9cb0604346bcc304338bd0acf5c";
+        git_config
+            .set_str("submodulehook.vendor/lib.pin", oid)
+            .unwrap();
+        git_config
+            .set_str("submodulehook.broken.pin", "main")
+            .unwrap();
+        git_config
+            .set_str("submodulehook.docs.ignore", "all")
+            .unwrap();
+
+        let config = read_git_config(&git_config, "test");
+        let pins = HashMap::from([("vendor/lib".to_string(), Oid::from_str(oid).unwrap())]);
+        assert_eq!(config.pins, pins);
+        assert_eq!(config.ignore_policies.len(), 1);
+
+        let config = parse_toml_config(&format!("[pin]\n\"vendor/lib\" = \"{oid}\"")).unwrap();
+        assert_eq!(config.pins, pins);
+        assert_eq!(
+            config
+                .entries()
+                .iter()
+                .find(|(key, _)| *key == "pin")
+                .unwrap()
+                .1,
+            Some(format!("vendor/lib={oid}"))
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_config_exit_codes() {
+        let config = parse_toml_config(
+            r#"
+            [exitcode]
+            declined = 10
+            checkerror = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.exit_code_declined, Some(10));
+        assert_eq!(config.exit_code_cancelled, None);
+        assert_eq!(config.exit_code_check_error, Some(0));
+
+        assert!(parse_toml_config("[exitcode]\ndeclined = 256").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let lower = HookConfig {
+            strict: Some(true),
+            confirm_staging: Some(false),
+            allow_list: vec!["vendor".to_string()],
+            max_depth: Some(2),
+            ..HookConfig::default()
+        };
+        let higher = HookConfig {
+            strict: Some(false),
+            prompt_message: Some("Continue?".to_string()),
+            ..HookConfig::default()
+        };
+        let merged = lower.merge(higher);
+        assert_eq!(merged.strict, Some(false));
+        assert_eq!(merged.confirm_staging, Some(false));
+        assert_eq!(merged.allow_list, vec!["vendor"]);
+        assert_eq!(merged.max_depth, Some(2));
+        assert_eq!(merged.prompt_message.as_deref(), Some("Continue?"));
+        assert_eq!(merged.recursive, None);
+    }
+
+    #[test]
+    fn test_merge_allow_list() {
+        let lower = HookConfig {
+            allow_list: vec!["vendor".to_string()],
+            ..HookConfig::default()
+        };
+        let higher = HookConfig {
+            allow_list: vec!["deps/*".to_string()],
+            ..HookConfig::default()
+        };
+        assert_eq!(lower.merge(higher).allow_list, vec!["deps/*"]);
+    }
+
+    #[test]
+    fn test_merge_chain() {
+        let layer = |depth| HookConfig {
+            max_depth: depth,
+            ..HookConfig::default()
+        };
+        let merged = HookConfig::default()
+            .merge(layer(Some(1)))
+            .merge(layer(Some(2)))
+            .merge(layer(None));
+        assert_eq!(merged.max_depth, Some(2));
+    }
+
+    #[test]
+    fn test_show_config() {
+        let global = HookConfig {
+            strict: Some(true),
+            max_depth: Some(2),
+            ..HookConfig::default()
+        };
+        let cli = HookConfig {
+            strict: Some(false),
+            ..HookConfig::default()
+        };
+        let table = show_config(&[(ConfigSource::Global, global), (ConfigSource::Cli, cli)]);
+        let row = |key: &str| -> Vec<String> {
+            table
+                .lines()
+                .find(|line| line.starts_with(&format!("{key} ")))
+                .unwrap()
+                .split_whitespace()
+                .map(String::from)
+                .collect()
+        };
+        assert_eq!(row("strict"), vec!["strict", "=", "false", "cli"]);
+        assert_eq!(row("maxdepth"), vec!["maxdepth", "=", "2", "global"]);
+        assert_eq!(row("staging"), vec!["staging", "=", "true", "default"]);
+    }
+}
@@ -0,0 +1,724 @@
+//! Human readable description of a `SubmodulesDiagnostic`.
+
+use crate::check_submodules::{SubmoduleEntry, SubmodulesDiagnostic};
+use git2::Oid;
+use std::fmt;
+
+/// Kind of submodules listed in a section of the description
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    NotStaged,
+    Staged,
+    Uninitialized,
+    NewlyAdded,
+    Deleted,
+    DetachedHead,
+    Unreachable,
+    Pinned,
+    ConfigMismatch,
+    PathConflict,
+    Duplicate,
+    MissingCommit,
+    InsecureUrl,
+    Uncommitted,
+    Unpushed,
+    UrlMismatch,
+}
+
+/// Role of a piece of text in the description, for callers styling it (e.g. with colors)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Highlight {
+    /// Section header text
+    Text,
+    /// Section header keyword, e.g. "modified and staged"
+    Keyword(Section),
+    /// Submodule name
+    Name(Section),
+}
+
+/// Abbreviated commit hash, as displayed by `git log --oneline`
+pub fn short_oid(oid: Option<Oid>) -> String {
+    match oid {
+        Some(oid) => oid.to_string().chars().take(7).collect(),
+        None => "none".to_string(),
+    }
+}
+
+/// Branch tracked by the submodule, as displayed next to its commits
+fn branch_info(entry: &SubmoduleEntry) -> String {
+    match &entry.branch {
+        Some(branch) => format!("tracks branch `{branch}`"),
+        None => "tracking no branch (pinned commit)".to_string(),
+    }
+}
+
+/// Position of the staged commit relative to the upstream branch, if computed
+fn ahead_behind_info(entry: &SubmoduleEntry) -> String {
+    match &entry.ahead_behind {
+        Some(ahead_behind) => format!(
+            ", +{} / -{} commits vs {}",
+            ahead_behind.ahead, ahead_behind.behind, ahead_behind.upstream
+        ),
+        None => String::new(),
+    }
+}
+
+/// Summary of the commit checked out in the submodule, if looked up
+fn head_commit_info(entry: &SubmoduleEntry) -> String {
+    match &entry.head_commit {
+        Some(commit) => format!(" (HEAD: \"{}\" by {})", commit.summary, commit.author),
+        None => String::new(),
+    }
+}
+
+/// How many changed files are listed per submodule, the others being counted
+const MAX_CHANGED_FILES: usize = 5;
+
+/// Files changed in the submodule working directory, if looked up and any
+fn changed_files_info(entry: &SubmoduleEntry) -> Option<String> {
+    let files = entry
+        .changed_files
+        .as_ref()
+        .filter(|files| !files.is_empty())?;
+    let mut info = format!(
+        "  changed files: {}",
+        files
+            .iter()
+            .take(MAX_CHANGED_FILES)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if files.len() > MAX_CHANGED_FILES {
+        info.push_str(&format!(" and {} more", files.len() - MAX_CHANGED_FILES));
+    }
+    Some(info)
+}
+
+/// Aligned `Name Path Status Short-SHA` table of `entries`, listed in `section`, `commits`
+/// describing the commits of an entry.
+///
+/// Details (commit summary and changed files) follow the table, one line per submodule.
+fn entries_table(
+    entries: &[SubmoduleEntry],
+    section: Section,
+    commits: impl Fn(&SubmoduleEntry) -> String,
+    highlight: &dyn Fn(&str, Highlight) -> String,
+) -> Vec<String> {
+    let header = ["Name", "Path", "Status", "Short-SHA"].map(String::from);
+    let rows: Vec<[String; 4]> = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.name.clone(),
+                entry.path.clone(),
+                format!("{}{}", branch_info(entry), ahead_behind_info(entry)),
+                commits(entry),
+            ]
+        })
+        .collect();
+    let mut widths = header.each_ref().map(String::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    // padded before highlighting, styles not taking any room on screen
+    let pad = |cell: &str, width: usize| " ".repeat(width.saturating_sub(cell.chars().count()));
+    let mut lines = vec![format!(
+        "  {}",
+        header
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell}{}", pad(cell, width)))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+    )];
+    for row in &rows {
+        let [name, path, status, commits] = row;
+        lines.push(format!(
+            "  {}{}  {path}{}  {status}{}  {commits}",
+            highlight(name, Highlight::Name(section)),
+            pad(name, widths[0]),
+            pad(path, widths[1]),
+            pad(status, widths[2]),
+        ));
+    }
+    for entry in entries {
+        let head_commit = head_commit_info(entry);
+        if !head_commit.is_empty() {
+            lines.push(format!("  {}{head_commit}", entry.name));
+        }
+        if let Some(files) = changed_files_info(entry) {
+            lines.push(format!("  {} {}", entry.name, files.trim_start()));
+        }
+    }
+    lines
+}
+
+impl SubmodulesDiagnostic {
+    /// Description of the reported submodules, `highlight` styling section headers and
+    /// submodule names according to their role
+    pub fn render(&self, highlight: &dyn Fn(&str, Highlight) -> String) -> String {
+        use Highlight::{Keyword, Name, Text};
+        let mut lines = vec![];
+        if !self.modified_not_staged_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules are", Text),
+                highlight("modified but not staged", Keyword(Section::NotStaged)),
+                highlight("for commit:", Text),
+            ));
+            if let [entry] = self.modified_not_staged_submodules.as_slice() {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {}{} {} -> {}, {} (`git add {name}` to add submodule to staging)",
+                    highlight(name, Name(Section::NotStaged)),
+                    head_commit_info(entry),
+                    short_oid(entry.index_oid),
+                    short_oid(entry.workdir_head),
+                    branch_info(entry),
+                ));
+                lines.extend(changed_files_info(entry));
+            } else {
+                lines.extend(entries_table(
+                    &self.modified_not_staged_submodules,
+                    Section::NotStaged,
+                    |entry| {
+                        format!(
+                            "{} -> {}",
+                            short_oid(entry.index_oid),
+                            short_oid(entry.workdir_head)
+                        )
+                    },
+                    highlight,
+                ));
+                lines.push("(`git add <name>` to add submodules to staging)".to_string());
+            }
+        }
+        if !self.modified_staged_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules are", Text),
+                highlight("modified and staged", Keyword(Section::Staged)),
+                highlight("for commit:", Text),
+            ));
+            if let [entry] = self.modified_staged_submodules.as_slice() {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {} {} -> {}, {}{} (`git restore --staged {name}` to remove submodule from staging)",
+                    highlight(name, Name(Section::Staged)),
+                    short_oid(entry.head_oid),
+                    short_oid(entry.index_oid),
+                    branch_info(entry),
+                    ahead_behind_info(entry),
+                ));
+            } else {
+                lines.extend(entries_table(
+                    &self.modified_staged_submodules,
+                    Section::Staged,
+                    |entry| {
+                        format!(
+                            "{} -> {}",
+                            short_oid(entry.head_oid),
+                            short_oid(entry.index_oid)
+                        )
+                    },
+                    highlight,
+                ));
+                lines.push(
+                    "(`git restore --staged <name>` to remove submodules from staging)".to_string(),
+                );
+            }
+        }
+        if !self.uninitialized_submodules.is_empty() {
+            lines.push(format!(
+                "{} {}{}",
+                highlight("The following submodules are", Text),
+                highlight("not initialized", Keyword(Section::Uninitialized)),
+                highlight(":", Text),
+            ));
+            for entry in &self.uninitialized_submodules {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {}, {} (`git submodule update --init {name}` to initialize submodule)",
+                    highlight(name, Name(Section::Uninitialized)),
+                    branch_info(entry),
+                ));
+            }
+        }
+        if !self.newly_added_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules are", Text),
+                highlight("newly added", Keyword(Section::NewlyAdded)),
+                highlight("for commit:", Text),
+            ));
+            for entry in &self.newly_added_submodules {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {} at {}, {} (`git rm --cached {name}` to remove submodule from staging)",
+                    highlight(name, Name(Section::NewlyAdded)),
+                    short_oid(entry.index_oid),
+                    branch_info(entry),
+                ));
+            }
+        }
+        if !self.deleted_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules are being", Text),
+                highlight("removed", Keyword(Section::Deleted)),
+                highlight("from the index:", Text),
+            ));
+            for entry in &self.deleted_submodules {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {} was {}, {} (`git restore --staged {name}` to restore submodule in staging)",
+                    highlight(name, Name(Section::Deleted)),
+                    short_oid(entry.head_oid),
+                    branch_info(entry),
+                ));
+            }
+        }
+        if !self.uncommitted_changes_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules have", Text),
+                highlight("uncommitted changes", Keyword(Section::Uncommitted)),
+                highlight("in their working tree:", Text),
+            ));
+            for name in &self.uncommitted_changes_submodules {
+                lines.push(format!(
+                    "* {} (`git -C {name} status` to review them, then commit them in the submodule)",
+                    highlight(name, Name(Section::Uncommitted)),
+                ));
+            }
+        }
+        if !self.detached_head_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules are in", Text),
+                highlight("detached HEAD", Keyword(Section::DetachedHead)),
+                highlight("state and may not track any branch:", Text),
+            ));
+            for entry in &self.detached_head_submodules {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {} at {}, {} (`git -C {name} switch <branch>` to attach submodule to a branch)",
+                    highlight(name, Name(Section::DetachedHead)),
+                    short_oid(entry.workdir_head),
+                    branch_info(entry),
+                ));
+            }
+        }
+        if !self.unreachable_commit_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules point to a commit", Text),
+                highlight("not found on any remote", Keyword(Section::Unreachable)),
+                highlight("(others will not be able to fetch it):", Text),
+            ));
+            for entry in &self.unreachable_commit_submodules {
+                let name = &entry.name;
+                lines.push(format!(
+                    "* {} at {} (`git -C {name} push` to publish submodule commit)",
+                    highlight(name, Name(Section::Unreachable)),
+                    short_oid(entry.index_oid),
+                ));
+            }
+        }
+        if !self.unpushed_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules have", Text),
+                highlight("unpushed commits", Keyword(Section::Unpushed)),
+                highlight("(others will not be able to clone them):", Text),
+            ));
+            for name in &self.unpushed_submodules {
+                lines.push(format!(
+                    "* {} (`git -C {name} push` to push them)",
+                    highlight(name, Name(Section::Unpushed)),
+                ));
+            }
+        }
+        if !self.pinned_version_mismatch.is_empty() {
+            lines.push(format!(
+                "{} {}{}",
+                highlight("The following submodules do not match their", Text),
+                highlight("pinned commit", Keyword(Section::Pinned)),
+                highlight(":", Text),
+            ));
+            for mismatch in &self.pinned_version_mismatch {
+                let name = &mismatch.name;
+                lines.push(format!(
+                    "* {} pinned to {}, staged {} (`git config submodulehook.{name}.pin <commit>` to update the pin)",
+                    highlight(name, Name(Section::Pinned)),
+                    short_oid(Some(mismatch.pinned)),
+                    short_oid(mismatch.index_oid),
+                ));
+            }
+        }
+        if !self.missing_commit_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules have a staged commit", Text),
+                highlight(
+                    "missing from the submodule repository",
+                    Keyword(Section::MissingCommit)
+                ),
+                highlight("(garbage-collected or not fetched):", Text),
+            ));
+            for name in &self.missing_commit_submodules {
+                lines.push(format!(
+                    "* {} (`git -C {name} fetch` to fetch it, or stage another commit)",
+                    highlight(name, Name(Section::MissingCommit)),
+                ));
+            }
+        }
+        if !self.duplicate_submodules.is_empty() {
+            lines.push(format!(
+                "{} {}{}",
+                highlight("The following submodules are", Text),
+                highlight(
+                    "defined several times in `.gitmodules`",
+                    Keyword(Section::Duplicate)
+                ),
+                highlight(":", Text),
+            ));
+            for name in &self.duplicate_submodules {
+                lines.push(format!(
+                    "* {} (remove all but one `[submodule \"{name}\"]` section)",
+                    highlight(name, Name(Section::Duplicate)),
+                ));
+            }
+        }
+        if !self.path_conflict_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules have a path", Text),
+                highlight("tracked as a regular file", Keyword(Section::PathConflict)),
+                highlight("by the repository, which may be corrupted:", Text),
+            ));
+            for name in &self.path_conflict_submodules {
+                lines.push(format!(
+                    "* {} (`git rm --cached <path>` to untrack the file, or remove the submodule from `.gitmodules`)",
+                    highlight(name, Name(Section::PathConflict)),
+                ));
+            }
+        }
+        if !self.insecure_url_submodules.is_empty() {
+            lines.push(format!(
+                "{} {}{}",
+                highlight("The following submodules have a URL with a", Text),
+                highlight("disallowed scheme", Keyword(Section::InsecureUrl)),
+                highlight(":", Text),
+            ));
+            for (name, url) in &self.insecure_url_submodules {
+                lines.push(format!(
+                    "* {} {url} (`git submodule set-url <path> <url>` to change it)",
+                    highlight(name, Name(Section::InsecureUrl)),
+                ));
+            }
+        }
+        if !self.config_mismatch_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight(
+                    "The following submodules are configured in `.git/config` but",
+                    Text
+                ),
+                highlight(
+                    "missing from `.gitmodules`",
+                    Keyword(Section::ConfigMismatch)
+                ),
+                highlight(
+                    "(restore them in `.gitmodules` then `git submodule sync`, or remove them):",
+                    Text
+                ),
+            ));
+            for name in &self.config_mismatch_submodules {
+                lines.push(format!(
+                    "* {} (`git config --remove-section submodule.{name}` to remove it)",
+                    highlight(name, Name(Section::ConfigMismatch)),
+                ));
+            }
+        }
+        if !self.url_mismatch_submodules.is_empty() {
+            lines.push(format!(
+                "{} {} {}",
+                highlight("The following submodules have a URL in `.gitmodules`", Text),
+                highlight(
+                    "different from `.git/config`",
+                    Keyword(Section::UrlMismatch)
+                ),
+                highlight("(`git submodule sync` to update `.git/config`):", Text),
+            ));
+            for (name, gitmodules_url, config_url) in &self.url_mismatch_submodules {
+                lines.push(format!(
+                    "* {} {gitmodules_url} in `.gitmodules`, {config_url} in `.git/config`",
+                    highlight(name, Name(Section::UrlMismatch)),
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for SubmodulesDiagnostic {
+    /// Plain text description of the submodules, see `render` to style it
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&|text, _| text.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_submodules::{AheadBehind, CommitSummary, PinnedMismatch};
+
+    fn staged_diagnostics() -> SubmodulesDiagnostic {
+        SubmodulesDiagnostic {
+            modified_staged_submodules: vec![SubmoduleEntry {
+                name: "sub".to_string(),
+                path: "sub".to_string(),
+                head_oid: None,
+                index_oid: None,
+                workdir_head: None,
+                branch: None,
+                ahead_behind: None,
+                head_commit: None,
+                changed_files: None,
+            }],
+            ..SubmodulesDiagnostic::default()
+        }
+    }
+
+    #[test]
+    fn test_short_oid() {
+        let oid = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        assert_eq!(short_oid(Some(oid)), "0123456");
+        assert_eq!(short_oid(None), "none");
+    }
+
+    #[test]
+    fn test_branch_info() {
+        let mut entry = staged_diagnostics().modified_staged_submodules.remove(0);
+        assert_eq!(branch_info(&entry), "tracking no branch (pinned commit)");
+        entry.branch = Some("main".to_string());
+        assert_eq!(branch_info(&entry), "tracks branch `main`");
+    }
+
+    #[test]
+    fn test_ahead_behind_info() {
+        let mut entry = staged_diagnostics().modified_staged_submodules.remove(0);
+        assert_eq!(ahead_behind_info(&entry), "");
+        entry.ahead_behind = Some(AheadBehind {
+            ahead: 3,
+            behind: 0,
+            upstream: "origin/main".to_string(),
+        });
+        assert_eq!(
+            ahead_behind_info(&entry),
+            ", +3 / -0 commits vs origin/main"
+        );
+    }
+
+    #[test]
+    fn test_head_commit_info() {
+        let mut entry = staged_diagnostics().modified_staged_submodules.remove(0);
+        assert_eq!(head_commit_info(&entry), "");
+        entry.head_commit = Some(CommitSummary {
+            summary: "Fix buffer overflow".to_string(),
+            author: "Alice".to_string(),
+        });
+        assert_eq!(
+            head_commit_info(&entry),
+            " (HEAD: \"Fix buffer overflow\" by Alice)"
+        );
+    }
+
+    #[test]
+    fn test_changed_files_info() {
+        let mut entry = staged_diagnostics().modified_staged_submodules.remove(0);
+        assert_eq!(changed_files_info(&entry), None);
+        entry.changed_files = Some(vec![]);
+        assert_eq!(changed_files_info(&entry), None);
+        entry.changed_files = Some(vec!["a.rs".to_string(), "b.rs".to_string()]);
+        assert_eq!(
+            changed_files_info(&entry).unwrap(),
+            "  changed files: a.rs, b.rs"
+        );
+        entry.changed_files = Some((1..=8).map(|i| format!("{i}.rs")).collect());
+        assert_eq!(
+            changed_files_info(&entry).unwrap(),
+            "  changed files: 1.rs, 2.rs, 3.rs, 4.rs, 5.rs and 3 more"
+        );
+    }
+
+    #[test]
+    fn test_display_plain_text() {
+        let mut diagnostics = staged_diagnostics();
+        diagnostics.modified_staged_submodules[0].branch = Some("main".to_string());
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules are modified and staged for commit:\n\
+             * sub none -> none, tracks branch `main` \
+             (`git restore --staged sub` to remove submodule from staging)"
+        );
+    }
+
+    #[test]
+    fn test_display_table() {
+        let mut diagnostics = staged_diagnostics();
+        diagnostics.modified_staged_submodules[0].branch = Some("main".to_string());
+        let mut other = diagnostics.modified_staged_submodules[0].clone();
+        other.name = "library".to_string();
+        other.path = "libs/library".to_string();
+        other.branch = None;
+        other.head_commit = Some(CommitSummary {
+            summary: "Fix buffer overflow".to_string(),
+            author: "Alice".to_string(),
+        });
+        diagnostics.modified_staged_submodules.push(other);
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules are modified and staged for commit:\n  \
+             Name     Path          Status                              Short-SHA\n  \
+             sub      sub           tracks branch `main`                none -> none\n  \
+             library  libs/library  tracking no branch (pinned commit)  none -> none\n  \
+             library (HEAD: \"Fix buffer overflow\" by Alice)\n\
+             (`git restore --staged <name>` to remove submodules from staging)"
+        );
+    }
+
+    #[test]
+    fn test_display_pinned_mismatch() {
+        let oid = Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let diagnostics = SubmodulesDiagnostic {
+            pinned_version_mismatch: vec![PinnedMismatch {
+                name: "sub".to_string(),
+                pinned: oid,
+                index_oid: None,
+            }],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules do not match their pinned commit:\n\
+             * sub pinned to 0123456, staged none \
+             (`git config submodulehook.sub.pin <commit>` to update the pin)"
+        );
+    }
+
+    #[test]
+    fn test_display_missing_commit() {
+        let diagnostics = SubmodulesDiagnostic {
+            missing_commit_submodules: vec!["sub".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules have a staged commit missing from the submodule repository \
+             (garbage-collected or not fetched):\n\
+             * sub (`git -C sub fetch` to fetch it, or stage another commit)"
+        );
+    }
+
+    #[test]
+    fn test_display_duplicate() {
+        let diagnostics = SubmodulesDiagnostic {
+            duplicate_submodules: vec!["sub".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules are defined several times in `.gitmodules`:\n\
+             * sub (remove all but one `[submodule \"sub\"]` section)"
+        );
+    }
+
+    #[test]
+    fn test_display_path_conflict() {
+        let diagnostics = SubmodulesDiagnostic {
+            path_conflict_submodules: vec!["sub".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules have a path tracked as a regular file by the repository, \
+             which may be corrupted:\n\
+             * sub (`git rm --cached <path>` to untrack the file, or remove the submodule from `.gitmodules`)"
+        );
+    }
+
+    #[test]
+    fn test_display_unpushed() {
+        let diagnostics = SubmodulesDiagnostic {
+            unpushed_submodules: vec!["sub".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules have unpushed commits (others will not be able to clone them):\n\
+             * sub (`git -C sub push` to push them)"
+        );
+    }
+
+    #[test]
+    fn test_display_uncommitted_changes() {
+        let diagnostics = SubmodulesDiagnostic {
+            uncommitted_changes_submodules: vec!["sub".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules have uncommitted changes in their working tree:\n\
+             * sub (`git -C sub status` to review them, then commit them in the submodule)"
+        );
+    }
+
+    #[test]
+    fn test_display_insecure_url() {
+        let diagnostics = SubmodulesDiagnostic {
+            insecure_url_submodules: vec![(
+                "sub".to_string(),
+                "http://example.com/sub".to_string(),
+            )],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules have a URL with a disallowed scheme:\n\
+             * sub http://example.com/sub (`git submodule set-url <path> <url>` to change it)"
+        );
+    }
+
+    #[test]
+    fn test_display_config_mismatch() {
+        let diagnostics = SubmodulesDiagnostic {
+            config_mismatch_submodules: vec!["old".to_string()],
+            ..SubmodulesDiagnostic::default()
+        };
+        assert_eq!(
+            diagnostics.to_string(),
+            "The following submodules are configured in `.git/config` but missing from `.gitmodules` \
+             (restore them in `.gitmodules` then `git submodule sync`, or remove them):\n\
+             * old (`git config --remove-section submodule.old` to remove it)"
+        );
+    }
+
+    #[test]
+    fn test_render_highlight() {
+        let rendered = staged_diagnostics().render(&|text, highlight| match highlight {
+            Highlight::Text => text.to_string(),
+            Highlight::Keyword(section) | Highlight::Name(section) => {
+                assert_eq!(section, Section::Staged);
+                format!("[{text}]")
+            }
+        });
+        assert!(rendered.starts_with("The following submodules are [modified and staged]"));
+        assert!(rendered.contains("* [sub] none -> none"));
+    }
+}
@@ -0,0 +1,33 @@
+//! Submodule checks and configuration of [submodule-hook](https://github.com/PicoJr/submodule-hook),
+//! for tools (IDE plugins, CI utilities...) that want to report the same diagnostic as the hook
+//! without its interactive prompt.
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! let config = submodule_hook_lib::get_config(Path::new("."));
+//! let diagnostic = submodule_hook_lib::check_submodules(
+//!     config.strict.unwrap_or(false),
+//!     Path::new("."),
+//!     &config.allow_list,
+//!     &config.ignore_policies,
+//!     git2::SubmoduleIgnore::None,
+//!     false,
+//! )?;
+//! if let Some(diagnostic) = diagnostic {
+//!     println!("clean: {}", diagnostic.is_clean());
+//! }
+//! # Ok::<(), submodule_hook_lib::check_submodules::CheckError>(())
+//! ```
+//!
+//! The crate requires `std`: it relies on `git2` (libgit2) and on the file system.
+
+mod cache;
+pub mod check_submodules;
+pub mod config;
+pub mod display;
+#[cfg(test)]
+mod test_repos;
+
+pub use check_submodules::{SubmodulesDiagnostic, check_submodules, open_repository};
+pub use config::{HookConfig, get_config};
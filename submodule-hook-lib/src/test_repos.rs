@@ -0,0 +1,146 @@
+//! Repositories built by the tests and the benchmarks (`benches/helpers.rs` includes this file).
+
+use git2::{IndexAddOption, Oid, Repository, Signature};
+use std::fs;
+use tempfile::TempDir;
+
+/// Creates a temporary git repository with an initial commit
+/// Returns (TempDir, Repository) - TempDir must be kept alive for the repository to remain valid
+pub fn create_temp_repo() -> anyhow::Result<(TempDir, Repository)> {
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path().to_path_buf();
+    let repo = Repository::init(&temp_path)?;
+
+    // Create initial commit (required for submodule operations)
+    let sig = Signature::now("Test User", "test@example.com")?;
+    let tree_id = {
+        let mut index = repo.index()?;
+        // Create a README file
+        let readme_path = temp_path.join("README.md");
+        fs::write(&readme_path, "# Test Repository\n")?;
+        index.add_path(std::path::Path::new("README.md"))?;
+        index.write()?;
+        index.write_tree()?
+    };
+    let tree = repo.find_tree(tree_id)?;
+    repo.commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])?;
+    drop(tree); // Drop tree to release the borrow on repo
+
+    Ok((temp_dir, repo))
+}
+
+/// Creates a commit in the given repository with a new file
+pub fn create_commit(repo: &Repository, message: &str) -> anyhow::Result<Oid> {
+    let sig = Signature::now("Test User", "test@example.com")?;
+
+    // Create a unique file for this commit
+    let file_name = format!("file_{}.txt", message.replace(' ', "_"));
+    let file_path = repo.workdir().unwrap().join(&file_name);
+    fs::write(&file_path, format!("Content for {}", message))?;
+
+    // Stage the file
+    let mut index = repo.index()?;
+    index.add_path(std::path::Path::new(&file_name))?;
+    index.write()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+
+    // Get parent commit
+    let parent_commit = repo.head()?.peel_to_commit()?;
+
+    // Create commit
+    let oid = repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[&parent_commit])?;
+
+    Ok(oid)
+}
+
+/// Adds a submodule to the parent repository and stages it without committing
+/// Returns (TempDir, submodule_name) - TempDir must be kept alive
+pub fn stage_new_submodule(
+    parent_repo: &Repository,
+    submodule_name: &str,
+) -> anyhow::Result<(TempDir, String)> {
+    // Create a separate repository for the submodule
+    let (submodule_temp_dir, submodule_repo) = create_temp_repo()?;
+    let submodule_path = submodule_temp_dir.path().to_path_buf();
+    let submodule_url = format!("file://{}", submodule_path.display());
+
+    // Add submodule to parent repository
+    let mut submodule =
+        parent_repo.submodule(&submodule_url, std::path::Path::new(submodule_name), false)?;
+
+    // Clone the submodule repository
+    let _cloned_repo = submodule.clone(None)?;
+
+    // Finalize the submodule addition
+    submodule.add_finalize()?;
+
+    // Stage the submodule addition
+    let mut index = parent_repo.index()?;
+    index.add_all(
+        [".gitmodules", submodule_name].iter(),
+        IndexAddOption::DEFAULT,
+        None,
+    )?;
+    index.write()?;
+
+    // Keep the submodule repo alive by storing it
+    drop(submodule_repo);
+
+    Ok((submodule_temp_dir, submodule_name.to_string()))
+}
+
+/// Adds a submodule to the parent repository
+/// Returns (TempDir, submodule_name) - TempDir must be kept alive
+pub fn add_submodule(
+    parent_repo: &Repository,
+    submodule_name: &str,
+) -> anyhow::Result<(TempDir, String)> {
+    let (submodule_temp_dir, submodule_name) = stage_new_submodule(parent_repo, submodule_name)?;
+
+    // Commit the submodule addition
+    let sig = Signature::now("Test User", "test@example.com")?;
+    let tree_id = parent_repo.index()?.write_tree()?;
+    let tree = parent_repo.find_tree(tree_id)?;
+    let parent_commit = parent_repo.head()?.peel_to_commit()?;
+    parent_repo.commit(
+        Some("HEAD"),
+        &sig,
+        &sig,
+        &format!("Add submodule {}", submodule_name),
+        &tree,
+        &[&parent_commit],
+    )?;
+    drop(tree); // Drop tree to release the borrow
+
+    Ok((submodule_temp_dir, submodule_name))
+}
+
+/// Modifies the submodule's working directory by creating a commit
+/// This makes the submodule modified but NOT staged in the parent
+pub fn modify_submodule_wd(parent_repo: &Repository, submodule_name: &str) -> anyhow::Result<()> {
+    let submodule_path = parent_repo.workdir().unwrap().join(submodule_name);
+    let submodule_repo = Repository::open(&submodule_path)?;
+
+    // Create a new commit in the submodule
+    create_commit(&submodule_repo, "Submodule modification")?;
+
+    Ok(())
+}
+
+/// Stages the submodule changes in the parent repository's index
+pub fn stage_submodule(parent_repo: &Repository, submodule_name: &str) -> anyhow::Result<()> {
+    let mut index = parent_repo.index()?;
+    index.add_path(std::path::Path::new(submodule_name))?;
+    index.write()?;
+    Ok(())
+}
+
+/// Empties the submodule's working directory, as if it was never initialized
+/// This makes the submodule uninitialized in the parent
+pub fn deinit_submodule_wd(parent_repo: &Repository, submodule_name: &str) -> anyhow::Result<()> {
+    let submodule_path = parent_repo.workdir().unwrap().join(submodule_name);
+    fs::remove_dir_all(&submodule_path)?;
+    fs::create_dir(&submodule_path)?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Priority of the configuration sources as resolved by the binary: CLI over local git config
+//! over global git config.
+
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Write `submodulehook.strict` to the git config file at `path`
+fn set_strict(path: &Path, strict: bool) {
+    git2::Config::open(path)
+        .unwrap()
+        .set_bool("submodulehook.strict", strict)
+        .unwrap();
+}
+
+/// Resolved `strict` value and its source, as printed by `config --show`, `home` being used as
+/// the home directory
+fn resolved_strict(repo: &Path, home: &Path, cli_args: &[&str]) -> (String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_submodule-hook"))
+        .arg("--repo")
+        .arg(repo)
+        .args(cli_args)
+        .args(["config", "--show"])
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env_remove("SUBMODULE_HOOK_STRICT")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let row: Vec<&str> = stdout
+        .lines()
+        .find(|line| line.starts_with("strict "))
+        .unwrap()
+        .split_whitespace()
+        .collect();
+    match row[..] {
+        ["strict", "=", value, source] => (value.to_string(), source.to_string()),
+        _ => panic!("unexpected row {row:?}"),
+    }
+}
+
+#[test]
+fn test_config_priority() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path().join("home");
+    std::fs::create_dir(&home).unwrap();
+    let repo_path = temp_dir.path().join("repo");
+    let repo = git2::Repository::init(&repo_path).unwrap();
+    let strict = |cli_args: &[&str]| resolved_strict(&repo_path, &home, cli_args);
+    let expected = |value: &str, source: &str| (value.to_string(), source.to_string());
+
+    // global is used when local is absent
+    set_strict(&home.join(".gitconfig"), true);
+    assert_eq!(strict(&[]), expected("true", "global"));
+
+    // local overrides global
+    set_strict(&home.join(".gitconfig"), false);
+    set_strict(&repo.path().join("config"), true);
+    assert_eq!(strict(&[]), expected("true", "local"));
+
+    // CLI overrides local and global
+    assert_eq!(strict(&["--strict", "false"]), expected("false", "cli"));
+}
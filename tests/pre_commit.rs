@@ -0,0 +1,118 @@
+//! End-to-end test of the pre-commit hook: installed with `submodule-hook install`, then run by
+//! `git commit` itself.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use tempfile::TempDir;
+
+/// `git` run in `dir`, isolated from the user and system configuration (`home` being used as
+/// the home directory)
+fn git(dir: &Path, home: &Path) -> Command {
+    let mut command = Command::new("git");
+    command
+        .current_dir(dir)
+        .env("HOME", home)
+        .env("XDG_CONFIG_HOME", home.join(".config"))
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env_remove("CI");
+    command
+}
+
+fn run_git(dir: &Path, home: &Path, args: &[&str]) {
+    let status = git(dir, home).args(args).status().unwrap();
+    assert!(status.success(), "git {args:?} failed in {}", dir.display());
+}
+
+/// Repository at `path` with a single commit
+fn init_repo(path: &Path, home: &Path) {
+    fs::create_dir_all(path).unwrap();
+    run_git(path, home, &["init", "--quiet"]);
+    fs::write(path.join("README.md"), "# Test Repository\n").unwrap();
+    run_git(path, home, &["add", "README.md"]);
+    run_git(path, home, &["commit", "--quiet", "-m", "Initial commit"]);
+}
+
+fn head(dir: &Path, home: &Path) -> String {
+    let output = git(dir, home).args(["rev-parse", "HEAD"]).output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// `git commit` of a change to `README.md`, with the hook answering without prompting
+fn commit(parent: &Path, home: &Path, strict: bool) -> ExitStatus {
+    fs::write(parent.join("README.md"), format!("# Strict: {strict}\n")).unwrap();
+    run_git(parent, home, &["add", "README.md"]);
+    git(parent, home)
+        .args(["commit", "--quiet", "-m", "Update README"])
+        .env("GIT_EDITOR", "true")
+        .env("SUBMODULE_HOOK_NON_INTERACTIVE", "true")
+        .env("SUBMODULE_HOOK_STRICT", strict.to_string())
+        .status()
+        .unwrap()
+}
+
+#[test]
+fn test_pre_commit_hook() {
+    let temp_dir = TempDir::new().unwrap();
+    let home = temp_dir.path().join("home");
+    fs::create_dir(&home).unwrap();
+    let origin = temp_dir.path().join("origin");
+    let parent = temp_dir.path().join("parent");
+    init_repo(&origin, &home);
+    init_repo(&parent, &home);
+    run_git(
+        &parent,
+        &home,
+        &[
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            "--quiet",
+            origin.to_str().unwrap(),
+            "sub",
+        ],
+    );
+    run_git(
+        &parent,
+        &home,
+        &["commit", "--quiet", "-m", "Add submodule"],
+    );
+
+    let install = Command::new(env!("CARGO_BIN_EXE_submodule-hook"))
+        .arg("--repo")
+        .arg(&parent)
+        .arg("install")
+        .status()
+        .unwrap();
+    assert!(install.success());
+    assert!(parent.join(".git/hooks/pre-commit").exists());
+
+    // new commit checked out in the submodule, not staged in the parent repository
+    let sub = parent.join("sub");
+    fs::write(sub.join("change.txt"), "change\n").unwrap();
+    run_git(&sub, &home, &["add", "change.txt"]);
+    run_git(&sub, &home, &["commit", "--quiet", "-m", "Change"]);
+
+    let before = head(&parent, &home);
+    let status = commit(&parent, &home, true);
+    // git exits with 1 whatever the non-zero exit code of the hook
+    assert_eq!(
+        status.code(),
+        Some(1),
+        "strict mode should block the commit"
+    );
+    assert_eq!(head(&parent, &home), before);
+
+    let status = commit(&parent, &home, false);
+    assert_eq!(
+        status.code(),
+        Some(0),
+        "non-strict mode should allow the commit"
+    );
+    assert_ne!(head(&parent, &home), before);
+}